@@ -69,3 +69,222 @@ short_registration = Anmeldung
 edit_participant = Teilnehmer bearbeiten
 short_edit_participant = Teilnehmer bearbeiten
 short_add_participant = Teilnehmer hinzufügen
+
+race = Strecke
+time_records = Zielzeiten
+finish_time = Zielzeit
+net_time = Nettozeit
+record_finish_time = Zielzeit erfassen
+
+bib = Startnummer
+first_bib = Erste Startnummer
+last_bib = Letzte Startnummer
+bib_range = Startnummernbereich
+
+results = Ergebnisse
+place = Platz
+category_place = Platz AK
+gender_place = Platz M/W
+time = Zeit
+gap = Rückstand
+no_results_yet = Noch keine Ergebnisse
+
+status = Status
+all = Alle
+filter = Filtern
+reason = Grund
+status_registered = Angemeldet
+status_checked_in = Eingecheckt
+status_started = Gestartet
+status_finished = Im Ziel
+status_dnf = Aufgegeben
+status_dns = Nicht angetreten
+status_dsq = Disqualifiziert
+non_finishers = Nicht gewertet
+timing_points = Zwischenzeitpunkte
+timing_point = Zwischenzeitpunkt
+new_timing_point = Zwischenzeitpunkt hinzufügen
+position = Position
+split_times = Zwischenzeiten
+record_split_time = Zwischenzeit erfassen
+finish = Ziel
+stopwatch = Stoppuhr
+finish_order = Zieleinlauf
+finish_merge = Zeiten und Zieleinlauf zusammenführen
+stop_now = Stopp
+add = Hinzufügen
+insert_time = Zeit einfügen
+insert_bib = Startnummer einfügen
+missing = fehlt
+unknown_bib = Unbekannte Startnummer
+apply_merge = Zielzeiten übernehmen
+chips = Chips
+chip = Chip
+chip_reads = Chip-Lesungen
+import_chip_reads = Export des Lesegeräts importieren
+file = Datei
+dedup_window = Wiederholte Lesungen ignorieren innerhalb von (Sekunden)
+import = Importieren
+assign_chip = Chip zuweisen
+unassigned_chips = Nicht zugewiesene Chips
+last_import = Letzter Import
+stored_reads = Gespeicherte Lesungen
+duplicate_reads = Ignorierte wiederholte Lesungen
+rejected_finish_reads = Ignorierte Ziellesungen direkt nach dem Start
+gun_time = Bruttozeit
+set_gun_time = Startschuss setzen
+start_now = Jetzt starten
+ranking_mode = Wertung
+ranking_mode_gun = Nach Bruttozeit
+ranking_mode_net = Nach Nettozeit
+team_size = Teamgröße
+teams = Staffeln
+team_name = Staffelname
+team_category = Staffelwertung
+team_categories = Staffelwertungen
+new_team_category = Staffelwertung hinzufügen
+team_members = Staffelmitglieder
+team_composition = Zusammensetzung
+team_composition_male = Männer
+team_composition_female = Frauen
+team_composition_mixed = Mixed
+team_composition_any = Beliebig
+from_combined_age = Gesamtalter von
+to_combined_age = Gesamtalter bis
+label = Bezeichnung
+leg = Abschnitt
+not_classified = Nicht gewertet
+team_registration = Staffelanmeldung zum {$competition}
+short_team_registration = Staffelanmeldung
+to_team_registration = Zur Staffelanmeldung
+clubs = Vereine
+new_club = Verein hinzufügen
+club_aliases = Schreibweisen
+add_club_alias = Schreibweise hinzufügen
+unmatched_clubs = Nicht zugeordnete Vereinsnamen
+assign_club = Verein zuordnen
+club_results = Vereinswertung
+club_members = Gewertete Finisher
+finishers = Finisher
+score = Wertung
+club_scoring = Vereinswertung
+club_scoring_none = Keine Vereinswertung
+club_scoring_time_sum = Summe der besten Zeiten
+club_scoring_place_sum = Summe der besten Platzierungen
+counting_finishers = Gewertete Finisher
+series = Serien
+new_series = Serie hinzufügen
+series_standings = Serienwertung
+points_table = Punktetabelle
+counting_results = Gewertete Ergebnisse
+points = Punkte
+competition = Wettkampf
+email = E-Mail
+persons = Personen
+participations = Teilnahmen
+unlinked_participants = Anmeldungen ohne Person
+link_participants = Mit Personen verknüpfen
+history = Historie
+search = Suchen
+split_person = Abtrennen
+merge_person = Zusammenführen mit
+distance_meters = Distanz (m)
+age_graded_time = Altersbereinigte Zeit
+age_grade = Alterswertung
+age_graded_ranking = Altersbereinigte Wertung
+age_grading_source = Quelle der Alterswertungsfaktoren
+elevation_gain_meters = Höhenmeter
+surface = Untergrund
+course_description = Streckenbeschreibung
+pace = Pace
+speed = Geschwindigkeit
+time_adjustments = Zeitkorrekturen
+time_adjustment = Korrektur
+time_adjusted = korrigiert
+add_time_adjustment = Zeitkorrektur hinzufügen
+seconds = Sekunden
+created_at = Erstellt am
+created_by = Erstellt von
+result_versions = Ergebnisversionen
+version = Version
+published_at = Veröffentlicht am
+published_by = Veröffentlicht von
+version_note = Grund für die neue Version
+changes = Änderungen
+previous_result = Bisheriges Ergebnis
+new_result = Neues Ergebnis
+publish_results = Ergebnisse veröffentlichen
+unpublished_changes = Unveröffentlichte Änderungen
+provisional_results = Vorläufige Ergebnisse
+official_results = Offizielle Ergebnisse
+superseded_results = Veraltete Ergebnisse
+request_correction = Korrektur beantragen
+correction_requests = Korrekturanträge
+open_correction_requests = Offene Korrekturanträge
+handled_correction_requests = Bearbeitete Korrekturanträge
+correction_kind = Korrektur
+correction_kind_time = Falsche Zeit
+correction_kind_category = Falsche Altersklasse
+correction_kind_other = Sonstiges
+correction_status_open = Offen
+correction_status_accepted = Angenommen
+correction_status_rejected = Abgelehnt
+requested_time = Richtige Zeit
+requested_category = Richtige Altersklasse
+contact = E-Mail oder Telefon
+comment = Kommentar
+accept = Annehmen
+reject = Ablehnen
+decision = Entscheidung
+handled_by = Bearbeitet von
+award_list = Siegerliste
+award_settings = Einstellungen der Siegerehrung
+awarded_places = Geehrte Plätze pro Altersklasse
+min_finishers = Altersklassen zusammenlegen mit weniger Finishern als
+ceremony_order = Reihenfolge der Siegerehrung
+export_csv = Als CSV exportieren
+export_pdf = Als PDF exportieren
+mail_registration_subject = Anmeldung zu {$competition}
+mail_greeting = Hallo
+mail_registration_confirmed = Vielen Dank für deine Anmeldung. Wir haben dich mit folgenden Daten angemeldet:
+mail_closing = Wir sehen uns am Start!
+edit_registration = Deine Anmeldung zu {$competition}
+short_edit_registration = Deine Anmeldung
+edit_registration_link = Anmeldung ändern
+cancel_registration = Anmeldung stornieren
+cancel_registration_question = Möchtest du deine Anmeldung wirklich stornieren? Das kann nicht rückgängig gemacht werden.
+mail_edit_registration = Hier kannst du deine Anmeldung ändern:
+mail_cancel_registration = Falls du nicht teilnehmen kannst, storniere bitte hier deine Anmeldung:
+registration_opens = Anmeldung ab
+registration_closes = Anmeldeschluss
+registration_not_open_yet = Die Anmeldung ist noch nicht geöffnet.
+registration_closed = Die Anmeldung ist geschlossen.
+capacity = Teilnehmerlimit
+remaining_spots = Freie Plätze
+race_full_waitlist = Dieser Lauf ist ausgebucht, neue Anmeldungen kommen auf die Warteliste.
+waitlist = Warteliste
+mail_waitlisted = Der Lauf ist derzeit ausgebucht, daher stehst du auf der Warteliste. Sobald ein Platz frei wird, rückst du automatisch nach.
+duplicates = Mögliche Doppelanmeldungen
+merge_duplicate = Mit erster Anmeldung zusammenführen
+no_duplicates = Keine möglichen Doppelanmeldungen.
+duplicate_registration = {$first_name} {$last_name} ({$birth_year}) ist bereits für diesen Wettkampf angemeldet.
+entry_fees = Startgebühren
+base_fee = Startgebühr
+late_surcharge = Nachmeldegebühr
+late_from = Nachmeldung ab
+youth_discount = Jugendrabatt
+youth_max_age = Jugendrabatt bis Alter
+club_discount = Vereinsrabatt
+payments = Zahlungen
+payment_status = Zahlungsstatus
+payment_open = Offen
+payment_paid = Bezahlt
+payment_refunded = Erstattet
+payment_method = Zahlungsart
+missing_fees = Anmeldungen ohne Startgebühr
+assign_missing_fees = Startgebühren berechnen
+payment_reference = Verwendungszweck
+paid_at = Bezahlt am
+amount_due = Zu zahlen
+currency = EUR
+registration_confirmation = Anmeldebestätigung
@@ -69,3 +69,222 @@ short_registration = Registration
 edit_participant = Edit Participant
 short_edit_participant = Edit Participant
 short_add_participant = Add Participant
+
+race = Race
+time_records = Finish Times
+finish_time = Finish time
+net_time = Net time
+record_finish_time = Record finish time
+
+bib = Bib
+first_bib = First bib
+last_bib = Last bib
+bib_range = Bib range
+
+results = Results
+place = Place
+category_place = Category place
+gender_place = Gender place
+time = Time
+gap = Gap
+no_results_yet = No results yet
+
+status = Status
+all = All
+filter = Filter
+reason = Reason
+status_registered = Registered
+status_checked_in = Checked in
+status_started = Started
+status_finished = Finished
+status_dnf = DNF
+status_dns = DNS
+status_dsq = DSQ
+non_finishers = Not classified
+timing_points = Timing Points
+timing_point = Timing Point
+new_timing_point = Add Timing Point
+position = Position
+split_times = Split Times
+record_split_time = Record Split Time
+finish = Finish
+stopwatch = Stopwatch
+finish_order = Finish Order
+finish_merge = Merge Times and Finish Order
+stop_now = Stop
+add = Add
+insert_time = Insert Time
+insert_bib = Insert Bib
+missing = missing
+unknown_bib = Unknown bib
+apply_merge = Record Finish Times
+chips = Chips
+chip = Chip
+chip_reads = Chip Reads
+import_chip_reads = Import Reader Export
+file = File
+dedup_window = Ignore repeated reads within (seconds)
+import = Import
+assign_chip = Assign Chip
+unassigned_chips = Unassigned Chips
+last_import = Last Import
+stored_reads = Stored reads
+duplicate_reads = Ignored repeated reads
+rejected_finish_reads = Ignored finish reads right after the start
+gun_time = Gun time
+set_gun_time = Set Gun Time
+start_now = Start now
+ranking_mode = Ranking
+ranking_mode_gun = By gun time
+ranking_mode_net = By net time
+team_size = Team size
+teams = Teams
+team_name = Team name
+team_category = Team category
+team_categories = Team Categories
+new_team_category = Add Team Category
+team_members = Team members
+team_composition = Composition
+team_composition_male = Male
+team_composition_female = Female
+team_composition_mixed = Mixed
+team_composition_any = Any
+from_combined_age = Combined age from
+to_combined_age = Combined age to
+label = Label
+leg = Leg
+not_classified = Not classified
+team_registration = Team registration to {$competition}
+short_team_registration = Team registration
+to_team_registration = To Team Registration
+clubs = Clubs
+new_club = Add Club
+club_aliases = Spellings
+add_club_alias = Add Spelling
+unmatched_clubs = Unmatched Club Names
+assign_club = Assign to Club
+club_results = Club Results
+club_members = Counting finishers
+finishers = Finishers
+score = Score
+club_scoring = Club scoring
+club_scoring_none = No club scoring
+club_scoring_time_sum = Sum of the best times
+club_scoring_place_sum = Sum of the best places
+counting_finishers = Counting finishers
+series = Series
+new_series = Add Series
+series_standings = Series Standings
+points_table = Points table
+counting_results = Counting results
+points = Points
+competition = Competition
+email = Email
+persons = Persons
+participations = Participations
+unlinked_participants = Registrations without a person
+link_participants = Link to persons
+history = History
+search = Search
+split_person = Split off
+merge_person = Merge with
+distance_meters = Distance (m)
+age_graded_time = Age graded time
+age_grade = Age grade
+age_graded_ranking = Age graded ranking
+age_grading_source = Source of the age grading factors
+elevation_gain_meters = Elevation gain (m)
+surface = Surface
+course_description = Course description
+pace = Pace
+speed = Speed
+time_adjustments = Time adjustments
+time_adjustment = Adjustment
+time_adjusted = adjusted
+add_time_adjustment = Add time adjustment
+seconds = seconds
+created_at = Created at
+created_by = Created by
+result_versions = Result versions
+version = Version
+published_at = Published at
+published_by = Published by
+version_note = Reason for the new version
+changes = Changes
+previous_result = Previous result
+new_result = New result
+publish_results = Publish results
+unpublished_changes = Unpublished changes
+provisional_results = Provisional results
+official_results = Official results
+superseded_results = Outdated results
+request_correction = Request correction
+correction_requests = Correction requests
+open_correction_requests = Open correction requests
+handled_correction_requests = Handled correction requests
+correction_kind = Correction
+correction_kind_time = Wrong time
+correction_kind_category = Wrong category
+correction_kind_other = Other
+correction_status_open = Open
+correction_status_accepted = Accepted
+correction_status_rejected = Rejected
+requested_time = Correct time
+requested_category = Correct category
+contact = Email or phone
+comment = Comment
+accept = Accept
+reject = Reject
+decision = Decision
+handled_by = Handled by
+award_list = Award list
+award_settings = Award settings
+awarded_places = Awarded places per category
+min_finishers = Merge categories with fewer finishers than
+ceremony_order = Ceremony order
+export_csv = Export as CSV
+export_pdf = Export as PDF
+mail_registration_subject = Registration for {$competition}
+mail_greeting = Hello
+mail_registration_confirmed = Thank you for your registration. We have registered you with the following data:
+mail_closing = See you at the start!
+edit_registration = Your registration to {$competition}
+short_edit_registration = Your registration
+edit_registration_link = Edit registration
+cancel_registration = Cancel registration
+cancel_registration_question = Do you really want to cancel your registration? This cannot be undone.
+mail_edit_registration = You can change your registration here:
+mail_cancel_registration = If you cannot participate, please cancel your registration here:
+registration_opens = Registration opens
+registration_closes = Registration closes
+registration_not_open_yet = The registration is not open yet.
+registration_closed = The registration is closed.
+capacity = Capacity
+remaining_spots = Remaining spots
+race_full_waitlist = This race is full, new registrations are put on the waitlist.
+waitlist = waitlist
+mail_waitlisted = The race is currently full, so you are on the waitlist. You will move up automatically as soon as a spot becomes free.
+duplicates = Suspected duplicates
+merge_duplicate = Merge into first registration
+no_duplicates = No suspected duplicates.
+duplicate_registration = {$first_name} {$last_name} ({$birth_year}) is already registered for this competition.
+entry_fees = Entry fees
+base_fee = Entry fee
+late_surcharge = Late registration surcharge
+late_from = Late registration from
+youth_discount = Youth discount
+youth_max_age = Youth discount up to age
+club_discount = Club member discount
+payments = Payments
+payment_status = Payment status
+payment_open = Open
+payment_paid = Paid
+payment_refunded = Refunded
+payment_method = Payment method
+missing_fees = Registrations without an entry fee
+assign_missing_fees = Compute entry fees
+payment_reference = Reference
+paid_at = Paid at
+amount_due = Amount due
+currency = EUR
+registration_confirmation = Registration confirmation
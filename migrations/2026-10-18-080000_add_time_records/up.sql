@@ -0,0 +1,5 @@
+CREATE TABLE `time_records`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`participant_id` INTEGER NOT NULL UNIQUE REFERENCES participants(id) ON DELETE CASCADE,
+	`finish_time` TIMESTAMP NOT NULL
+);
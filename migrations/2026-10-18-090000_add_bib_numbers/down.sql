@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS `bib_numbers`;
+ALTER TABLE `starts` DROP COLUMN `last_bib`;
+ALTER TABLE `starts` DROP COLUMN `first_bib`;
@@ -0,0 +1,9 @@
+ALTER TABLE `starts` ADD COLUMN `first_bib` INTEGER;
+ALTER TABLE `starts` ADD COLUMN `last_bib` INTEGER;
+
+CREATE TABLE `bib_numbers`(
+	`participant_id` INTEGER NOT NULL PRIMARY KEY REFERENCES participants(id) ON DELETE CASCADE,
+	`competition_id` INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+	`bib` INTEGER NOT NULL,
+	UNIQUE(`competition_id`, `bib`)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE `participants` DROP COLUMN `status_reason`;
+ALTER TABLE `participants` DROP COLUMN `status`;
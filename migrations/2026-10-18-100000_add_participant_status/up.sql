@@ -0,0 +1,2 @@
+ALTER TABLE `participants` ADD COLUMN `status` TEXT NOT NULL DEFAULT 'registered';
+ALTER TABLE `participants` ADD COLUMN `status_reason` TEXT;
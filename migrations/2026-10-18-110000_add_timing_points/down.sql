@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS `split_times`;
+DROP TABLE IF EXISTS `timing_points`;
@@ -0,0 +1,14 @@
+CREATE TABLE `timing_points`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`race_id` INTEGER NOT NULL REFERENCES races(id) ON DELETE CASCADE,
+	`name` TEXT NOT NULL,
+	`position` INTEGER NOT NULL,
+	UNIQUE(`race_id`, `position`)
+);
+
+CREATE TABLE `split_times`(
+	`participant_id` INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+	`timing_point_id` INTEGER NOT NULL REFERENCES timing_points(id) ON DELETE CASCADE,
+	`time` TIMESTAMP NOT NULL,
+	PRIMARY KEY(`participant_id`, `timing_point_id`)
+);
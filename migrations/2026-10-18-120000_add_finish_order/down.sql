@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS `finish_order_bibs`;
+DROP TABLE IF EXISTS `stopwatch_times`;
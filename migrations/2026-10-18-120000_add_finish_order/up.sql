@@ -0,0 +1,18 @@
+-- times recorded with a stopwatch, in the order the participants finished
+CREATE TABLE `stopwatch_times`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`competition_id` INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+	`position` INTEGER NOT NULL,
+	`time` TIMESTAMP NOT NULL
+);
+
+-- bibs written down in the order the participants finished
+CREATE TABLE `finish_order_bibs`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`competition_id` INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+	`position` INTEGER NOT NULL,
+	`bib` INTEGER NOT NULL
+);
+
+CREATE INDEX `stopwatch_times_competition` ON `stopwatch_times`(`competition_id`, `position`);
+CREATE INDEX `finish_order_bibs_competition` ON `finish_order_bibs`(`competition_id`, `position`);
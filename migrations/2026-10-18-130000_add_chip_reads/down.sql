@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS `chip_reads`;
+DROP TABLE IF EXISTS `chips`;
@@ -0,0 +1,18 @@
+-- timing chips handed out to participants
+CREATE TABLE `chips`(
+	`competition_id` INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+	`chip` TEXT NOT NULL,
+	`participant_id` INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+	PRIMARY KEY(`competition_id`, `chip`)
+);
+
+-- raw reads of timing readers, including reads of unassigned chips
+CREATE TABLE `chip_reads`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`competition_id` INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+	`chip` TEXT NOT NULL,
+	`time` TIMESTAMP NOT NULL,
+	`reader` TEXT NOT NULL
+);
+
+CREATE INDEX `chip_reads_chip` ON `chip_reads`(`competition_id`, `chip`, `reader`, `time`);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS `start_reads`;
+ALTER TABLE `races` DROP COLUMN `ranking_mode`;
+ALTER TABLE `starts` DROP COLUMN `gun_time`;
@@ -0,0 +1,8 @@
+ALTER TABLE `starts` ADD COLUMN `gun_time` TIMESTAMP;
+ALTER TABLE `races` ADD COLUMN `ranking_mode` TEXT NOT NULL DEFAULT 'net';
+
+-- individual start times, recorded by a start mat
+CREATE TABLE `start_reads`(
+	`participant_id` INTEGER NOT NULL PRIMARY KEY REFERENCES participants(id) ON DELETE CASCADE,
+	`time` TIMESTAMP NOT NULL
+);
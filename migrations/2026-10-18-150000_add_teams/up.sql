@@ -0,0 +1,27 @@
+-- number of runners per team, only set for relay races
+ALTER TABLE `races` ADD COLUMN `team_size` INTEGER;
+
+-- category rules for teams, the counterpart of `categories` for relay races
+CREATE TABLE `team_categories`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`label` TEXT NOT NULL,
+	-- one of 'male', 'female', 'mixed' or 'any'
+	`composition` TEXT NOT NULL DEFAULT 'any',
+	`from_combined_age` INTEGER NOT NULL,
+	`to_combined_age` INTEGER NOT NULL,
+	`race_id` INTEGER NOT NULL REFERENCES races(id) ON DELETE CASCADE
+);
+
+CREATE TABLE `teams`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`name` TEXT NOT NULL,
+	`race_id` INTEGER NOT NULL REFERENCES races(id) ON DELETE CASCADE,
+	`team_category_id` INTEGER NOT NULL REFERENCES team_categories(id) ON DELETE CASCADE
+);
+
+CREATE TABLE `team_members`(
+	`team_id` INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+	`participant_id` INTEGER NOT NULL UNIQUE REFERENCES participants(id) ON DELETE CASCADE,
+	`leg` INTEGER NOT NULL,
+	PRIMARY KEY(`team_id`, `leg`)
+);
@@ -0,0 +1,3 @@
+DROP TABLE `club_scorings`;
+DROP TABLE `club_aliases`;
+DROP TABLE `clubs`;
@@ -0,0 +1,20 @@
+-- canonical clubs, participants still enter their club as free text
+CREATE TABLE `clubs`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`name` TEXT NOT NULL UNIQUE
+);
+
+-- free-text variants of a club name, stored in their normalized form
+CREATE TABLE `club_aliases`(
+	`alias` TEXT NOT NULL PRIMARY KEY,
+	`club_id` INTEGER NOT NULL REFERENCES clubs(id) ON DELETE CASCADE
+);
+
+-- how clubs are scored in a race, races without an entry have no club scoring
+CREATE TABLE `club_scorings`(
+	`race_id` INTEGER NOT NULL PRIMARY KEY REFERENCES races(id) ON DELETE CASCADE,
+	-- one of 'time_sum' or 'place_sum'
+	`mode` TEXT NOT NULL,
+	-- number of finishers per club that count for the score
+	`counting` INTEGER NOT NULL
+);
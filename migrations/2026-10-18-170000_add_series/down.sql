@@ -0,0 +1,5 @@
+DROP TABLE `series_competitions`;
+DROP TABLE `series`;
+ALTER TABLE `participants` DROP COLUMN `person_id`;
+DROP INDEX `persons_name_key`;
+DROP TABLE `persons`;
@@ -0,0 +1,29 @@
+-- an athlete across competitions, participants are linked to a person
+-- by their normalized name and birth year
+CREATE TABLE `persons`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`first_name` TEXT NOT NULL,
+	`last_name` TEXT NOT NULL,
+	`birth_year` INTEGER NOT NULL,
+	-- normalized "first_name last_name" used to match participants
+	`name_key` TEXT NOT NULL
+);
+
+CREATE INDEX `persons_name_key` ON `persons`(`name_key`, `birth_year`);
+
+ALTER TABLE `participants` ADD COLUMN `person_id` INTEGER REFERENCES persons(id) ON DELETE SET NULL;
+
+CREATE TABLE `series`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`name` TEXT NOT NULL,
+	-- comma separated points for the category places, e.g. '100,95,90'
+	`points_table` TEXT NOT NULL,
+	-- number of competitions that count for the standings
+	`counting_results` INTEGER NOT NULL
+);
+
+CREATE TABLE `series_competitions`(
+	`series_id` INTEGER NOT NULL REFERENCES series(id) ON DELETE CASCADE,
+	`competition_id` INTEGER NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+	PRIMARY KEY(`series_id`, `competition_id`)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE `persons` DROP COLUMN `email`;
+ALTER TABLE `participants` DROP COLUMN `email`;
@@ -0,0 +1,5 @@
+-- optional email address given at the registration
+ALTER TABLE `participants` ADD COLUMN `email` TEXT;
+
+-- used to tell apart different persons with the same name and birth year
+ALTER TABLE `persons` ADD COLUMN `email` TEXT;
@@ -0,0 +1,2 @@
+-- length of the course, used for age grading
+ALTER TABLE `races` ADD COLUMN `distance_meters` INTEGER;
@@ -0,0 +1,3 @@
+ALTER TABLE `races` DROP COLUMN `course_description`;
+ALTER TABLE `races` DROP COLUMN `surface`;
+ALTER TABLE `races` DROP COLUMN `elevation_gain_meters`;
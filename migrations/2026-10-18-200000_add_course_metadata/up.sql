@@ -0,0 +1,4 @@
+-- information about the course shown at the registration
+ALTER TABLE `races` ADD COLUMN `elevation_gain_meters` INTEGER;
+ALTER TABLE `races` ADD COLUMN `surface` TEXT;
+ALTER TABLE `races` ADD COLUMN `course_description` TEXT;
@@ -0,0 +1,13 @@
+-- corrections of recorded finish times
+--
+-- the recorded finish time is never changed, the results apply the sum of all
+-- adjustments of a participant instead
+CREATE TABLE `time_adjustments`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`participant_id` INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+	`delta_ms` BIGINT NOT NULL,
+	`reason` TEXT NOT NULL,
+	`created_by` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL
+);
+CREATE INDEX `time_adjustments_participant_id` ON `time_adjustments`(`participant_id`);
@@ -0,0 +1,18 @@
+-- published results of a race
+--
+-- each version is an immutable snapshot of the ranking at the time it was
+-- published. The latest version is the official result of the race
+CREATE TABLE `result_versions`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`race_id` INTEGER NOT NULL REFERENCES races(id) ON DELETE CASCADE,
+	`version` INTEGER NOT NULL,
+	-- the ranked results of the race as JSON
+	`results` TEXT NOT NULL,
+	-- why the results were published again, empty for the first version
+	`note` TEXT NOT NULL,
+	-- changed places and times compared to the previous version as JSON
+	`changes` TEXT NOT NULL,
+	`published_by` TEXT NOT NULL,
+	`published_at` TIMESTAMP NOT NULL,
+	UNIQUE(`race_id`, `version`)
+);
@@ -0,0 +1,21 @@
+-- corrections of the results requested by participants
+CREATE TABLE `correction_requests`(
+	`id` INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+	`participant_id` INTEGER NOT NULL REFERENCES participants(id) ON DELETE CASCADE,
+	-- one of 'time', 'category' or 'other'
+	`kind` TEXT NOT NULL,
+	-- the ranking time the participant expects in milliseconds, only for 'time'
+	`requested_time_ms` BIGINT,
+	-- the category the participant expects, only for 'category'
+	`requested_category_id` INTEGER REFERENCES categories(id) ON DELETE CASCADE,
+	`reason` TEXT NOT NULL,
+	-- email address or phone number to contact the participant
+	`contact` TEXT NOT NULL,
+	`created_at` TIMESTAMP NOT NULL,
+	-- one of 'open', 'accepted' or 'rejected'
+	`status` TEXT NOT NULL DEFAULT 'open',
+	`admin_comment` TEXT,
+	`handled_by` TEXT,
+	`handled_at` TIMESTAMP
+);
+CREATE INDEX `correction_requests_status` ON `correction_requests`(`status`);
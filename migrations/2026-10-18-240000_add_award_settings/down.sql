@@ -0,0 +1,3 @@
+ALTER TABLE `special_categories` DROP COLUMN `ceremony_order`;
+ALTER TABLE `categories` DROP COLUMN `ceremony_order`;
+DROP TABLE `award_settings`;
@@ -0,0 +1,12 @@
+-- settings of the award list of a competition
+CREATE TABLE `award_settings`(
+	`competition_id` INTEGER NOT NULL PRIMARY KEY REFERENCES competitions(id) ON DELETE CASCADE,
+	-- number of awarded places per category
+	`places` INTEGER NOT NULL DEFAULT 3,
+	-- categories with fewer finishers are merged with their neighbours
+	`min_finishers` INTEGER NOT NULL DEFAULT 0
+);
+
+-- position of the category in the prize-giving ceremony
+ALTER TABLE `categories` ADD COLUMN `ceremony_order` INTEGER;
+ALTER TABLE `special_categories` ADD COLUMN `ceremony_order` INTEGER;
@@ -0,0 +1,6 @@
+-- Server side secrets, e.g. the key used to sign the self-service links
+-- of participants
+CREATE TABLE `secrets`(
+	`name` TEXT NOT NULL PRIMARY KEY,
+	`value` BINARY NOT NULL
+);
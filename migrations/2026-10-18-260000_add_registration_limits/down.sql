@@ -0,0 +1,5 @@
+ALTER TABLE `participants` DROP COLUMN `waitlisted_at`;
+ALTER TABLE `starts` DROP COLUMN `capacity`;
+ALTER TABLE `races` DROP COLUMN `capacity`;
+ALTER TABLE `competitions` DROP COLUMN `registration_closes`;
+ALTER TABLE `competitions` DROP COLUMN `registration_opens`;
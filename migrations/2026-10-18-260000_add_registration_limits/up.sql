@@ -0,0 +1,8 @@
+-- registration is only possible between these timestamps, NULL means no limit
+ALTER TABLE `competitions` ADD COLUMN `registration_opens` TIMESTAMP;
+ALTER TABLE `competitions` ADD COLUMN `registration_closes` TIMESTAMP;
+-- maximal number of confirmed participants, NULL means no limit
+ALTER TABLE `races` ADD COLUMN `capacity` INTEGER;
+ALTER TABLE `starts` ADD COLUMN `capacity` INTEGER;
+-- set while the participant is on the waitlist, the waitlist is ordered by it
+ALTER TABLE `participants` ADD COLUMN `waitlisted_at` TIMESTAMP;
@@ -0,0 +1,2 @@
+DROP TABLE `payments`;
+DROP TABLE `race_fees`;
@@ -0,0 +1,26 @@
+-- entry fees of a race in cents, races without an entry are free
+CREATE TABLE `race_fees`(
+	`race_id` INTEGER NOT NULL PRIMARY KEY REFERENCES races(id) ON DELETE CASCADE,
+	`base_fee` INTEGER NOT NULL,
+	-- added for registrations from `late_from` on
+	`late_surcharge` INTEGER NOT NULL DEFAULT 0,
+	`late_from` TIMESTAMP,
+	-- subtracted for participants up to `youth_max_age` in the year of the competition
+	`youth_discount` INTEGER NOT NULL DEFAULT 0,
+	`youth_max_age` INTEGER,
+	-- subtracted for members of a club from the club list
+	`club_discount` INTEGER NOT NULL DEFAULT 0
+);
+
+-- the entry fee of a participant and whether it was paid
+CREATE TABLE `payments`(
+	`participant_id` INTEGER NOT NULL PRIMARY KEY REFERENCES participants(id) ON DELETE CASCADE,
+	-- in cents, computed at the time of the registration
+	`amount_due` INTEGER NOT NULL,
+	-- one of 'open', 'paid' or 'refunded'
+	`status` TEXT NOT NULL DEFAULT 'open',
+	`method` TEXT,
+	`reference` TEXT,
+	`paid_at` TIMESTAMP
+);
+CREATE INDEX `payments_status` ON `payments`(`status`);
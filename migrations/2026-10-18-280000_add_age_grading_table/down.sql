@@ -0,0 +1 @@
+DROP TABLE IF EXISTS `age_grading_tables`;
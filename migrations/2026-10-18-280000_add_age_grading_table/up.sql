@@ -0,0 +1,10 @@
+-- The age grading factors used for the results, age grading is disabled
+-- as long as no table is uploaded
+CREATE TABLE `age_grading_tables`(
+	`id` INTEGER NOT NULL PRIMARY KEY CHECK (`id` = 1),
+	-- source and license of the factors, shown next to the age graded results
+	`description` TEXT NOT NULL,
+	-- the factor tables as JSON, see `data/age_grading.json` for the format
+	`tables` TEXT NOT NULL,
+	`uploaded_at` TIMESTAMP NOT NULL
+);
@@ -0,0 +1,3 @@
+-- time of the registration, used to price the entry fee
+-- NULL for registrations from before this column existed
+ALTER TABLE `participants` ADD COLUMN `registered_at` TIMESTAMP;
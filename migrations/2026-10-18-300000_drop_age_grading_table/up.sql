@@ -0,0 +1,2 @@
+-- The age grading factors are embedded again, see `data/age_grading.json`
+DROP TABLE IF EXISTS `age_grading_tables`;
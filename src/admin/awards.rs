@@ -0,0 +1,356 @@
+//! Admin page setup for the award lists of the prize-giving
+use crate::app_state::{self, AppState};
+use crate::awards::{AwardGroup, AwardSettings};
+use crate::database::schema::{
+    award_settings, categories, competitions, races, special_categories, starts,
+};
+use crate::database::shared_models::Competition;
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::pdf::PdfLine;
+use axum::extract::Path;
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::{Html, IntoResponse, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::Serialize;
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/competitions/{competition_id}/awards.html",
+            axum::routing::get(render_award_list),
+        )
+        .route(
+            "/competitions/{competition_id}/awards.csv",
+            axum::routing::get(export_award_list_csv),
+        )
+        .route(
+            "/competitions/{competition_id}/awards.pdf",
+            axum::routing::get(export_award_list_pdf),
+        )
+        .route(
+            "/competitions/{competition_id}/award_settings.html",
+            axum::routing::get(render_award_settings),
+        )
+        .route(
+            "/competitions/{competition_id}/award_settings",
+            axum::routing::post(update_award_settings),
+        )
+}
+
+/// Data used to render the award list
+///
+/// See `templates/admin_awards.html` for the relevant template
+#[derive(Serialize)]
+struct AwardListData {
+    competition: Competition,
+    settings: AwardSettings,
+    groups: Vec<AwardGroup>,
+}
+
+async fn load_award_list(state: &AppState, competition_id: Id) -> Result<AwardListData> {
+    state
+        .with_connection(move |conn| {
+            let competition = competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)?;
+            let settings = AwardSettings::load(conn, competition_id)?;
+            let groups = crate::awards::award_list(conn, competition_id, settings)?;
+            QueryResult::Ok(AwardListData {
+                competition,
+                settings,
+                groups,
+            })
+        })
+        .await
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_award_list(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<Html<String>> {
+    let data = load_award_list(&state, competition_id).await?;
+    state.render_template("admin_awards.html", data)
+}
+
+/// Quote a CSV field if needed
+///
+/// Fields that a spreadsheet would evaluate as a formula are prefixed with
+/// a single quote, registrations are free text after all
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_owned()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn export_award_list_csv(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<impl IntoResponse> {
+    let data = load_award_list(&state, competition_id).await?;
+    let header = [
+        "race",
+        "category",
+        "place",
+        "bib",
+        "first_name",
+        "last_name",
+        "club",
+        "time",
+    ]
+    .map(|key| csv_field(&state.translation(key)))
+    .join(",");
+    let mut csv = format!("{header}\r\n");
+    for group in &data.groups {
+        for awardee in &group.awardees {
+            let row = [
+                group.race.clone(),
+                group.label.clone(),
+                awardee.place.to_string(),
+                awardee.bib.map(|bib| bib.to_string()).unwrap_or_default(),
+                awardee.first_name.clone(),
+                awardee.last_name.clone(),
+                awardee.club.clone().unwrap_or_default(),
+                app_state::format_duration(awardee.time),
+            ]
+            .map(|value| csv_field(&value))
+            .join(",");
+            csv.push_str(&row);
+            csv.push_str("\r\n");
+        }
+    }
+    Ok((
+        [
+            (CONTENT_TYPE, String::from("text/csv; charset=utf-8")),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"awards_{competition_id}.csv\""),
+            ),
+        ],
+        csv,
+    ))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn export_award_list_pdf(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<impl IntoResponse> {
+    let data = load_award_list(&state, competition_id).await?;
+    let mut lines = vec![
+        PdfLine::Heading(format!(
+            "{} - {}",
+            state.translation("award_list"),
+            data.competition.name
+        )),
+        PdfLine::Blank,
+    ];
+    for group in &data.groups {
+        lines.push(PdfLine::Heading(format!("{}: {}", group.race, group.label)));
+        for awardee in &group.awardees {
+            let bib = awardee
+                .bib
+                .map(|bib| format!(" ({bib})"))
+                .unwrap_or_default();
+            let club = awardee
+                .club
+                .as_ref()
+                .map(|club| format!(", {club}"))
+                .unwrap_or_default();
+            lines.push(PdfLine::Text(format!(
+                "{}. {} {}{bib}{club} - {}",
+                awardee.place,
+                awardee.first_name,
+                awardee.last_name,
+                app_state::format_duration(awardee.time)
+            )));
+        }
+        lines.push(PdfLine::Blank);
+    }
+    Ok((
+        [
+            (CONTENT_TYPE, String::from("application/pdf")),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"awards_{competition_id}.pdf\""),
+            ),
+        ],
+        crate::pdf::document(&lines),
+    ))
+}
+
+/// A category or special category with its ceremony order
+#[derive(Queryable, Serialize)]
+struct CeremonyEntry {
+    id: Id,
+    label: String,
+    race: String,
+    ceremony_order: Option<i32>,
+}
+
+/// Data used to render the award settings
+///
+/// See `templates/admin_award_settings.html` for the relevant template
+#[derive(Serialize)]
+struct AwardSettingsData {
+    competition: Competition,
+    settings: AwardSettings,
+    categories: Vec<CeremonyEntry>,
+    special_categories: Vec<CeremonyEntry>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_award_settings(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let competition = competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)?;
+            let settings = AwardSettings::load(conn, competition_id)?;
+            let categories = categories::table
+                .inner_join(starts::table.inner_join(races::table))
+                .filter(races::competition_id.eq(competition_id))
+                .order_by((races::id, categories::id))
+                .select((
+                    categories::id,
+                    categories::label,
+                    races::name,
+                    categories::ceremony_order,
+                ))
+                .load(conn)?;
+            let special_categories = special_categories::table
+                .inner_join(races::table)
+                .filter(races::competition_id.eq(competition_id))
+                .order_by((races::id, special_categories::id))
+                .select((
+                    special_categories::id,
+                    special_categories::name,
+                    races::name,
+                    special_categories::ceremony_order,
+                ))
+                .load(conn)?;
+            QueryResult::Ok(AwardSettingsData {
+                competition,
+                settings,
+                categories,
+                special_categories,
+            })
+        })
+        .await?;
+    state.render_template("admin_award_settings.html", data)
+}
+
+/// Parse a number from the settings form, empty fields are `None`
+fn parse_setting(key: &str, value: &str) -> Result<Option<i32>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| Error::InvalidInput(format!("Invalid number for {key}: {value}")))
+}
+
+/// Store the award settings
+///
+/// Besides `places` and `min_finishers` the form contains the ceremony order
+/// of each category as `category_{id}` and of each special category as
+/// `special_{id}`
+#[axum::debug_handler(state = app_state::State)]
+async fn update_award_settings(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+    Form(form): Form<Vec<(String, String)>>,
+) -> Result<Redirect> {
+    let mut settings = AwardSettings::default();
+    let mut category_orders = Vec::new();
+    let mut special_orders = Vec::new();
+    for (key, value) in &form {
+        let number = parse_setting(key, value)?;
+        if let Some(id) = key.strip_prefix("category_") {
+            let id = id
+                .parse::<Id>()
+                .map_err(|_| Error::InvalidInput(format!("Invalid field {key}")))?;
+            category_orders.push((id, number));
+        } else if let Some(id) = key.strip_prefix("special_") {
+            let id = id
+                .parse::<Id>()
+                .map_err(|_| Error::InvalidInput(format!("Invalid field {key}")))?;
+            special_orders.push((id, number));
+        } else if key == "places" {
+            settings.places = number.unwrap_or(settings.places);
+        } else if key == "min_finishers" {
+            settings.min_finishers = number.unwrap_or(settings.min_finishers);
+        }
+    }
+    if settings.places < 1 {
+        return Err(Error::InvalidInput(String::from(
+            "At least one place needs to be awarded",
+        )));
+    }
+    if settings.min_finishers < 0 {
+        return Err(Error::InvalidInput(String::from(
+            "The minimum number of finishers cannot be negative",
+        )));
+    }
+
+    state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                diesel::replace_into(award_settings::table)
+                    .values((
+                        award_settings::competition_id.eq(competition_id),
+                        award_settings::places.eq(settings.places),
+                        award_settings::min_finishers.eq(settings.min_finishers),
+                    ))
+                    .execute(conn)?;
+                for (id, order) in category_orders {
+                    let in_competition = starts::table
+                        .inner_join(races::table)
+                        .filter(races::competition_id.eq(competition_id))
+                        .select(starts::id);
+                    diesel::update(
+                        categories::table
+                            .filter(categories::id.eq(id))
+                            .filter(categories::start_id.eq_any(in_competition)),
+                    )
+                    .set(categories::ceremony_order.eq(order))
+                    .execute(conn)?;
+                }
+                for (id, order) in special_orders {
+                    let in_competition = races::table
+                        .filter(races::competition_id.eq(competition_id))
+                        .select(races::id);
+                    diesel::update(
+                        special_categories::table
+                            .filter(special_categories::id.eq(id))
+                            .filter(special_categories::race_id.eq_any(in_competition)),
+                    )
+                    .set(special_categories::ceremony_order.eq(order))
+                    .execute(conn)?;
+                }
+                QueryResult::Ok(())
+            })
+        })
+        .await?;
+    let base_url = state.base_url();
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/awards.html"
+    )))
+}
@@ -0,0 +1,288 @@
+//! Admin page setup for chip assignments and imports of chip timing exports
+use crate::app_state::{self, AppState};
+use crate::chip_timing;
+use crate::database::chip_reads::{self, DEFAULT_DEDUP_WINDOW};
+use crate::database::schema::{
+    bib_numbers, categories, chip_reads as chip_reads_table, chips, competitions, participants,
+    races, starts,
+};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::{Multipart, Path, Query};
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::dsl;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/competitions/{competition_id}/chips.html",
+            axum::routing::get(list_chips),
+        )
+        .route(
+            "/competitions/{competition_id}/chips",
+            axum::routing::post(assign_chip),
+        )
+        .route(
+            "/competitions/{competition_id}/chips/{chip}/delete.html",
+            axum::routing::get(delete_chip),
+        )
+        .route(
+            "/competitions/{competition_id}/chip_reads",
+            axum::routing::post(import_chip_reads),
+        )
+}
+
+/// A chip together with the participant it is assigned to
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = chips)]
+#[diesel(check_for_backend(Sqlite))]
+struct ChipAssignment {
+    chip: String,
+    participant_id: Id,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = participants::first_name)]
+    first_name: String,
+    #[diesel(select_expression = participants::last_name)]
+    last_name: String,
+}
+
+/// A chip with reads, that is not assigned to any participant
+#[derive(Serialize)]
+struct UnassignedChip {
+    chip: String,
+    reads: i64,
+}
+
+/// Summary of an import, passed to the chip page after the import
+#[derive(Deserialize, Serialize)]
+struct LastImport {
+    stored: Option<usize>,
+    duplicates: Option<usize>,
+    unassigned: Option<usize>,
+    rejected: Option<usize>,
+}
+
+/// Data for the chip page
+///
+/// See `templates/admin_chips.html` for the relevant template
+#[derive(Serialize)]
+struct ListChipsData {
+    competition_id: Id,
+    competition_name: String,
+    chips: Vec<ChipAssignment>,
+    unassigned: Vec<UnassignedChip>,
+    /// number of all stored reads
+    read_count: i64,
+    /// default value for the deduplication window in seconds
+    dedup_window: i64,
+    last_import: LastImport,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_chips(
+    state: AppState,
+    competition_id: Path<Id>,
+    last_import: Query<LastImport>,
+) -> Result<Html<String>> {
+    let competition_id = competition_id.0;
+    let (competition_name, chips, unassigned, read_count) = state
+        .with_connection(move |conn| {
+            let competition_name = competitions::table
+                .find(competition_id)
+                .select(competitions::name)
+                .first::<String>(conn)
+                .optional()?;
+            let chips = chips::table
+                .inner_join(participants::table.left_join(bib_numbers::table))
+                .filter(chips::competition_id.eq(competition_id))
+                .order_by(chips::chip)
+                .select(ChipAssignment::as_select())
+                .load(conn)?;
+            let unassigned = chip_reads_table::table
+                .filter(chip_reads_table::competition_id.eq(competition_id))
+                .filter(dsl::not(dsl::exists(
+                    chips::table
+                        .filter(chips::competition_id.eq(competition_id))
+                        .filter(chips::chip.eq(chip_reads_table::chip)),
+                )))
+                .group_by(chip_reads_table::chip)
+                .order_by(chip_reads_table::chip)
+                .select((chip_reads_table::chip, dsl::count_star()))
+                .load::<(String, i64)>(conn)?
+                .into_iter()
+                .map(|(chip, reads)| UnassignedChip { chip, reads })
+                .collect();
+            let read_count = chip_reads_table::table
+                .filter(chip_reads_table::competition_id.eq(competition_id))
+                .count()
+                .get_result::<i64>(conn)?;
+            QueryResult::Ok((competition_name, chips, unassigned, read_count))
+        })
+        .await?;
+    let competition_name = competition_name
+        .ok_or_else(|| Error::NotFound(format!("No competition for id {competition_id} found")))?;
+
+    state.render_template(
+        "admin_chips.html",
+        ListChipsData {
+            competition_id,
+            competition_name,
+            chips,
+            unassigned,
+            read_count,
+            dedup_window: DEFAULT_DEDUP_WINDOW.whole_seconds(),
+            last_import: last_import.0,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct ChipInput {
+    chip: String,
+    participant_id: Id,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn assign_chip(
+    state: AppState,
+    competition_id: Path<Id>,
+    data: Form<ChipInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let ChipInput {
+        chip,
+        participant_id,
+    } = data.0;
+    let chip = chip.trim().to_owned();
+    if chip.is_empty() {
+        return Err(Error::InvalidInput("The chip id must not be empty".into()));
+    }
+    let assigned = state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let participant_exists = diesel::select(dsl::exists(
+                    participants::table
+                        .inner_join(
+                            categories::table.inner_join(starts::table.inner_join(races::table)),
+                        )
+                        .filter(participants::id.eq(participant_id))
+                        .filter(races::competition_id.eq(competition_id)),
+                ))
+                .get_result::<bool>(conn)?;
+                if !participant_exists {
+                    return Ok(false);
+                }
+                diesel::insert_into(chips::table)
+                    .values((
+                        chips::competition_id.eq(competition_id),
+                        chips::chip.eq(&chip),
+                        chips::participant_id.eq(participant_id),
+                    ))
+                    .on_conflict((chips::competition_id, chips::chip))
+                    .do_update()
+                    .set(chips::participant_id.eq(participant_id))
+                    .execute(conn)?;
+                // reads might have been imported before the chip was assigned
+                chip_reads::apply_chip(conn, competition_id, &chip)?;
+                QueryResult::Ok(true)
+            })
+        })
+        .await?;
+    if !assigned {
+        return Err(Error::NotFound(format!(
+            "Participant with id {participant_id} not found in competition {competition_id}"
+        )));
+    }
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/chips.html"
+    )))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_chip(state: AppState, path: Path<(Id, String)>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let (competition_id, chip) = path.0;
+    state
+        .with_connection(move |conn| {
+            diesel::delete(chips::table.find((competition_id, chip))).execute(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/chips.html"
+    )))
+}
+
+/// Import an export file of a timing reader
+///
+/// Expects a multipart form with the export as `file` and an optional
+/// `dedup_window` in seconds
+#[axum::debug_handler(state = app_state::State)]
+async fn import_chip_reads(
+    state: AppState,
+    competition_id: Path<Id>,
+    mut multipart: Multipart,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let mut content = None;
+    let mut dedup_window = DEFAULT_DEDUP_WINDOW;
+    while let Some(field) = multipart.next_field().await? {
+        match field.name() {
+            Some("file") => {
+                let bytes = field.bytes().await?;
+                content = Some(String::from_utf8_lossy(&bytes).into_owned());
+            }
+            Some("dedup_window") => {
+                let text = field.text().await?;
+                if !text.is_empty() {
+                    let seconds = text
+                        .parse::<u32>()
+                        .map_err(|e| Error::InvalidInput(format!("Invalid window: {e}")))?;
+                    dedup_window = time::Duration::seconds(seconds.into());
+                }
+            }
+            _ => {}
+        }
+    }
+    let content = content.ok_or_else(|| Error::InvalidInput("No file uploaded".into()))?;
+    let reads = chip_timing::parse_export(&content)?;
+
+    let competition_exists = state
+        .with_connection(move |conn| {
+            diesel::select(dsl::exists(competitions::table.find(competition_id)))
+                .get_result::<bool>(conn)
+        })
+        .await?;
+    if !competition_exists {
+        return Err(Error::NotFound(format!(
+            "No competition for id {competition_id} found"
+        )));
+    }
+    let summary = state
+        .with_connection(move |conn| {
+            chip_reads::store_reads(conn, competition_id, &reads, dedup_window)
+        })
+        .await?;
+    tracing::info!(
+        "Imported {} chip reads for competition {competition_id}, ignored {} duplicates, {} unassigned chips, rejected {} early finish reads",
+        summary.stored,
+        summary.duplicates,
+        summary.unassigned.len(),
+        summary.rejected
+    );
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/chips.html?stored={}&duplicates={}&unassigned={}&rejected={}",
+        summary.stored,
+        summary.duplicates,
+        summary.unassigned.len(),
+        summary.rejected
+    )))
+}
@@ -0,0 +1,307 @@
+//! Admin page setup for canonical clubs and the club scoring of races
+use crate::app_state::{self, AppState};
+use crate::database::clubs::{ClubDirectory, ClubKey};
+use crate::database::normalize_name;
+use crate::database::schema::{club_aliases, club_scorings, clubs, participants, races};
+use crate::database::shared_models::{ClubScoring, ClubScoringMode, Race};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::dsl;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route("/clubs.html", axum::routing::get(list_clubs))
+        .route("/clubs", axum::routing::post(new_club))
+        .route(
+            "/clubs/{club_id}/delete.html",
+            axum::routing::get(delete_club),
+        )
+        .route("/club_aliases", axum::routing::post(new_club_alias))
+        .route(
+            "/club_aliases/delete",
+            axum::routing::post(delete_club_alias),
+        )
+        .route(
+            "/races/{race_id}/club_scoring.html",
+            axum::routing::get(render_club_scoring),
+        )
+        .route(
+            "/races/{race_id}/club_scoring",
+            axum::routing::post(update_club_scoring),
+        )
+}
+
+#[derive(Serialize)]
+struct ClubData {
+    id: Id,
+    name: String,
+    aliases: Vec<String>,
+}
+
+/// Club names entered by participants that do not belong to a canonical club
+#[derive(Serialize)]
+struct UnmatchedClub {
+    /// the normalized name, that's what is stored as alias
+    normalized: String,
+    /// all spellings of this name
+    spellings: Vec<String>,
+    participants: i64,
+}
+
+#[derive(Serialize)]
+struct ListClubsData {
+    clubs: Vec<ClubData>,
+    unmatched: Vec<UnmatchedClub>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_clubs(state: AppState) -> Result<Html<String>> {
+    let (clubs, unmatched) = state
+        .with_connection(|conn| {
+            let aliases = club_aliases::table
+                .order_by(club_aliases::alias)
+                .select((club_aliases::club_id, club_aliases::alias))
+                .load::<(Id, String)>(conn)?;
+            let clubs = clubs::table
+                .order_by(clubs::name)
+                .select((clubs::id, clubs::name))
+                .load::<(Id, String)>(conn)?
+                .into_iter()
+                .map(|(id, name)| ClubData {
+                    aliases: aliases
+                        .iter()
+                        .filter(|(club_id, _)| *club_id == id)
+                        .map(|(_, alias)| alias.clone())
+                        .collect(),
+                    id,
+                    name,
+                })
+                .collect::<Vec<_>>();
+
+            let directory = ClubDirectory::load(conn)?;
+            let club_names = participants::table
+                .filter(participants::club.is_not_null())
+                .group_by(participants::club)
+                .select((participants::club.assume_not_null(), dsl::count_star()))
+                .load::<(String, i64)>(conn)?;
+            let mut unmatched = BTreeMap::<String, UnmatchedClub>::new();
+            for (name, count) in club_names {
+                if let Some(ClubKey::Unknown(normalized)) = directory.resolve(Some(&name)) {
+                    let entry =
+                        unmatched
+                            .entry(normalized.clone())
+                            .or_insert_with(|| UnmatchedClub {
+                                normalized,
+                                spellings: Vec::new(),
+                                participants: 0,
+                            });
+                    entry.spellings.push(name);
+                    entry.participants += count;
+                }
+            }
+            QueryResult::Ok((clubs, unmatched.into_values().collect()))
+        })
+        .await?;
+    state.render_template("admin_list_clubs.html", ListClubsData { clubs, unmatched })
+}
+
+#[derive(Deserialize)]
+struct ClubInput {
+    name: String,
+    /// an optional alias, used to create a club directly from an unmatched name
+    #[serde(default)]
+    alias: Option<String>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_club(state: AppState, data: Form<ClubInput>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let ClubInput { name, alias } = data.0;
+    let name = name.trim().to_owned();
+    if name.is_empty() {
+        return Err(Error::InvalidInput("The name must not be empty".into()));
+    }
+    state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let club_id = diesel::insert_into(clubs::table)
+                    .values(clubs::name.eq(&name))
+                    .returning(clubs::id)
+                    .get_result::<Id>(conn)?;
+                if let Some(alias) = alias.map(|a| normalize_name(&a)) {
+                    if !alias.is_empty() && alias != normalize_name(&name) {
+                        upsert_alias(conn, club_id, &alias)?;
+                    }
+                }
+                QueryResult::Ok(())
+            })
+        })
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/clubs.html")))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_club(state: AppState, club_id: Path<Id>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let club_id = club_id.0;
+    state
+        .with_connection(move |conn| {
+            diesel::delete(clubs::table.find(club_id))
+                .returning(clubs::id)
+                .get_result::<Id>(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/clubs.html")))
+}
+
+#[derive(Deserialize)]
+struct AliasInput {
+    alias: String,
+}
+
+#[derive(Deserialize)]
+struct NewAliasInput {
+    club_id: Id,
+    alias: String,
+}
+
+/// Map an alias to a club, an existing alias is moved to the given club
+fn upsert_alias(conn: &mut SqliteConnection, club_id: Id, alias: &str) -> QueryResult<usize> {
+    diesel::insert_into(club_aliases::table)
+        .values((
+            club_aliases::alias.eq(alias),
+            club_aliases::club_id.eq(club_id),
+        ))
+        .on_conflict(club_aliases::alias)
+        .do_update()
+        .set(club_aliases::club_id.eq(club_id))
+        .execute(conn)
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_club_alias(state: AppState, data: Form<NewAliasInput>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let club_id = data.club_id;
+    let alias = normalize_name(&data.alias);
+    if alias.is_empty() {
+        return Err(Error::InvalidInput("The alias must not be empty".into()));
+    }
+    state
+        .with_connection(move |conn| upsert_alias(conn, club_id, &alias))
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/clubs.html")))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_club_alias(state: AppState, data: Form<AliasInput>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let alias = data.0.alias;
+    state
+        .with_connection(move |conn| {
+            diesel::delete(club_aliases::table.find(alias))
+                .returning(club_aliases::club_id)
+                .get_result::<Id>(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/clubs.html")))
+}
+
+#[derive(Serialize)]
+struct ClubScoringData {
+    race: Race,
+    scoring: Option<ClubScoring>,
+    modes: [ClubScoringMode; 2],
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_club_scoring(state: AppState, race_id: Path<Id>) -> Result<Html<String>> {
+    let race_id = race_id.0;
+    let (race, scoring) = state
+        .with_connection(move |conn| {
+            let race = races::table
+                .find(race_id)
+                .select(Race::as_select())
+                .first(conn)?;
+            let scoring = club_scorings::table
+                .find(race_id)
+                .select(ClubScoring::as_select())
+                .first(conn)
+                .optional()?;
+            QueryResult::Ok((race, scoring))
+        })
+        .await?;
+    state.render_template(
+        "admin_club_scoring.html",
+        ClubScoringData {
+            race,
+            scoring,
+            modes: ClubScoringMode::ALL,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct ClubScoringInput {
+    /// the scoring mode, an empty value disables the club scoring
+    mode: String,
+    counting: i32,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn update_club_scoring(
+    state: AppState,
+    race_id: Path<Id>,
+    data: Form<ClubScoringInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let race_id = race_id.0;
+    let ClubScoringInput { mode, counting } = data.0;
+    let mode = if mode.is_empty() {
+        None
+    } else {
+        Some(
+            mode.parse::<ClubScoringMode>()
+                .map_err(Error::InvalidInput)?,
+        )
+    };
+    if counting < 1 {
+        return Err(Error::InvalidInput(
+            "At least one finisher needs to count".into(),
+        ));
+    }
+    let competition_id = state
+        .with_connection(move |conn| {
+            let competition_id = races::table
+                .find(race_id)
+                .select(races::competition_id)
+                .first::<Id>(conn)?;
+            if let Some(mode) = mode {
+                diesel::insert_into(club_scorings::table)
+                    .values((
+                        club_scorings::race_id.eq(race_id),
+                        club_scorings::mode.eq(mode),
+                        club_scorings::counting.eq(counting),
+                    ))
+                    .on_conflict(club_scorings::race_id)
+                    .do_update()
+                    .set((
+                        club_scorings::mode.eq(mode),
+                        club_scorings::counting.eq(counting),
+                    ))
+                    .execute(conn)?;
+            } else {
+                diesel::delete(club_scorings::table.find(race_id)).execute(conn)?;
+            }
+            QueryResult::Ok(competition_id)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/races.html"
+    )))
+}
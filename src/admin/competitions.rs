@@ -9,8 +9,9 @@ use axum::extract::Path;
 use axum::response::Html;
 use axum::response::Redirect;
 use axum::{Form, Router};
-use serde::{Deserialize, Serialize};
-use time::Date;
+use serde::{Deserialize, Deserializer, Serialize};
+use time::macros::format_description;
+use time::{Date, PrimitiveDateTime};
 
 pub fn routes() -> Router<app_state::State> {
     Router::new()
@@ -48,6 +49,27 @@ pub(crate) struct NewCompetition {
     pub(crate) date: Date,
     pub(crate) location: String,
     pub(crate) announcement: String,
+    /// registrations are accepted from this point in time
+    #[serde(default, deserialize_with = "parse_optional_timestamp")]
+    pub(crate) registration_opens: Option<PrimitiveDateTime>,
+    /// registrations are accepted until this point in time
+    #[serde(default, deserialize_with = "parse_optional_timestamp")]
+    pub(crate) registration_closes: Option<PrimitiveDateTime>,
+}
+
+pub(crate) fn parse_optional_timestamp<'de, D>(d: D) -> Result<Option<PrimitiveDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // percent-encoded values cannot be borrowed from the request body
+    let s = <String as Deserialize>::deserialize(d)?;
+    if s.is_empty() {
+        return Ok(None);
+    }
+    let format = format_description!("[year]-[month]-[day]T[hour]:[minute]");
+    PrimitiveDateTime::parse(&s, format)
+        .map(Some)
+        .map_err(|e| serde::de::Error::custom(e.to_string()))
 }
 
 #[axum::debug_handler(state = app_state::State)]
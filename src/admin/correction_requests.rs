@@ -0,0 +1,227 @@
+//! Admin page setup for handling correction requests of participants
+use crate::admin::user::auth_session::AuthSession;
+use crate::app_state::{self, AppState};
+use crate::database::correction_requests::CorrectionOutcome;
+use crate::database::schema::{
+    bib_numbers, categories, competitions, correction_requests, participants, races, starts,
+};
+use crate::database::shared_models::{CorrectionKind, CorrectionRequest, CorrectionStatus};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/correction_requests.html",
+            axum::routing::get(list_correction_requests),
+        )
+        .route(
+            "/correction_requests/{request_id}/accept",
+            axum::routing::post(accept_correction_request),
+        )
+        .route(
+            "/correction_requests/{request_id}/reject",
+            axum::routing::post(reject_correction_request),
+        )
+}
+
+/// A correction request joined with the data of the participant
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = correction_requests)]
+#[diesel(check_for_backend(Sqlite))]
+struct QueueEntry {
+    #[serde(flatten)]
+    #[diesel(embed)]
+    request: CorrectionRequest,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = participants::first_name)]
+    first_name: String,
+    #[diesel(select_expression = participants::last_name)]
+    last_name: String,
+    #[diesel(select_expression = categories::label)]
+    category: String,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = competitions::name)]
+    competition: String,
+}
+
+/// Data used to render the queue
+///
+/// See `templates/admin_correction_requests.html` for the relevant template
+#[derive(Serialize)]
+struct CorrectionQueueData {
+    /// open requests, the oldest first
+    open: Vec<QueueEntry>,
+    /// accepted and rejected requests, the latest first
+    handled: Vec<QueueEntry>,
+    /// labels of the requested categories by id
+    category_labels: HashMap<String, String>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_correction_requests(state: AppState) -> Result<Html<String>> {
+    let (entries, category_labels) = state
+        .with_connection(|conn| {
+            let entries = correction_requests::table
+                .inner_join(
+                    participants::table
+                        .inner_join(categories::table.inner_join(
+                            starts::table.inner_join(races::table.inner_join(competitions::table)),
+                        ))
+                        .left_join(bib_numbers::table),
+                )
+                .order_by((correction_requests::created_at, correction_requests::id))
+                .select(QueueEntry::as_select())
+                .load::<QueueEntry>(conn)?;
+            let requested = entries
+                .iter()
+                .filter_map(|e| e.request.requested_category_id)
+                .collect::<Vec<_>>();
+            let category_labels = categories::table
+                .filter(categories::id.eq_any(requested))
+                .select((categories::id, categories::label))
+                .load::<(Id, String)>(conn)?;
+            QueryResult::Ok((entries, category_labels))
+        })
+        .await?;
+    let (open, mut handled): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|e| e.request.status == CorrectionStatus::Open);
+    handled.reverse();
+    state.render_template(
+        "admin_correction_requests.html",
+        CorrectionQueueData {
+            open,
+            handled,
+            // template maps only support string keys
+            category_labels: category_labels
+                .into_iter()
+                .map(|(id, label)| (id.to_string(), label))
+                .collect(),
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct DecisionInput {
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_string"
+    )]
+    comment: Option<String>,
+}
+
+/// The race and the competition of a participant
+fn race_of_participant(conn: &mut SqliteConnection, participant_id: Id) -> QueryResult<(Id, Id)> {
+    participants::table
+        .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+        .filter(participants::id.eq(participant_id))
+        .select((races::id, races::competition_id))
+        .first(conn)
+}
+
+fn outcome_to_result(request_id: Id, outcome: CorrectionOutcome) -> Result<()> {
+    match outcome {
+        CorrectionOutcome::Handled => Ok(()),
+        CorrectionOutcome::NotOpen => Err(Error::InvalidInput(format!(
+            "Correction request {request_id} was already handled"
+        ))),
+        CorrectionOutcome::NotRanked => Err(Error::InvalidInput(format!(
+            "The participant of correction request {request_id} has no time to correct"
+        ))),
+        CorrectionOutcome::InvalidCategory => Err(Error::InvalidInput(format!(
+            "The category of correction request {request_id} does not belong to the race"
+        ))),
+    }
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn accept_correction_request(
+    state: AppState,
+    auth_session: AuthSession,
+    Path(request_id): Path<Id>,
+    data: Form<DecisionInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let comment = data.0.comment;
+    let handled_by = auth_session
+        .user
+        .map(|user| user.name)
+        .ok_or_else(|| Error::InvalidInput(String::from("Not logged in")))?;
+    let (outcome, competition_id) = state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let request = crate::database::correction_requests::load_request(conn, request_id)?;
+                let (race_id, competition_id) = race_of_participant(conn, request.participant_id)?;
+                let current_time = if request.kind == CorrectionKind::Time {
+                    crate::result_versions::live_results(conn, race_id)
+                        .optional()?
+                        .and_then(|(_, results)| {
+                            results
+                                .participants
+                                .iter()
+                                .find(|p| p.participant.id == request.participant_id)
+                                .map(|p| p.time)
+                        })
+                } else {
+                    None
+                };
+                let outcome = crate::database::correction_requests::accept_request(
+                    conn,
+                    &request,
+                    current_time,
+                    comment.as_deref(),
+                    &handled_by,
+                )?;
+                QueryResult::Ok((outcome, competition_id))
+            })
+        })
+        .await?;
+    outcome_to_result(request_id, outcome)?;
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/correction_requests.html"
+    )))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn reject_correction_request(
+    state: AppState,
+    auth_session: AuthSession,
+    Path(request_id): Path<Id>,
+    data: Form<DecisionInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let comment = data
+        .0
+        .comment
+        .ok_or_else(|| Error::InvalidInput(String::from("A rejection needs a comment")))?;
+    let handled_by = auth_session
+        .user
+        .map(|user| user.name)
+        .ok_or_else(|| Error::InvalidInput(String::from("Not logged in")))?;
+    let outcome = state
+        .with_connection(move |conn| {
+            let request = crate::database::correction_requests::load_request(conn, request_id)?;
+            crate::database::correction_requests::reject_request(
+                conn,
+                &request,
+                &comment,
+                &handled_by,
+            )
+        })
+        .await?;
+    outcome_to_result(request_id, outcome)?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/correction_requests.html"
+    )))
+}
@@ -0,0 +1,159 @@
+//! Admin page setup for the report of suspected duplicate registrations
+//!
+//! Participants of a competition with the same normalized name, birth year
+//! and club are listed together. Each duplicate can be merged into the first
+//! registration of its group.
+use crate::app_state::{self, AppState};
+use crate::database::duplicates::MergeOutcome;
+use crate::database::schema::{
+    bib_numbers, categories, competitions, participants, races, starts, time_records,
+};
+use crate::database::shared_models::Competition;
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/competitions/{competition_id}/duplicates.html",
+            axum::routing::get(render_duplicates),
+        )
+        .route(
+            "/competitions/{competition_id}/duplicates/merge",
+            axum::routing::post(merge_duplicates),
+        )
+}
+
+/// A participant as shown in the duplicate report
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = participants)]
+struct DuplicateParticipant {
+    id: Id,
+    first_name: String,
+    last_name: String,
+    birth_year: i32,
+    club: Option<String>,
+    email: Option<String>,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = categories::label)]
+    category: String,
+    /// whether the participant is on the waitlist
+    #[diesel(select_expression = participants::waitlisted_at.is_not_null())]
+    waitlisted: bool,
+    /// whether there is already a finish time for the participant
+    #[diesel(select_expression = diesel::dsl::exists(
+        time_records::table.filter(time_records::participant_id.eq(participants::id))
+    ))]
+    finished: bool,
+}
+
+/// Data used to render the duplicate report
+///
+/// See `templates/admin_duplicates.html` for the relevant template
+#[derive(Serialize)]
+struct DuplicatesData {
+    competition: Competition,
+    /// groups of suspected duplicates, the first participant of each group
+    /// is the one the others are merged into
+    groups: Vec<Vec<DuplicateParticipant>>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_duplicates(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let competition = competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)?;
+            let groups = crate::database::duplicates::duplicate_groups(conn, competition_id)?
+                .into_iter()
+                .map(|ids| {
+                    participants::table
+                        .inner_join(
+                            categories::table.inner_join(starts::table.inner_join(races::table)),
+                        )
+                        .left_join(bib_numbers::table)
+                        .filter(participants::id.eq_any(ids))
+                        .order_by(participants::id)
+                        .select(DuplicateParticipant::as_select())
+                        .load(conn)
+                })
+                .collect::<QueryResult<Vec<_>>>()?;
+            QueryResult::Ok(DuplicatesData {
+                competition,
+                groups,
+            })
+        })
+        .await?;
+    state.render_template("admin_duplicates.html", data)
+}
+
+#[derive(Deserialize)]
+struct MergeInput {
+    /// the participant that is kept
+    into: Id,
+    /// the participant that is merged into `into` and removed
+    from: Id,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn merge_duplicates(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+    Form(data): Form<MergeInput>,
+) -> Result<Redirect> {
+    let MergeInput { into, from } = data;
+    if into == from {
+        return Err(Error::InvalidInput(
+            "A participant cannot be merged with itself".into(),
+        ));
+    }
+    let merged = state
+        .with_connection(move |conn| {
+            let in_competition = participants::table
+                .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+                .filter(participants::id.eq_any([into, from]))
+                .filter(races::competition_id.eq(competition_id))
+                .count()
+                .get_result::<i64>(conn)?;
+            if in_competition != 2 {
+                return Ok(None);
+            }
+            crate::database::duplicates::merge_participants(conn, into, from).map(Some)
+        })
+        .await?;
+    match merged {
+        None => {
+            return Err(Error::NotFound(format!(
+                "Participants {into} and {from} are not registered for competition {competition_id}"
+            )));
+        }
+        Some(MergeOutcome::HasResults) => {
+            return Err(Error::InvalidInput(format!(
+                "Participant {from} already has results and cannot be removed"
+            )));
+        }
+        Some(MergeOutcome::BothPaymentsSettled) => {
+            return Err(Error::InvalidInput(format!(
+                "Participants {into} and {from} both paid, settle one of the payments first"
+            )));
+        }
+        Some(MergeOutcome::Merged) => {}
+    }
+    let base_url = state.base_url();
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/duplicates.html"
+    )))
+}
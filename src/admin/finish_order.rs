@@ -0,0 +1,310 @@
+//! Admin page setup for capturing stopwatch times and the finish order
+//! separately and merging both lists afterwards
+use super::time_records::parse_timestamp;
+use super::RedirectInfo;
+use crate::app_state::{self, AppState};
+use crate::database::finish_order::{self, MergeRow};
+use crate::database::schema::{competitions, finish_order_bibs, stopwatch_times};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::{Path, Query};
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/competitions/{competition_id}/stopwatch.html",
+            axum::routing::get(list_stopwatch_times),
+        )
+        .route(
+            "/competitions/{competition_id}/stopwatch",
+            axum::routing::post(new_stopwatch_time),
+        )
+        .route(
+            "/stopwatch_times/{time_id}/delete.html",
+            axum::routing::get(delete_stopwatch_time),
+        )
+        .route(
+            "/competitions/{competition_id}/finish_order.html",
+            axum::routing::get(list_finish_order),
+        )
+        .route(
+            "/competitions/{competition_id}/finish_order",
+            axum::routing::post(new_finish_bib),
+        )
+        .route(
+            "/finish_order/{bib_id}/delete.html",
+            axum::routing::get(delete_finish_bib),
+        )
+        .route(
+            "/competitions/{competition_id}/finish_merge.html",
+            axum::routing::get(render_merge),
+        )
+        .route(
+            "/competitions/{competition_id}/finish_merge",
+            axum::routing::post(apply_merge),
+        )
+}
+
+/// A single entry of one of the capture lists
+#[derive(Serialize)]
+struct CaptureEntry {
+    id: Id,
+    position: i32,
+    /// set for stopwatch entries
+    time: Option<PrimitiveDateTime>,
+    /// set for finish order entries
+    bib: Option<i32>,
+}
+
+/// Data for the capture pages
+///
+/// See `templates/admin_finish_capture.html` for the relevant template
+#[derive(Serialize)]
+struct CaptureData {
+    competition_id: Id,
+    competition_name: String,
+    /// either `stopwatch` or `finish_order`
+    kind: &'static str,
+    entries: Vec<CaptureEntry>,
+}
+
+/// Data for the merge view
+///
+/// See `templates/admin_finish_merge.html` for the relevant template
+#[derive(Serialize)]
+struct MergeData {
+    competition_id: Id,
+    competition_name: String,
+    rows: Vec<MergeRow>,
+}
+
+/// Fails with `NotFound` if there is no such competition
+fn load_competition_name(conn: &mut SqliteConnection, competition_id: Id) -> QueryResult<String> {
+    competitions::table
+        .find(competition_id)
+        .select(competitions::name)
+        .first(conn)
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_stopwatch_times(state: AppState, competition_id: Path<Id>) -> Result<Html<String>> {
+    let competition_id = competition_id.0;
+    let (competition_name, entries) = state
+        .with_connection(move |conn| {
+            let competition_name = load_competition_name(conn, competition_id)?;
+            let entries = stopwatch_times::table
+                .filter(stopwatch_times::competition_id.eq(competition_id))
+                .order_by((stopwatch_times::position, stopwatch_times::id))
+                .select((
+                    stopwatch_times::id,
+                    stopwatch_times::position,
+                    stopwatch_times::time,
+                ))
+                .load::<(Id, i32, PrimitiveDateTime)>(conn)?;
+            QueryResult::Ok((competition_name, entries))
+        })
+        .await?;
+    let entries = entries
+        .into_iter()
+        .map(|(id, position, time)| CaptureEntry {
+            id,
+            position,
+            time: Some(time),
+            bib: None,
+        })
+        .collect();
+
+    state.render_template(
+        "admin_finish_capture.html",
+        CaptureData {
+            competition_id,
+            competition_name,
+            kind: "stopwatch",
+            entries,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct StopwatchInput {
+    #[serde(deserialize_with = "parse_timestamp")]
+    time: PrimitiveDateTime,
+    /// insert the time at this position instead of appending it
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    position: Option<i32>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_stopwatch_time(
+    state: AppState,
+    competition_id: Path<Id>,
+    query: Query<RedirectInfo>,
+    data: Form<StopwatchInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let StopwatchInput { time, position } = data.0;
+    state
+        .with_connection(move |conn| {
+            load_competition_name(conn, competition_id)?;
+            finish_order::insert_stopwatch_time(conn, competition_id, position, time)?;
+            QueryResult::Ok(())
+        })
+        .await?;
+    Ok(query.0.into_redirect(
+        base_url,
+        format!("competitions/{competition_id}/stopwatch.html"),
+    ))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_stopwatch_time(
+    state: AppState,
+    time_id: Path<Id>,
+    query: Query<RedirectInfo>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let time_id = time_id.0;
+    let competition_id = state
+        .with_connection(move |conn| finish_order::delete_stopwatch_time(conn, time_id))
+        .await?;
+    Ok(query.0.into_redirect(
+        base_url,
+        format!("competitions/{competition_id}/stopwatch.html"),
+    ))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_finish_order(state: AppState, competition_id: Path<Id>) -> Result<Html<String>> {
+    let competition_id = competition_id.0;
+    let (competition_name, entries) = state
+        .with_connection(move |conn| {
+            let competition_name = load_competition_name(conn, competition_id)?;
+            let entries = finish_order_bibs::table
+                .filter(finish_order_bibs::competition_id.eq(competition_id))
+                .order_by((finish_order_bibs::position, finish_order_bibs::id))
+                .select((
+                    finish_order_bibs::id,
+                    finish_order_bibs::position,
+                    finish_order_bibs::bib,
+                ))
+                .load::<(Id, i32, i32)>(conn)?;
+            QueryResult::Ok((competition_name, entries))
+        })
+        .await?;
+    let entries = entries
+        .into_iter()
+        .map(|(id, position, bib)| CaptureEntry {
+            id,
+            position,
+            time: None,
+            bib: Some(bib),
+        })
+        .collect();
+
+    state.render_template(
+        "admin_finish_capture.html",
+        CaptureData {
+            competition_id,
+            competition_name,
+            kind: "finish_order",
+            entries,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct FinishBibInput {
+    bib: i32,
+    /// insert the bib at this position instead of appending it
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    position: Option<i32>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_finish_bib(
+    state: AppState,
+    competition_id: Path<Id>,
+    query: Query<RedirectInfo>,
+    data: Form<FinishBibInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let FinishBibInput { bib, position } = data.0;
+    if bib <= 0 {
+        return Err(Error::InvalidInput("Bib numbers must be positive".into()));
+    }
+    state
+        .with_connection(move |conn| {
+            load_competition_name(conn, competition_id)?;
+            finish_order::insert_finish_bib(conn, competition_id, position, bib)?;
+            QueryResult::Ok(())
+        })
+        .await?;
+    Ok(query.0.into_redirect(
+        base_url,
+        format!("competitions/{competition_id}/finish_order.html"),
+    ))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_finish_bib(
+    state: AppState,
+    bib_id: Path<Id>,
+    query: Query<RedirectInfo>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let bib_id = bib_id.0;
+    let competition_id = state
+        .with_connection(move |conn| finish_order::delete_finish_bib(conn, bib_id))
+        .await?;
+    Ok(query.0.into_redirect(
+        base_url,
+        format!("competitions/{competition_id}/finish_order.html"),
+    ))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_merge(state: AppState, competition_id: Path<Id>) -> Result<Html<String>> {
+    let competition_id = competition_id.0;
+    let (competition_name, rows) = state
+        .with_connection(move |conn| {
+            let competition_name = load_competition_name(conn, competition_id)?;
+            let rows = finish_order::load_merge(conn, competition_id)?;
+            QueryResult::Ok((competition_name, rows))
+        })
+        .await?;
+
+    state.render_template(
+        "admin_finish_merge.html",
+        MergeData {
+            competition_id,
+            competition_name,
+            rows,
+        },
+    )
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn apply_merge(state: AppState, competition_id: Path<Id>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    state
+        .with_connection(move |conn| finish_order::apply_merge(conn, competition_id))
+        .await?;
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/time_records.html"
+    )))
+}
@@ -2,19 +2,59 @@
 //! Additionally it contains the code for the user authentification for
 //! access to the admin pages
 use crate::app_state::{self};
+use axum::response::Redirect;
 use axum::Router;
 use axum_login::login_required;
+use serde::Deserialize;
 use user::auth_session::LoginBackend;
 
+mod awards;
 mod categories;
+mod chips;
+mod clubs;
 mod competitions;
-mod participants;
+mod correction_requests;
+mod duplicates;
+mod finish_order;
+pub(crate) mod participants;
+mod payments;
+mod persons;
 mod races;
+mod result_versions;
+mod series;
 mod special_categories;
 mod starts;
+mod teams;
+mod time_records;
+mod timing_points;
 /// User authentication for the admin pages
 pub mod user;
 
+/// Query parameter of admin actions that return to the page they were
+/// started from
+#[derive(Deserialize)]
+pub(crate) struct RedirectInfo {
+    /// admin page to return to, relative to `/admin/`
+    redirect_to: Option<String>,
+}
+
+impl RedirectInfo {
+    /// The admin page to return to, fails if none was given
+    pub(crate) fn target(&self) -> crate::errors::Result<&str> {
+        self.redirect_to.as_deref().ok_or_else(|| {
+            crate::errors::Error::InvalidInput("Missing redirect_to parameter".into())
+        })
+    }
+
+    /// Redirect to the requested admin page or to `default`
+    pub(crate) fn into_redirect(self, base_url: &str, default: String) -> Redirect {
+        Redirect::to(&format!(
+            "{base_url}/admin/{}",
+            self.redirect_to.unwrap_or(default)
+        ))
+    }
+}
+
 pub fn routes() -> Router<app_state::State> {
     Router::new()
         .nest("/competitions", competitions::routes())
@@ -23,6 +63,19 @@ pub fn routes() -> Router<app_state::State> {
         .merge(starts::routes())
         .merge(categories::routes())
         .merge(special_categories::routes())
+        .merge(time_records::routes())
+        .merge(finish_order::routes())
+        .merge(chips::routes())
+        .merge(timing_points::routes())
+        .merge(teams::routes())
+        .merge(clubs::routes())
+        .merge(series::routes())
+        .merge(persons::routes())
+        .merge(result_versions::routes())
+        .merge(correction_requests::routes())
+        .merge(awards::routes())
+        .merge(duplicates::routes())
+        .merge(payments::routes())
         .route_layer(login_required!(
             LoginBackend,
             login_url = "/admin/login.html"
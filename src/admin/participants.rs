@@ -1,8 +1,11 @@
 //! Admin page setup for participants
+use super::RedirectInfo;
 use crate::app_state::{self, AppState};
 use crate::database::schema::{
-    categories, participants, participants_in_special_category, races, special_categories, starts,
+    bib_numbers, categories, participants, participants_in_special_category, races,
+    special_categories, starts,
 };
+use crate::database::shared_models::ParticipantStatus;
 use crate::database::Id;
 use crate::errors::{Error, Result};
 use crate::registration::{ParticipantForForm, ParticipantWithSpecialCategories, RegistrationForm};
@@ -28,6 +31,10 @@ pub fn routes() -> Router<app_state::State> {
             axum::routing::get(render_edit_participant),
         )
         .route("/{participant_id}", axum::routing::post(update_participant))
+        .route(
+            "/{participant_id}/status",
+            axum::routing::post(update_participant_status),
+        )
         .route(
             "/add_participant.html",
             axum::routing::get(render_add_participant),
@@ -60,16 +67,26 @@ pub fn routes() -> Router<app_state::State> {
         .nest("/participants", participants_routes)
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = participants)]
 pub struct Participant {
     pub id: Id,
+    #[diesel(select_expression = bib_numbers::table
+        .filter(bib_numbers::participant_id.eq(participants::id))
+        .select(bib_numbers::bib)
+        .single_value())]
+    bib: Option<i32>,
     last_name: String,
     first_name: String,
     club: Option<String>,
     birth_year: i32,
     consent_agb: bool,
+    #[diesel(select_expression = categories::label)]
     category: String,
+    #[diesel(select_expression = races::name)]
     race: String,
+    status: ParticipantStatus,
+    status_reason: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -78,12 +95,36 @@ struct ParticipantListData {
     competition_id: Id,
     redirect_to: String,
     specifier: String,
+    /// the status the list is filtered by
+    status: Option<ParticipantStatus>,
+    /// all possible status values
+    statuses: [ParticipantStatus; 7],
+}
+
+/// Optional filter for the participant lists
+#[derive(Deserialize)]
+struct StatusFilter {
+    #[serde(default, deserialize_with = "parse_optional_status")]
+    status: Option<ParticipantStatus>,
+}
+
+fn parse_optional_status<'de, D>(d: D) -> Result<Option<ParticipantStatus>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s = String::deserialize(d)?;
+    if s.is_empty() {
+        Ok(None)
+    } else {
+        s.parse().map(Some).map_err(serde::de::Error::custom)
+    }
 }
 
 #[axum::debug_handler(state = app_state::State)]
 async fn list_participants_for_competition(
     state: AppState,
     comp_id: Path<Id>,
+    filter: Query<StatusFilter>,
 ) -> Result<Html<String>> {
     let competition_name: String = todo!("Get the competition name here");
 
@@ -93,12 +134,17 @@ async fn list_participants_for_competition(
         comp_id.0,
         format!("competitions/{}/participants.html", comp_id.0),
         competition_name,
+        filter.0.status,
     )
     .await
 }
 
 #[axum::debug_handler(state = app_state::State)]
-async fn list_participants_for_race(state: AppState, race_id: Path<Id>) -> Result<Html<String>> {
+async fn list_participants_for_race(
+    state: AppState,
+    race_id: Path<Id>,
+    filter: Query<StatusFilter>,
+) -> Result<Html<String>> {
     let (competition_id, race_name): (Id, String) = todo!("Load the competition_id and race_name");
     list_participants_for_filter(
         state,
@@ -106,12 +152,17 @@ async fn list_participants_for_race(state: AppState, race_id: Path<Id>) -> Resul
         competition_id,
         format!("races/{}/participants.html", race_id.0),
         race_name,
+        filter.0.status,
     )
     .await
 }
 
 #[axum::debug_handler(state = app_state::State)]
-async fn list_participants_for_start(state: AppState, start_id: Path<Id>) -> Result<Html<String>> {
+async fn list_participants_for_start(
+    state: AppState,
+    start_id: Path<Id>,
+    filter: Query<StatusFilter>,
+) -> Result<Html<String>> {
     let (competition_id, start_name): (Id, String) =
         todo!("Load the competition_id and the start name");
 
@@ -121,6 +172,7 @@ async fn list_participants_for_start(state: AppState, start_id: Path<Id>) -> Res
         competition_id,
         format!("starts/{}/participants.html", start_id.0),
         start_name,
+        filter.0.status,
     )
     .await
 }
@@ -129,6 +181,7 @@ async fn list_participants_for_start(state: AppState, start_id: Path<Id>) -> Res
 async fn list_participants_for_category(
     state: AppState,
     category_id: Path<Id>,
+    filter: Query<StatusFilter>,
 ) -> Result<Html<String>> {
     let (competition_id, race_name, category_label): (Id, String, String) =
         todo!("Load the competition id, race_name and category label");
@@ -139,6 +192,7 @@ async fn list_participants_for_category(
         competition_id,
         format!("categories/{}/participants.html", category_id.0),
         format!("{category_label} ({race_name})"),
+        filter.0.status,
     )
     .await
 }
@@ -147,6 +201,7 @@ async fn list_participants_for_category(
 async fn list_participants_for_special_categories(
     state: AppState,
     special_id: Path<Id>,
+    filter: Query<StatusFilter>,
 ) -> Result<Html<String>> {
     let (competition_id, special_label): (Id, String) =
         todo!("Load the competition id and the special category label");
@@ -162,20 +217,18 @@ async fn list_participants_for_special_categories(
         competition_id,
         format!("special_categories/{}/participants.html", special_id.0),
         special_label,
+        filter.0.status,
     )
     .await
 }
 
-#[expect(
-    clippy::unused_async,
-    reason = "Resolving the todo will make this function async"
-)]
 async fn list_participants_for_filter<F>(
     state: AppState,
     filter: F,
     competition_id: Id,
     redirect_to: String,
     specifier: String,
+    status: Option<ParticipantStatus>,
 ) -> Result<Html<String>>
 where
     F: BoxableExpression<
@@ -191,7 +244,24 @@ where
         + 'static,
     F::IsAggregate: MixedAggregates<is_aggregate::No, Output = is_aggregate::No>,
 {
-    let participants: Vec<Participant> = todo!("Load participants");
+    let participants = state
+        .with_connection(move |conn| {
+            let mut query = participants::table
+                .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+                .filter(filter)
+                .order_by((
+                    participants::last_name,
+                    participants::first_name,
+                    participants::id,
+                ))
+                .select(Participant::as_select())
+                .into_boxed();
+            if let Some(status) = status {
+                query = query.filter(participants::status.eq(status));
+            }
+            query.load(conn)
+        })
+        .await?;
     state.render_template(
         "admin_participant_list.html",
         ParticipantListData {
@@ -199,15 +269,12 @@ where
             competition_id,
             redirect_to,
             specifier,
+            status,
+            statuses: ParticipantStatus::ALL,
         },
     )
 }
 
-#[derive(Deserialize)]
-struct RedirectInfo {
-    redirect_to: String,
-}
-
 #[axum::debug_handler(state = app_state::State)]
 async fn delete_participant(
     state: AppState,
@@ -224,16 +291,71 @@ async fn delete_participant(
     } else {
         Ok(Redirect::to(&format!(
             "{base_url}/admin/{}",
-            query.redirect_to
+            query.target()?
         )))
     }
 }
 
+#[derive(Deserialize)]
+struct StatusFormInput {
+    status: ParticipantStatus,
+    #[serde(default)]
+    reason: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn update_participant_status(
+    state: AppState,
+    participant_id: Path<Id>,
+    query: Query<RedirectInfo>,
+    data: Form<StatusFormInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let participant_id = participant_id.0;
+    let StatusFormInput { status, reason } = data.0;
+    let reason = Some(reason.trim().to_owned()).filter(|r| !r.is_empty());
+    if status == ParticipantStatus::Dsq && reason.is_none() {
+        return Err(Error::InvalidInput(String::from(
+            "A disqualification requires a reason",
+        )));
+    }
+    let competition_id = state
+        .with_connection(move |conn| {
+            let count = diesel::update(participants::table.find(participant_id))
+                .set((
+                    participants::status.eq(status),
+                    participants::status_reason.eq(reason),
+                ))
+                .execute(conn)?;
+            if count != 1 {
+                return Ok(None);
+            }
+            participants::table
+                .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+                .filter(participants::id.eq(participant_id))
+                .select(races::competition_id)
+                .first::<Id>(conn)
+                .map(Some)
+        })
+        .await?;
+    let Some(competition_id) = competition_id else {
+        return Err(Error::NotFound(format!(
+            "Participant with id {participant_id} not found"
+        )));
+    };
+    // a changed status might change the ranking
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/{}",
+        query.target()?
+    )))
+}
+
 #[expect(
     clippy::unused_async,
     reason = "Implementing the todo will make the function async"
 )]
-async fn load_participant_by_id(
+pub(crate) async fn load_participant_by_id(
     state: &AppState,
     participant_id: Id,
 ) -> Result<(ParticipantWithSpecialCategories, Id)> {
@@ -245,13 +367,20 @@ async fn render_edit_participant(
     state: AppState,
     participant_id: Path<Id>,
 ) -> Result<Html<String>> {
-    let (participant, competition_id) = load_participant_by_id(&state, participant_id.0).await?;
+    let participant_id = participant_id.0;
+    let (mut participant, competition_id) = load_participant_by_id(&state, participant_id).await?;
+    participant.id = Some(participant_id);
+    participant.time_adjustments = state
+        .with_connection(move |conn| {
+            crate::database::time_records::load_time_adjustments(conn, participant_id)
+        })
+        .await?;
     crate::registration::render_registration_page_with_optional_data(
         state,
         competition_id,
         Some(participant),
         "edit_participant",
-        format!("admin/participants/{}", participant_id.0),
+        format!("admin/participants/{participant_id}"),
     )
     .await
 }
@@ -266,11 +395,11 @@ async fn update_participant(
     let base_url = state.base_url();
     let (_participant, competition_id) = load_participant_by_id(&state, participant_id.0).await?;
     data.0
-        .into_database(&state, competition_id, Some(participant_id.0))
+        .into_database(&state, competition_id, Some(participant_id.0), true)
         .await?;
     Ok(Redirect::to(&format!(
         "{base_url}/admin/{}",
-        query.redirect_to
+        query.target()?
     )))
 }
 
@@ -282,8 +411,10 @@ async fn render_add_participant(
     let mut participant = ParticipantWithSpecialCategories {
         participant: ParticipantForForm::default(),
         special_categories: Vec::new(),
+        id: None,
+        time_adjustments: Vec::new(),
     };
-    let redirect_parts = redirect.redirect_to.split("/").collect::<Vec<_>>();
+    let redirect_parts = redirect.target()?.split("/").collect::<Vec<_>>();
     let id = redirect_parts[1]
         .parse::<Id>()
         .map_err(|e| Error::InvalidInput(e.to_string()))?;
@@ -327,7 +458,8 @@ async fn render_add_participant(
         "new_participant",
         format!(
             "admin/competitions/{}/add_participant?redirect_to={}",
-            competition_id, redirect.redirect_to
+            competition_id,
+            redirect.target()?
         ),
     )
     .await
@@ -341,9 +473,11 @@ async fn add_participant(
     form: Form<RegistrationForm>,
 ) -> Result<Redirect> {
     let base_url = state.base_url();
-    form.0.into_database(&state, competition_id.0, None).await?;
+    form.0
+        .into_database(&state, competition_id.0, None, true)
+        .await?;
     Ok(Redirect::to(&format!(
         "{base_url}/admin/{}",
-        redirect.redirect_to
+        redirect.target()?
     )))
 }
@@ -0,0 +1,385 @@
+//! Admin page setup for entry fees and payments
+//!
+//! The fee schedule is configured per race. The payments page lists the
+//! amount due of each participant of a competition and is used to record
+//! payments and refunds. Participants registered before the fee schedule was
+//! set up get their amount on request.
+use super::competitions::parse_optional_timestamp;
+use crate::app_state::{self, AppState};
+use crate::database::schema::{
+    bib_numbers, categories, competitions, participants, payments, race_fees, races, starts,
+};
+use crate::database::shared_models::{Competition, FeeSchedule, PaymentStatus, Race};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::{Path, Query};
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use time::PrimitiveDateTime;
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/races/{race_id}/fees.html",
+            axum::routing::get(render_fee_schedule),
+        )
+        .route(
+            "/races/{race_id}/fees",
+            axum::routing::post(update_fee_schedule),
+        )
+        .route(
+            "/competitions/{competition_id}/payments.html",
+            axum::routing::get(list_payments),
+        )
+        .route(
+            "/competitions/{competition_id}/payments/{participant_id}",
+            axum::routing::post(update_payment),
+        )
+        .route(
+            "/competitions/{competition_id}/missing_fees",
+            axum::routing::post(assign_missing_fees),
+        )
+}
+
+/// Data used to render the fee schedule
+///
+/// See `templates/admin_fee_schedule.html` for the relevant template
+#[derive(Serialize)]
+struct FeeScheduleData {
+    race: Race,
+    schedule: Option<FeeSchedule>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_fee_schedule(state: AppState, Path(race_id): Path<Id>) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let race = races::table
+                .find(race_id)
+                .select(Race::as_select())
+                .first(conn)?;
+            let schedule = race_fees::table
+                .find(race_id)
+                .select(FeeSchedule::as_select())
+                .first(conn)
+                .optional()?;
+            QueryResult::Ok(FeeScheduleData { race, schedule })
+        })
+        .await?;
+    state.render_template("admin_fee_schedule.html", data)
+}
+
+/// Parse an amount like `25`, `25.5` or `25,50` into cents, empty fields
+/// are `None`
+fn parse_amount(key: &str, value: &str) -> Result<Option<i32>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || Error::InvalidInput(format!("Invalid amount for {key}: {value}"));
+    let (units, fraction) = value.split_once(['.', ',']).unwrap_or((value, ""));
+    if fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let units = units.parse::<u16>().map_err(|_| invalid())?;
+    let cents = format!("{fraction:0<2}")
+        .parse::<u8>()
+        .map_err(|_| invalid())?;
+    Ok(Some(i32::from(units) * 100 + i32::from(cents)))
+}
+
+#[derive(Deserialize)]
+struct FeeScheduleInput {
+    /// an empty base fee removes the fee schedule
+    base_fee: String,
+    #[serde(default)]
+    late_surcharge: String,
+    #[serde(default, deserialize_with = "parse_optional_timestamp")]
+    late_from: Option<PrimitiveDateTime>,
+    #[serde(default)]
+    youth_discount: String,
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    youth_max_age: Option<i32>,
+    #[serde(default)]
+    club_discount: String,
+}
+
+/// Store the fee schedule of a race
+///
+/// Existing registrations keep their amount due
+#[axum::debug_handler(state = app_state::State)]
+async fn update_fee_schedule(
+    state: AppState,
+    Path(race_id): Path<Id>,
+    Form(data): Form<FeeScheduleInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let base_fee = parse_amount("base_fee", &data.base_fee)?;
+    let late_surcharge = parse_amount("late_surcharge", &data.late_surcharge)?.unwrap_or(0);
+    let youth_discount = parse_amount("youth_discount", &data.youth_discount)?.unwrap_or(0);
+    let club_discount = parse_amount("club_discount", &data.club_discount)?.unwrap_or(0);
+    let late_from = data.late_from;
+    let youth_max_age = data.youth_max_age;
+    let competition_id = state
+        .with_connection(move |conn| {
+            let competition_id = races::table
+                .find(race_id)
+                .select(races::competition_id)
+                .first::<Id>(conn)?;
+            let Some(base_fee) = base_fee else {
+                diesel::delete(race_fees::table.find(race_id)).execute(conn)?;
+                return Ok(competition_id);
+            };
+            let values = (
+                race_fees::base_fee.eq(base_fee),
+                race_fees::late_surcharge.eq(late_surcharge),
+                race_fees::late_from.eq(late_from),
+                race_fees::youth_discount.eq(youth_discount),
+                race_fees::youth_max_age.eq(youth_max_age),
+                race_fees::club_discount.eq(club_discount),
+            );
+            diesel::insert_into(race_fees::table)
+                .values((race_fees::race_id.eq(race_id), values))
+                .on_conflict(race_fees::race_id)
+                .do_update()
+                .set(values)
+                .execute(conn)?;
+            QueryResult::Ok(competition_id)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/races.html"
+    )))
+}
+
+/// The payment of a participant as shown on the payments page
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = payments)]
+struct PaymentEntry {
+    participant_id: Id,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = participants::first_name)]
+    first_name: String,
+    #[diesel(select_expression = participants::last_name)]
+    last_name: String,
+    #[diesel(select_expression = participants::club)]
+    club: Option<String>,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = categories::label)]
+    category: String,
+    /// in cents
+    amount_due: i32,
+    status: PaymentStatus,
+    method: Option<String>,
+    reference: Option<String>,
+    paid_at: Option<PrimitiveDateTime>,
+}
+
+#[derive(Deserialize)]
+struct PaymentFilter {
+    /// only show payments with this status, empty for all payments
+    #[serde(default)]
+    status: String,
+}
+
+/// Data used to render the payments page
+///
+/// See `templates/admin_payments.html` for the relevant template
+#[derive(Serialize)]
+struct PaymentsData {
+    competition: Competition,
+    payments: Vec<PaymentEntry>,
+    /// the currently applied filter
+    filter: Option<PaymentStatus>,
+    statuses: [PaymentStatus; 3],
+    /// sum of the listed open amounts in cents
+    open_amount: i64,
+    /// sum of the listed paid amounts in cents
+    paid_amount: i64,
+    /// number of participants in races with a fee schedule without a payment
+    missing_fees: i64,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_payments(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+    Query(filter): Query<PaymentFilter>,
+) -> Result<Html<String>> {
+    let filter = if filter.status.is_empty() {
+        None
+    } else {
+        Some(
+            filter
+                .status
+                .parse::<PaymentStatus>()
+                .map_err(Error::InvalidInput)?,
+        )
+    };
+    let (competition, payments, missing_fees) = state
+        .with_connection(move |conn| {
+            let competition = competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)?;
+            let missing_fees = participants::table
+                .inner_join(categories::table.inner_join(
+                    starts::table.inner_join(races::table.inner_join(race_fees::table)),
+                ))
+                .left_join(payments::table)
+                .filter(races::competition_id.eq(competition_id))
+                .filter(payments::participant_id.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+            let mut query = payments::table
+                .inner_join(
+                    participants::table
+                        .inner_join(
+                            categories::table.inner_join(starts::table.inner_join(races::table)),
+                        )
+                        .left_join(bib_numbers::table),
+                )
+                .filter(races::competition_id.eq(competition_id))
+                .order_by((
+                    participants::last_name,
+                    participants::first_name,
+                    participants::id,
+                ))
+                .select(PaymentEntry::as_select())
+                .into_boxed();
+            if let Some(status) = filter {
+                query = query.filter(payments::status.eq(status));
+            }
+            let payments = query.load(conn)?;
+            QueryResult::Ok((competition, payments, missing_fees))
+        })
+        .await?;
+    let sum = |status| {
+        payments
+            .iter()
+            .filter(|p| p.status == status)
+            .map(|p| i64::from(p.amount_due))
+            .sum()
+    };
+    let open_amount = sum(PaymentStatus::Open);
+    let paid_amount = sum(PaymentStatus::Paid);
+    state.render_template(
+        "admin_payments.html",
+        PaymentsData {
+            competition,
+            payments,
+            filter,
+            statuses: PaymentStatus::ALL,
+            open_amount,
+            paid_amount,
+            missing_fees,
+        },
+    )
+}
+
+/// Compute the amount due for all participants registered without a fee,
+/// e.g. before the fee schedule was set up
+#[axum::debug_handler(state = app_state::State)]
+async fn assign_missing_fees(state: AppState, Path(competition_id): Path<Id>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    state
+        .with_connection(move |conn| {
+            crate::database::fees::assign_missing_fees(conn, competition_id)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/payments.html"
+    )))
+}
+
+#[derive(Deserialize)]
+struct PaymentInput {
+    status: PaymentStatus,
+    /// e.g. cash or bank transfer
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_string"
+    )]
+    method: Option<String>,
+    /// e.g. the reference of the bank transfer
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_string"
+    )]
+    reference: Option<String>,
+    /// the filter of the payments page, kept after the update
+    #[serde(default)]
+    filter: String,
+}
+
+/// Record a payment or a refund
+///
+/// The time of the payment is set when the status changes to paid and
+/// cleared when it is reset to open
+#[axum::debug_handler(state = app_state::State)]
+async fn update_payment(
+    state: AppState,
+    Path((competition_id, participant_id)): Path<(Id, Id)>,
+    Form(data): Form<PaymentInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let PaymentInput {
+        status,
+        method,
+        reference,
+        filter,
+    } = data;
+    let updated = state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let current = payments::table
+                    .inner_join(participants::table.inner_join(
+                        categories::table.inner_join(starts::table.inner_join(races::table)),
+                    ))
+                    .filter(payments::participant_id.eq(participant_id))
+                    .filter(races::competition_id.eq(competition_id))
+                    .select((payments::status, payments::paid_at))
+                    .first::<(PaymentStatus, Option<PrimitiveDateTime>)>(conn)
+                    .optional()?;
+                let Some((current_status, paid_at)) = current else {
+                    return Ok(false);
+                };
+                let paid_at = match status {
+                    PaymentStatus::Open => None,
+                    PaymentStatus::Paid if current_status != PaymentStatus::Paid => {
+                        Some(crate::database::registrations::now())
+                    }
+                    PaymentStatus::Paid | PaymentStatus::Refunded => paid_at,
+                };
+                diesel::update(payments::table.find(participant_id))
+                    .set((
+                        payments::status.eq(status),
+                        payments::method.eq(method),
+                        payments::reference.eq(reference),
+                        payments::paid_at.eq(paid_at),
+                    ))
+                    .execute(conn)?;
+                QueryResult::Ok(true)
+            })
+        })
+        .await?;
+    if !updated {
+        return Err(Error::NotFound(format!(
+            "No entry fee for participant {participant_id} in competition {competition_id}"
+        )));
+    }
+    let filter = filter
+        .parse::<PaymentStatus>()
+        .map(|status| format!("?status={}", status.as_str()))
+        .unwrap_or_default();
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/payments.html{filter}"
+    )))
+}
@@ -0,0 +1,238 @@
+//! Admin page setup to fix mismatched person identities
+//!
+//! Participants are linked to persons automatically. Two persons that are
+//! the same athlete can be merged, a participant that was linked to the
+//! wrong person can be split off into a new person.
+use crate::app_state::{self, AppState};
+use crate::database::schema::{categories, competitions, participants, persons, races, starts};
+use crate::database::shared_models::Competition;
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::{Path, Query};
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::dsl;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route("/persons.html", axum::routing::get(list_persons))
+        .route("/persons/link", axum::routing::post(link_participants))
+        .route(
+            "/persons/{person_id}/edit.html",
+            axum::routing::get(show_person),
+        )
+        .route(
+            "/persons/{person_id}/merge",
+            axum::routing::post(merge_person),
+        )
+        .route(
+            "/persons/{person_id}/split",
+            axum::routing::post(split_participant),
+        )
+}
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = persons)]
+struct PersonData {
+    id: Id,
+    first_name: String,
+    last_name: String,
+    birth_year: i32,
+    email: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ListPersonsData {
+    persons: Vec<(PersonData, i64)>,
+    query: String,
+    /// number of participants that are not linked to a person yet, e.g.
+    /// registrations from before persons were introduced
+    unlinked: i64,
+}
+
+#[derive(Deserialize)]
+struct PersonSearch {
+    #[serde(default)]
+    q: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_persons(state: AppState, search: Query<PersonSearch>) -> Result<Html<String>> {
+    let query = search.0.q;
+    // `%` and `_` are wildcards for `LIKE`, so they need to be escaped
+    let key = crate::database::normalize_name(&query)
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    let (persons, unlinked) = state
+        .with_connection(move |conn| {
+            let persons = persons::table
+                .left_join(participants::table)
+                .filter(persons::name_key.like(format!("%{key}%")).escape('\\'))
+                .group_by(persons::id)
+                .order_by((persons::name_key, persons::birth_year, persons::id))
+                .select((
+                    PersonData::as_select(),
+                    dsl::count(participants::id.nullable()),
+                ))
+                .limit(200)
+                .load::<(PersonData, i64)>(conn)?;
+            let unlinked = participants::table
+                .filter(participants::person_id.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+            QueryResult::Ok((persons, unlinked))
+        })
+        .await?;
+    state.render_template(
+        "admin_list_persons.html",
+        ListPersonsData {
+            persons,
+            query,
+            unlinked,
+        },
+    )
+}
+
+/// Link all participants that are not linked to a person yet
+///
+/// New registrations are linked when they are stored, this is only needed
+/// for older registrations
+#[axum::debug_handler(state = app_state::State)]
+async fn link_participants(state: AppState) -> Result<Redirect> {
+    let base_url = state.base_url();
+    state
+        .with_connection(crate::database::persons::link_participants)
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/persons.html")))
+}
+
+#[derive(Queryable, Serialize)]
+struct ParticipationData {
+    participant_id: Id,
+    first_name: String,
+    last_name: String,
+    email: Option<String>,
+    competition: Competition,
+    race: String,
+    category: String,
+}
+
+#[derive(Serialize)]
+struct PersonDetailData {
+    person: PersonData,
+    participations: Vec<ParticipationData>,
+    /// other persons with the same birth year, these are merge candidates
+    candidates: Vec<PersonData>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn show_person(state: AppState, person_id: Path<Id>) -> Result<Html<String>> {
+    let person_id = person_id.0;
+    let data = state
+        .with_connection(move |conn| {
+            let person = persons::table
+                .find(person_id)
+                .select(PersonData::as_select())
+                .first(conn)?;
+            let participations = participants::table
+                .inner_join(categories::table.inner_join(
+                    starts::table.inner_join(races::table.inner_join(competitions::table)),
+                ))
+                .filter(participants::person_id.eq(person_id))
+                .order_by((competitions::date.desc(), competitions::id.desc()))
+                .select((
+                    participants::id,
+                    participants::first_name,
+                    participants::last_name,
+                    participants::email,
+                    Competition::as_select(),
+                    races::name,
+                    categories::label,
+                ))
+                .load::<ParticipationData>(conn)?;
+            let candidates = persons::table
+                .filter(persons::birth_year.eq(person.birth_year))
+                .filter(persons::id.ne(person_id))
+                .order_by((persons::name_key, persons::id))
+                .select(PersonData::as_select())
+                .load(conn)?;
+            QueryResult::Ok(PersonDetailData {
+                person,
+                participations,
+                candidates,
+            })
+        })
+        .await?;
+    state.render_template("admin_person.html", data)
+}
+
+#[derive(Deserialize)]
+struct MergeInput {
+    /// the person that is merged into the current one
+    other_person_id: Id,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn merge_person(
+    state: AppState,
+    person_id: Path<Id>,
+    data: Form<MergeInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let person_id = person_id.0;
+    let other_person_id = data.other_person_id;
+    if person_id == other_person_id {
+        return Err(Error::InvalidInput(
+            "A person cannot be merged with itself".into(),
+        ));
+    }
+    state
+        .with_connection(move |conn| {
+            crate::database::persons::merge_persons(conn, person_id, other_person_id)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/persons/{person_id}/edit.html"
+    )))
+}
+
+#[derive(Deserialize)]
+struct SplitInput {
+    participant_id: Id,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn split_participant(
+    state: AppState,
+    person_id: Path<Id>,
+    data: Form<SplitInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let person_id = person_id.0;
+    let participant_id = data.participant_id;
+    let new_person_id = state
+        .with_connection(move |conn| {
+            let belongs_to_person = diesel::select(dsl::exists(
+                participants::table
+                    .find(participant_id)
+                    .filter(participants::person_id.eq(person_id)),
+            ))
+            .get_result::<bool>(conn)?;
+            if !belongs_to_person {
+                return Ok(None);
+            }
+            crate::database::persons::split_participant(conn, participant_id).map(Some)
+        })
+        .await?
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "Participant {participant_id} does not belong to person {person_id}"
+            ))
+        })?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/persons/{new_person_id}/edit.html"
+    )))
+}
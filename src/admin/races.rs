@@ -1,7 +1,8 @@
 //! Admin page setup for races
 use crate::app_state::{self, AppState};
+use crate::database::shared_models::RankingMode;
 use crate::database::Id;
-use crate::errors::Result;
+use crate::errors::{Error, Result};
 use axum::extract::Path;
 use axum::response::{Html, Redirect};
 use axum::{Form, Router};
@@ -35,6 +36,10 @@ struct RaceData {
     starts: i64,
     participants: i64,
     special_categories: i64,
+    /// maximal number of confirmed participants, `None` for no limit
+    capacity: Option<i32>,
+    /// number of participants on the waitlist
+    waitlisted: i64,
 }
 
 #[derive(Serialize)]
@@ -68,6 +73,7 @@ async fn render_new_race(state: AppState, competition_id: Path<Id>) -> Result<Ht
             race: None,
             title: state.translation("new_race"),
             target_url: format!("competitions/{}/new_race", competition_id.0),
+            ranking_modes: RankingMode::ALL,
         },
     )
 }
@@ -87,6 +93,13 @@ struct EditRaceData {
     id: Id,
     name: String,
     competition_id: Id,
+    ranking_mode: RankingMode,
+    team_size: Option<i32>,
+    distance_meters: Option<i32>,
+    elevation_gain_meters: Option<i32>,
+    surface: Option<String>,
+    course_description: Option<String>,
+    capacity: Option<i32>,
 }
 
 #[derive(Serialize)]
@@ -94,6 +107,8 @@ struct RaceFormData {
     race: Option<EditRaceData>,
     title: String,
     target_url: String,
+    /// all possible ranking modes
+    ranking_modes: [RankingMode; 2],
 }
 
 #[axum::debug_handler(state = app_state::State)]
@@ -105,6 +120,7 @@ async fn render_edit_race(state: AppState, race_id: Path<Id>) -> Result<Html<Str
             title: state.translation("edit_race"),
             target_url: format!("races/{}", race_id.0),
             race: Some(race_data),
+            ranking_modes: RankingMode::ALL,
         },
     )
 }
@@ -112,6 +128,55 @@ async fn render_edit_race(state: AppState, race_id: Path<Id>) -> Result<Html<Str
 #[derive(Deserialize)]
 struct RaceFormInput {
     name: String,
+    /// whether participants are ranked by gun or net time
+    #[serde(default)]
+    ranking_mode: RankingMode,
+    /// number of members per team, only set for relay races
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    team_size: Option<i32>,
+    /// length of the course in meters
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    distance_meters: Option<i32>,
+    /// total ascent of the course in meters
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    elevation_gain_meters: Option<i32>,
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_string"
+    )]
+    surface: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_string"
+    )]
+    course_description: Option<String>,
+    /// maximal number of confirmed participants, further registrations are
+    /// put on the waitlist
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    capacity: Option<i32>,
+}
+
+impl RaceFormInput {
+    fn is_valid(&self) -> Result<()> {
+        if self.capacity.is_some_and(|capacity| capacity < 0) {
+            return Err(Error::InvalidInput(String::from(
+                "The capacity cannot be negative",
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[axum::debug_handler(state = app_state::State)]
@@ -121,6 +186,7 @@ async fn update_race(
     data: Form<RaceFormInput>,
 ) -> Result<Redirect> {
     let base_url = state.base_url();
+    data.is_valid()?;
     let competition_id: Id = todo!("Update the race + get the competition id");
     Ok(Redirect::to(&format!(
         "{base_url}/admin/competitions/{}/races.html",
@@ -135,6 +201,7 @@ async fn new_race(
     data: Form<RaceFormInput>,
 ) -> Result<Redirect> {
     let base_url = state.base_url();
+    data.is_valid()?;
     todo!("Create a new race");
     Ok(Redirect::to(&format!(
         "{base_url}/admin/competitions/{}/races.html",
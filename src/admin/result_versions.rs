@@ -0,0 +1,158 @@
+//! Admin page setup for publishing the results of a race
+//!
+//! Publishing freezes the current ranking as a new version. Later changes
+//! of times only show up in the public results once a new version with a
+//! note explaining the changes is published
+use crate::admin::user::auth_session::AuthSession;
+use crate::app_state::{self, AppState};
+use crate::database::result_versions::NewResultVersion;
+use crate::database::schema::races;
+use crate::database::shared_models::Race;
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::result_versions::{ResultChange, VersionWithChanges};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/races/{race_id}/result_versions.html",
+            axum::routing::get(list_result_versions),
+        )
+        .route(
+            "/races/{race_id}/result_versions",
+            axum::routing::post(publish_results),
+        )
+}
+
+/// Data used to render the publishing page
+///
+/// See `templates/admin_result_versions.html` for the relevant template
+#[derive(Serialize)]
+struct ResultVersionsData {
+    race: Race,
+    /// all published versions, the latest first
+    versions: Vec<VersionWithChanges>,
+    /// whether the live results differ from the latest version
+    unpublished: bool,
+    /// changes of the live results compared to the latest version
+    pending_changes: Vec<ResultChange>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_result_versions(state: AppState, race_id: Path<Id>) -> Result<Html<String>> {
+    let race_id = race_id.0;
+    let (race, live, versions) = state
+        .with_connection(move |conn| {
+            let race = races::table
+                .find(race_id)
+                .select(Race::as_select())
+                .first(conn)?;
+            let live = crate::result_versions::live_results(conn, race_id)
+                .optional()?
+                .map(|(_, results)| results);
+            let versions = crate::database::result_versions::load_versions(conn, race_id)?;
+            QueryResult::Ok((race, live, versions))
+        })
+        .await?;
+    let live = live.map(serde_json::to_value).transpose()?;
+    let latest = versions
+        .first()
+        .map(|v| serde_json::from_str::<serde_json::Value>(&v.results))
+        .transpose()?;
+    let (unpublished, pending_changes) = match (&latest, &live) {
+        (Some(latest), Some(live)) => (
+            latest != live,
+            crate::result_versions::result_changes(latest, live),
+        ),
+        (None, Some(_)) => (true, Vec::new()),
+        (_, None) => (false, Vec::new()),
+    };
+    let versions = versions
+        .into_iter()
+        .map(VersionWithChanges::parse)
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    state.render_template(
+        "admin_result_versions.html",
+        ResultVersionsData {
+            race,
+            versions,
+            unpublished,
+            pending_changes,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct PublishInput {
+    /// why the results are published again, required after the first version
+    #[serde(default)]
+    note: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn publish_results(
+    state: AppState,
+    auth_session: AuthSession,
+    race_id: Path<Id>,
+    data: Form<PublishInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let race_id = race_id.0;
+    let note = data.0.note.trim().to_owned();
+    let published_by = auth_session
+        .user
+        .map(|user| user.name)
+        .ok_or_else(|| Error::InvalidInput(String::from("Not logged in")))?;
+    let competition_id = state
+        .with_connection(move |conn| {
+            // the latest version must not change between comparing and inserting
+            QueryResult::Ok(conn.transaction(|conn| {
+                let (competition_id, live) = crate::result_versions::live_results(conn, race_id)?;
+                let live = serde_json::to_value(live)?;
+                let latest = match crate::database::result_versions::latest_version(conn, race_id)? {
+                    Some(version) => {
+                        crate::database::result_versions::load_version(conn, race_id, version)?
+                    }
+                    None => None,
+                };
+                let changes = match latest {
+                    Some(latest) => {
+                        let latest = serde_json::from_str::<serde_json::Value>(&latest.results)?;
+                        if latest == live {
+                            return Err(Error::InvalidInput(format!(
+                                "The results of race {race_id} did not change since the last version"
+                            )));
+                        }
+                        if note.is_empty() {
+                            return Err(Error::InvalidInput(String::from(
+                                "Publishing changed results needs a note",
+                            )));
+                        }
+                        crate::result_versions::result_changes(&latest, &live)
+                    }
+                    None => Vec::new(),
+                };
+                crate::database::result_versions::publish_version(
+                    conn,
+                    &NewResultVersion {
+                        race_id,
+                        results: &serde_json::to_string(&live)?,
+                        note: &note,
+                        changes: &serde_json::to_string(&changes)?,
+                        published_by: &published_by,
+                    },
+                )?;
+                Ok(competition_id)
+            }))
+        })
+        .await??;
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/races/{race_id}/result_versions.html"
+    )))
+}
@@ -0,0 +1,216 @@
+//! Admin page setup for series of competitions
+use crate::app_state::{self, AppState};
+use crate::database::schema::{competitions, series, series_competitions};
+use crate::database::shared_models::{Competition, Series};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::dsl;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route("/series.html", axum::routing::get(list_series))
+        .route("/series", axum::routing::post(new_series))
+        .route(
+            "/series/{series_id}/delete.html",
+            axum::routing::get(delete_series),
+        )
+        .route(
+            "/series/{series_id}/competitions.html",
+            axum::routing::get(list_series_competitions),
+        )
+        .route(
+            "/series/{series_id}/competitions",
+            axum::routing::post(add_series_competition),
+        )
+        .route(
+            "/series/{series_id}/competitions/{competition_id}/delete.html",
+            axum::routing::get(remove_series_competition),
+        )
+}
+
+#[derive(Serialize)]
+struct SeriesData {
+    #[serde(flatten)]
+    series: Series,
+    competitions: i64,
+}
+
+#[derive(Serialize)]
+struct ListSeriesData {
+    series: Vec<SeriesData>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_series(state: AppState) -> Result<Html<String>> {
+    let series = state
+        .with_connection(|conn| {
+            series::table
+                .left_join(series_competitions::table)
+                .group_by(series::id)
+                .order_by(series::name)
+                .select((
+                    Series::as_select(),
+                    dsl::count(series_competitions::competition_id.nullable()),
+                ))
+                .load::<(Series, i64)>(conn)
+        })
+        .await?
+        .into_iter()
+        .map(|(series, competitions)| SeriesData {
+            series,
+            competitions,
+        })
+        .collect();
+    state.render_template("admin_list_series.html", ListSeriesData { series })
+}
+
+#[derive(Deserialize)]
+struct SeriesInput {
+    name: String,
+    points_table: String,
+    counting_results: i32,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_series(state: AppState, data: Form<SeriesInput>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let SeriesInput {
+        name,
+        points_table,
+        counting_results,
+    } = data.0;
+    if name.trim().is_empty() {
+        return Err(Error::InvalidInput("The name must not be empty".into()));
+    }
+    if counting_results < 1 {
+        return Err(Error::InvalidInput(
+            "At least one competition needs to count".into(),
+        ));
+    }
+    // store the points table in a canonical form
+    let points_table = Series::parse_points_table(&points_table)
+        .map_err(Error::InvalidInput)?
+        .iter()
+        .map(i32::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    state
+        .with_connection(move |conn| {
+            diesel::insert_into(series::table)
+                .values((
+                    series::name.eq(name.trim()),
+                    series::points_table.eq(points_table),
+                    series::counting_results.eq(counting_results),
+                ))
+                .execute(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/series.html")))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_series(state: AppState, series_id: Path<Id>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let series_id = series_id.0;
+    state
+        .with_connection(move |conn| {
+            diesel::delete(series::table.find(series_id))
+                .returning(series::id)
+                .get_result::<Id>(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!("{base_url}/admin/series.html")))
+}
+
+#[derive(Serialize)]
+struct SeriesCompetitionsData {
+    series: Series,
+    /// competitions that are part of the series
+    competitions: Vec<Competition>,
+    /// all other competitions
+    other_competitions: Vec<Competition>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_series_competitions(state: AppState, series_id: Path<Id>) -> Result<Html<String>> {
+    let series_id = series_id.0;
+    let data = state
+        .with_connection(move |conn| {
+            let series = series::table
+                .find(series_id)
+                .select(Series::as_select())
+                .first(conn)?;
+            let in_series = series_competitions::table
+                .filter(series_competitions::series_id.eq(series_id))
+                .select(series_competitions::competition_id);
+            let competitions = competitions::table
+                .filter(competitions::id.eq_any(in_series))
+                .order_by((competitions::date, competitions::id))
+                .select(Competition::as_select())
+                .load(conn)?;
+            let other_competitions = competitions::table
+                .filter(competitions::id.ne_all(in_series))
+                .order_by((competitions::date, competitions::id))
+                .select(Competition::as_select())
+                .load(conn)?;
+            QueryResult::Ok(SeriesCompetitionsData {
+                series,
+                competitions,
+                other_competitions,
+            })
+        })
+        .await?;
+    state.render_template("admin_series_competitions.html", data)
+}
+
+#[derive(Deserialize)]
+struct SeriesCompetitionInput {
+    competition_id: Id,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn add_series_competition(
+    state: AppState,
+    series_id: Path<Id>,
+    data: Form<SeriesCompetitionInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let series_id = series_id.0;
+    let competition_id = data.competition_id;
+    state
+        .with_connection(move |conn| {
+            diesel::insert_into(series_competitions::table)
+                .values((
+                    series_competitions::series_id.eq(series_id),
+                    series_competitions::competition_id.eq(competition_id),
+                ))
+                .execute(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/series/{series_id}/competitions.html"
+    )))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn remove_series_competition(
+    state: AppState,
+    Path((series_id, competition_id)): Path<(Id, Id)>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    state
+        .with_connection(move |conn| {
+            diesel::delete(series_competitions::table.find((series_id, competition_id)))
+                .returning(series_competitions::series_id)
+                .get_result::<Id>(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/series/{series_id}/competitions.html"
+    )))
+}
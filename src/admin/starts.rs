@@ -1,16 +1,21 @@
 //! Admin page setup for starts
+use super::time_records::parse_timestamp;
 use crate::app_state::{self, AppState};
+use crate::database::schema::{races, starts};
 use crate::database::Id;
-use crate::errors::Result;
+use crate::errors::{Error, Result};
 use axum::extract::Path;
 use axum::response::{Html, Redirect};
 use axum::{Form, Router};
+use diesel::prelude::*;
 use serde::{Deserialize, Deserializer, Serialize};
 use time::macros::format_description;
 use time::PrimitiveDateTime;
 
 pub fn routes() -> Router<app_state::State> {
     let start_routes = Router::new()
+        .route("/{start_id}/gun_time", axum::routing::post(record_gun_time))
+        .route("/{start_id}/start_now", axum::routing::post(start_now))
         .route("/{start_id}/delete.html", axum::routing::get(delete_start))
         .route(
             "/{start_id}/edit.html",
@@ -44,6 +49,10 @@ struct StartData {
     id: Id,
     name: String,
     time: PrimitiveDateTime,
+    first_bib: Option<i32>,
+    last_bib: Option<i32>,
+    /// maximal number of confirmed participants, `None` for no limit
+    capacity: Option<i32>,
     category_count: i64,
     participant_count: i64,
 }
@@ -64,6 +73,11 @@ async fn list_starts_per_race(state: AppState, race_id: Path<Id>) -> Result<Html
 struct EditStartData {
     name: String,
     time: PrimitiveDateTime,
+    first_bib: Option<i32>,
+    last_bib: Option<i32>,
+    capacity: Option<i32>,
+    /// actual time of the start signal, if already recorded
+    gun_time: Option<PrimitiveDateTime>,
     race_id: Id,
 }
 
@@ -94,6 +108,43 @@ struct StartInputData {
     name: String,
     #[serde(deserialize_with = "parse_date")]
     time: PrimitiveDateTime,
+    /// first bib number that is automatically assigned for this start
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    first_bib: Option<i32>,
+    /// last bib number that is automatically assigned for this start
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    last_bib: Option<i32>,
+    /// maximal number of confirmed participants, further registrations are
+    /// put on the waitlist
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    capacity: Option<i32>,
+}
+
+impl StartInputData {
+    fn is_valid(&self) -> Result<()> {
+        if self.capacity.is_some_and(|capacity| capacity < 0) {
+            return Err(Error::InvalidInput(String::from(
+                "The capacity cannot be negative",
+            )));
+        }
+        match (self.first_bib, self.last_bib) {
+            (None, None) => Ok(()),
+            (Some(first), Some(last)) if 0 < first && first <= last => Ok(()),
+            _ => Err(Error::InvalidInput(String::from(
+                "A bib range requires a positive first and last number \
+                 with first <= last",
+            ))),
+        }
+    }
 }
 
 fn parse_date<'de, D>(d: D) -> Result<PrimitiveDateTime, D::Error>
@@ -114,6 +165,7 @@ async fn create_start(
     data: Form<StartInputData>,
 ) -> Result<Redirect> {
     let base_url = state.base_url();
+    data.is_valid()?;
     todo!("Insert a new start");
 
     Ok(Redirect::to(&format!(
@@ -153,9 +205,84 @@ async fn update_start(
     data: Form<StartInputData>,
 ) -> Result<Redirect> {
     let base_url = state.base_url();
+    data.is_valid()?;
     let race_id: Id = todo!("Update the given start and return the id of the relevant race");
     Ok(Redirect::to(&format!(
         "{base_url}/admin/races/{}/starts.html",
         race_id
     )))
 }
+
+#[derive(Deserialize, Debug)]
+struct GunTimeInput {
+    #[serde(deserialize_with = "parse_timestamp")]
+    gun_time: PrimitiveDateTime,
+}
+
+/// Record the actual time of the start signal
+#[axum::debug_handler(state = app_state::State)]
+async fn record_gun_time(
+    state: AppState,
+    start_id: Path<Id>,
+    data: Form<GunTimeInput>,
+) -> Result<Redirect> {
+    set_gun_time(state, start_id.0, data.0.gun_time).await
+}
+
+#[derive(Deserialize, Debug)]
+struct StartNowInput {
+    /// offset of the local time of the admin's device to UTC in minutes
+    utc_offset_minutes: i16,
+}
+
+/// Record the current time as the time of the start signal
+///
+/// The time is taken from the server clock, only the time zone comes from
+/// the device the admin page is opened on, as gun times are local times
+/// like all other recorded times
+#[axum::debug_handler(state = app_state::State)]
+async fn start_now(
+    state: AppState,
+    start_id: Path<Id>,
+    data: Form<StartNowInput>,
+) -> Result<Redirect> {
+    let offset = time::UtcOffset::from_whole_seconds(i32::from(data.0.utc_offset_minutes) * 60)
+        .map_err(|e| Error::InvalidInput(format!("Invalid time zone offset: {e}")))?;
+    let now = time::OffsetDateTime::now_utc().to_offset(offset);
+    set_gun_time(
+        state,
+        start_id.0,
+        PrimitiveDateTime::new(now.date(), now.time()),
+    )
+    .await
+}
+
+async fn set_gun_time(
+    state: AppState,
+    start_id: Id,
+    gun_time: PrimitiveDateTime,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = state
+        .with_connection(move |conn| {
+            diesel::update(starts::table.find(start_id))
+                .set(starts::gun_time.eq(gun_time))
+                .execute(conn)?;
+            starts::table
+                .inner_join(races::table)
+                .filter(starts::id.eq(start_id))
+                .select(races::competition_id)
+                .first::<Id>(conn)
+                .optional()
+        })
+        .await?;
+    let Some(competition_id) = competition_id else {
+        return Err(Error::NotFound(format!(
+            "No start with id {start_id} found"
+        )));
+    };
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/starts/{start_id}/edit.html"
+    )))
+}
@@ -0,0 +1,190 @@
+//! Admin page setup for relay teams and their team categories
+use crate::app_state::{self, AppState};
+use crate::database::schema::{participants, races, team_categories, team_members, teams};
+use crate::database::shared_models::{Race, TeamCategory, TeamComposition};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/races/{race_id}/teams.html",
+            axum::routing::get(list_teams),
+        )
+        .route(
+            "/races/{race_id}/team_categories",
+            axum::routing::post(new_team_category),
+        )
+        .route(
+            "/team_categories/{team_category_id}/delete.html",
+            axum::routing::get(delete_team_category),
+        )
+}
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = teams)]
+struct TeamData {
+    id: Id,
+    name: String,
+    #[diesel(select_expression = team_categories::label)]
+    team_category: String,
+}
+
+#[derive(Queryable, Serialize)]
+struct TeamMemberData {
+    #[serde(skip)]
+    team_id: Id,
+    leg: i32,
+    first_name: String,
+    last_name: String,
+}
+
+#[derive(Serialize)]
+struct TeamWithMembers {
+    #[serde(flatten)]
+    team: TeamData,
+    members: Vec<TeamMemberData>,
+}
+
+#[derive(Serialize)]
+struct ListTeamsData {
+    race: Race,
+    team_categories: Vec<TeamCategory>,
+    teams: Vec<TeamWithMembers>,
+    compositions: [TeamComposition; 4],
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_teams(state: AppState, race_id: Path<Id>) -> Result<Html<String>> {
+    let race_id = race_id.0;
+    let data = state
+        .with_connection(move |conn| {
+            let Some(race) = races::table
+                .find(race_id)
+                .select(Race::as_select())
+                .first(conn)
+                .optional()?
+            else {
+                return QueryResult::Ok(None);
+            };
+            let team_categories = team_categories::table
+                .filter(team_categories::race_id.eq(race_id))
+                .order_by(team_categories::id)
+                .select(TeamCategory::as_select())
+                .load(conn)?;
+            let teams = teams::table
+                .inner_join(team_categories::table)
+                .filter(teams::race_id.eq(race_id))
+                .order_by(teams::name)
+                .select(TeamData::as_select())
+                .load(conn)?;
+            let members = team_members::table
+                .inner_join(teams::table)
+                .inner_join(participants::table)
+                .filter(teams::race_id.eq(race_id))
+                .order_by(team_members::leg)
+                .select((
+                    team_members::team_id,
+                    team_members::leg,
+                    participants::first_name,
+                    participants::last_name,
+                ))
+                .load::<TeamMemberData>(conn)?;
+            let teams = teams
+                .into_iter()
+                .map(|team| TeamWithMembers {
+                    members: Vec::new(),
+                    team,
+                })
+                .collect::<Vec<_>>();
+            let teams = members.into_iter().fold(teams, |mut teams, member| {
+                if let Some(team) = teams.iter_mut().find(|t| t.team.id == member.team_id) {
+                    team.members.push(member);
+                }
+                teams
+            });
+            Ok(Some((race, team_categories, teams)))
+        })
+        .await?;
+    let (race, team_categories, teams) =
+        data.ok_or_else(|| Error::NotFound(format!("No race for id {race_id} found")))?;
+
+    state.render_template(
+        "admin_list_teams.html",
+        ListTeamsData {
+            race,
+            team_categories,
+            teams,
+            compositions: TeamComposition::ALL,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct TeamCategoryInput {
+    label: String,
+    composition: TeamComposition,
+    from_combined_age: i32,
+    to_combined_age: i32,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_team_category(
+    state: AppState,
+    race_id: Path<Id>,
+    data: Form<TeamCategoryInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let race_id = race_id.0;
+    let TeamCategoryInput {
+        label,
+        composition,
+        from_combined_age,
+        to_combined_age,
+    } = data.0;
+    if label.trim().is_empty() {
+        return Err(Error::InvalidInput("The label must not be empty".into()));
+    }
+    if from_combined_age > to_combined_age {
+        return Err(Error::InvalidInput(
+            "The minimal combined age must not exceed the maximal combined age".into(),
+        ));
+    }
+    state
+        .with_connection(move |conn| {
+            diesel::insert_into(team_categories::table)
+                .values((
+                    team_categories::race_id.eq(race_id),
+                    team_categories::label.eq(label.trim()),
+                    team_categories::composition.eq(composition),
+                    team_categories::from_combined_age.eq(from_combined_age),
+                    team_categories::to_combined_age.eq(to_combined_age),
+                ))
+                .execute(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/races/{race_id}/teams.html"
+    )))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_team_category(state: AppState, team_category_id: Path<Id>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let team_category_id = team_category_id.0;
+    let race_id = state
+        .with_connection(move |conn| {
+            diesel::delete(team_categories::table.find(team_category_id))
+                .returning(team_categories::race_id)
+                .get_result::<Id>(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/races/{race_id}/teams.html"
+    )))
+}
@@ -0,0 +1,398 @@
+//! Admin page setup for capturing finish and split times
+use crate::admin::user::auth_session::AuthSession;
+use crate::app_state::{self, AppState};
+use crate::database::schema::{
+    bib_numbers, categories, competitions, participants, races, split_times, start_reads, starts,
+    time_records, timing_points,
+};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::results::{elapsed_time, milliseconds};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::dsl;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Deserializer, Serialize};
+use time::macros::format_description;
+use time::PrimitiveDateTime;
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/competitions/{competition_id}/time_records.html",
+            axum::routing::get(list_time_records),
+        )
+        .route(
+            "/competitions/{competition_id}/time_records",
+            axum::routing::post(record_finish_time),
+        )
+        .route(
+            "/competitions/{competition_id}/split_times",
+            axum::routing::post(record_split_time),
+        )
+        .route(
+            "/competitions/{competition_id}/time_adjustments",
+            axum::routing::post(add_time_adjustment),
+        )
+}
+
+/// A single recorded finish time joined with the relevant participant
+/// and start data
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = time_records)]
+#[diesel(check_for_backend(Sqlite))]
+struct TimeRecordEntry {
+    id: Id,
+    participant_id: Id,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = participants::first_name)]
+    first_name: String,
+    #[diesel(select_expression = participants::last_name)]
+    last_name: String,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = starts::time)]
+    start_time: PrimitiveDateTime,
+    #[diesel(select_expression = starts::gun_time)]
+    gun_time: Option<PrimitiveDateTime>,
+    #[diesel(select_expression = start_reads::time.nullable())]
+    start_read: Option<PrimitiveDateTime>,
+    finish_time: PrimitiveDateTime,
+}
+
+#[derive(Serialize)]
+struct TimeRecordWithNetTime {
+    #[serde(flatten)]
+    record: TimeRecordEntry,
+    /// individual start of the participant
+    start: PrimitiveDateTime,
+    /// finish time minus start time in milliseconds
+    net_time: i64,
+    /// sum of the time adjustments of the participant in milliseconds
+    time_adjustment: Option<i64>,
+}
+
+/// A timing point that can be selected while capturing split times
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = timing_points)]
+#[diesel(check_for_backend(Sqlite))]
+struct TimingPointOption {
+    id: Id,
+    name: String,
+    #[diesel(select_expression = races::name)]
+    race: String,
+}
+
+/// A single recorded split time joined with the relevant participant
+/// and timing point data
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = split_times)]
+#[diesel(check_for_backend(Sqlite))]
+struct SplitTimeEntry {
+    participant_id: Id,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = participants::first_name)]
+    first_name: String,
+    #[diesel(select_expression = participants::last_name)]
+    last_name: String,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = timing_points::name)]
+    timing_point: String,
+    time: PrimitiveDateTime,
+}
+
+#[derive(Serialize)]
+struct ListTimeRecordsData {
+    competition_id: Id,
+    competition_name: String,
+    time_records: Vec<TimeRecordWithNetTime>,
+    timing_points: Vec<TimingPointOption>,
+    split_times: Vec<SplitTimeEntry>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_time_records(state: AppState, competition_id: Path<Id>) -> Result<Html<String>> {
+    let competition_id = competition_id.0;
+    let (competition_name, time_records, timing_points, split_times, time_adjustments) = state
+        .with_connection(move |conn| {
+            let competition_name = competitions::table
+                .find(competition_id)
+                .select(competitions::name)
+                .first::<String>(conn)
+                .optional()?;
+            let time_records = time_records::table
+                .inner_join(
+                    participants::table
+                        .inner_join(
+                            categories::table.inner_join(starts::table.inner_join(races::table)),
+                        )
+                        .left_join(bib_numbers::table)
+                        .left_join(start_reads::table),
+                )
+                .filter(races::competition_id.eq(competition_id))
+                .order_by((time_records::finish_time, time_records::id))
+                .select(TimeRecordEntry::as_select())
+                .load(conn)?;
+            let timing_points = timing_points::table
+                .inner_join(races::table)
+                .filter(races::competition_id.eq(competition_id))
+                .order_by((races::id, timing_points::position))
+                .select(TimingPointOption::as_select())
+                .load(conn)?;
+            let split_times = split_times::table
+                .inner_join(timing_points::table.inner_join(races::table))
+                .inner_join(participants::table.left_join(bib_numbers::table))
+                .filter(races::competition_id.eq(competition_id))
+                .order_by((split_times::time, split_times::participant_id))
+                .select(SplitTimeEntry::as_select())
+                .load(conn)?;
+            let time_adjustments =
+                crate::database::time_records::summed_time_adjustments(conn, competition_id)?;
+            QueryResult::Ok((
+                competition_name,
+                time_records,
+                timing_points,
+                split_times,
+                time_adjustments,
+            ))
+        })
+        .await?;
+    let competition_name = competition_name
+        .ok_or_else(|| Error::NotFound(format!("No competition for id {competition_id} found")))?;
+
+    let time_records = time_records
+        .into_iter()
+        .map(|record| {
+            let start = record
+                .start_read
+                .or(record.gun_time)
+                .unwrap_or(record.start_time);
+            TimeRecordWithNetTime {
+                net_time: elapsed_time(start, record.finish_time),
+                time_adjustment: time_adjustments.get(&record.participant_id).copied(),
+                start,
+                record,
+            }
+        })
+        .collect();
+
+    state.render_template(
+        "admin_time_records.html",
+        ListTimeRecordsData {
+            competition_id,
+            competition_name,
+            time_records,
+            timing_points,
+            split_times,
+        },
+    )
+}
+
+#[derive(Deserialize, Debug)]
+struct FinishTimeInput {
+    participant_id: Id,
+    #[serde(deserialize_with = "parse_timestamp")]
+    finish_time: PrimitiveDateTime,
+}
+
+/// Parse the value of a `datetime-local` input field
+///
+/// Browsers omit the seconds if they are zero, and add subseconds if the
+/// `step` attribute allows them, so both parts are optional here
+pub(crate) fn parse_timestamp<'de, D>(d: D) -> Result<PrimitiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = <String as Deserialize>::deserialize(d)?;
+    let format = format_description!(
+        "[year]-[month]-[day]T[hour]:[minute][optional [:[second][optional [.[subsecond]]]]]"
+    );
+    PrimitiveDateTime::parse(&s, format).map_err(|e| serde::de::Error::custom(e.to_string()))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn record_finish_time(
+    state: AppState,
+    competition_id: Path<Id>,
+    data: Form<FinishTimeInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let FinishTimeInput {
+        participant_id,
+        finish_time,
+    } = data.0;
+    let recorded = state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let participant_exists = diesel::select(dsl::exists(
+                    participants::table
+                        .inner_join(
+                            categories::table.inner_join(starts::table.inner_join(races::table)),
+                        )
+                        .filter(participants::id.eq(participant_id))
+                        .filter(races::competition_id.eq(competition_id)),
+                ))
+                .get_result::<bool>(conn)?;
+                if !participant_exists {
+                    return Ok(false);
+                }
+                // a recorded time is never replaced, corrections are
+                // stored as time adjustments
+                crate::database::time_records::insert_finish_time(
+                    conn,
+                    participant_id,
+                    finish_time,
+                )?;
+                QueryResult::Ok(true)
+            })
+        })
+        .await?;
+    if !recorded {
+        return Err(Error::NotFound(format!(
+            "Participant with id {participant_id} not found in competition {competition_id}"
+        )));
+    }
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/time_records.html"
+    )))
+}
+
+#[derive(Deserialize, Debug)]
+struct TimeAdjustmentInput {
+    participant_id: Id,
+    /// correction in seconds, negative values make the time shorter
+    delta_seconds: f64,
+    reason: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn add_time_adjustment(
+    state: AppState,
+    auth_session: AuthSession,
+    competition_id: Path<Id>,
+    data: Form<TimeAdjustmentInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let TimeAdjustmentInput {
+        participant_id,
+        delta_seconds,
+        reason,
+    } = data.0;
+    let delta_ms = time::Duration::checked_seconds_f64(delta_seconds)
+        .filter(|delta| delta.abs() <= time::Duration::DAY)
+        .map(milliseconds)
+        .filter(|&delta_ms| delta_ms != 0)
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid time adjustment: {delta_seconds}")))?;
+    let reason = reason.trim().to_owned();
+    if reason.is_empty() {
+        return Err(Error::InvalidInput(String::from(
+            "A time adjustment needs a reason",
+        )));
+    }
+    let created_by = auth_session
+        .user
+        .map(|user| user.name)
+        .ok_or_else(|| Error::InvalidInput(String::from("Not logged in")))?;
+    let recorded = state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let participant_exists = diesel::select(dsl::exists(
+                    participants::table
+                        .inner_join(
+                            categories::table.inner_join(starts::table.inner_join(races::table)),
+                        )
+                        .filter(participants::id.eq(participant_id))
+                        .filter(races::competition_id.eq(competition_id)),
+                ))
+                .get_result::<bool>(conn)?;
+                if !participant_exists {
+                    return Ok(false);
+                }
+                crate::database::time_records::add_time_adjustment(
+                    conn,
+                    participant_id,
+                    delta_ms,
+                    &reason,
+                    &created_by,
+                )?;
+                QueryResult::Ok(true)
+            })
+        })
+        .await?;
+    if !recorded {
+        return Err(Error::NotFound(format!(
+            "Participant with id {participant_id} not found in competition {competition_id}"
+        )));
+    }
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/time_records.html"
+    )))
+}
+
+#[derive(Deserialize, Debug)]
+struct SplitTimeInput {
+    participant_id: Id,
+    timing_point_id: Id,
+    #[serde(deserialize_with = "parse_timestamp")]
+    time: PrimitiveDateTime,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn record_split_time(
+    state: AppState,
+    competition_id: Path<Id>,
+    data: Form<SplitTimeInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let competition_id = competition_id.0;
+    let SplitTimeInput {
+        participant_id,
+        timing_point_id,
+        time,
+    } = data.0;
+    let recorded = state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                // the timing point needs to belong to the race of the participant
+                let matches_race = diesel::select(dsl::exists(
+                    participants::table
+                        .inner_join(categories::table.inner_join(
+                            starts::table.inner_join(races::table.inner_join(timing_points::table)),
+                        ))
+                        .filter(participants::id.eq(participant_id))
+                        .filter(timing_points::id.eq(timing_point_id))
+                        .filter(races::competition_id.eq(competition_id)),
+                ))
+                .get_result::<bool>(conn)?;
+                if !matches_race {
+                    return Ok(false);
+                }
+                crate::database::time_records::record_split_time(
+                    conn,
+                    participant_id,
+                    timing_point_id,
+                    time,
+                )?;
+                QueryResult::Ok(true)
+            })
+        })
+        .await?;
+    if !recorded {
+        return Err(Error::NotFound(format!(
+            "Timing point {timing_point_id} not found for participant {participant_id} in competition {competition_id}"
+        )));
+    }
+    state.notify_results_changed(competition_id);
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/competitions/{competition_id}/time_records.html"
+    )))
+}
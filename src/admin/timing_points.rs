@@ -0,0 +1,112 @@
+//! Admin page setup for intermediate timing points of a race
+use crate::app_state::{self, AppState};
+use crate::database::schema::{races, timing_points};
+use crate::database::shared_models::{Race, TimingPoint};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::{Html, Redirect};
+use axum::{Form, Router};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub(crate) fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/races/{race_id}/timing_points.html",
+            axum::routing::get(list_timing_points),
+        )
+        .route(
+            "/races/{race_id}/timing_points",
+            axum::routing::post(new_timing_point),
+        )
+        .route(
+            "/timing_points/{timing_point_id}/delete.html",
+            axum::routing::get(delete_timing_point),
+        )
+}
+
+#[derive(Serialize)]
+struct ListTimingPointsData {
+    race: Race,
+    timing_points: Vec<TimingPoint>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_timing_points(state: AppState, race_id: Path<Id>) -> Result<Html<String>> {
+    let race_id = race_id.0;
+    let (race, timing_points) = state
+        .with_connection(move |conn| {
+            let race = races::table
+                .find(race_id)
+                .select(Race::as_select())
+                .first(conn)
+                .optional()?;
+            let timing_points = timing_points::table
+                .filter(timing_points::race_id.eq(race_id))
+                .order_by(timing_points::position)
+                .select(TimingPoint::as_select())
+                .load(conn)?;
+            QueryResult::Ok((race, timing_points))
+        })
+        .await?;
+    let race = race.ok_or_else(|| Error::NotFound(format!("No race for id {race_id} found")))?;
+
+    state.render_template(
+        "admin_list_timing_points.html",
+        ListTimingPointsData {
+            race,
+            timing_points,
+        },
+    )
+}
+
+#[derive(Deserialize)]
+struct TimingPointInput {
+    name: String,
+    position: i32,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn new_timing_point(
+    state: AppState,
+    race_id: Path<Id>,
+    data: Form<TimingPointInput>,
+) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let race_id = race_id.0;
+    let TimingPointInput { name, position } = data.0;
+    if name.trim().is_empty() {
+        return Err(Error::InvalidInput("The name must not be empty".into()));
+    }
+    state
+        .with_connection(move |conn| {
+            diesel::insert_into(timing_points::table)
+                .values((
+                    timing_points::race_id.eq(race_id),
+                    timing_points::name.eq(name.trim()),
+                    timing_points::position.eq(position),
+                ))
+                .execute(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/races/{race_id}/timing_points.html"
+    )))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn delete_timing_point(state: AppState, timing_point_id: Path<Id>) -> Result<Redirect> {
+    let base_url = state.base_url();
+    let timing_point_id = timing_point_id.0;
+    let race_id = state
+        .with_connection(move |conn| {
+            diesel::delete(timing_points::table.find(timing_point_id))
+                .returning(timing_points::race_id)
+                .get_result::<Id>(conn)
+        })
+        .await?;
+    Ok(Redirect::to(&format!(
+        "{base_url}/admin/races/{race_id}/timing_points.html"
+    )))
+}
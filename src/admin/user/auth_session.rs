@@ -16,6 +16,8 @@ use diesel::sqlite::Sqlite;
 #[diesel(check_for_backend(Sqlite))]
 pub struct User {
     pub(crate) id: Id,
+    /// login name, used to record who changed data
+    pub(crate) name: String,
     pub(crate) password: String,
 }
 
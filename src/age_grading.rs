@@ -0,0 +1,112 @@
+//! Age grading of finish times
+//!
+//! The factor tables are loaded from `data/age_grading.json`, which is
+//! embedded at compile time like the translations and parsed once on first
+//! use. The shipped tables are illustrative approximations and not the
+//! official WMA data, see the description in the data file, which is shown
+//! next to the age graded results. Each table covers one distance and
+//! gender. Races within [`MAX_DISTANCE_DEVIATION_PERCENT`] of a table
+//! distance are graded with the closest table, other races are not graded.
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+/// Factor tables loaded at compile time
+static TABLES: LazyLock<AgeGradingTables> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../data/age_grading.json"))
+        .expect("The embedded age grading data is valid")
+});
+
+/// How far the distance of a race may differ from the distance of a table
+///
+/// The open standard is scaled with Riegel's formula for the difference,
+/// which is only a reasonable approximation for similar distances
+const MAX_DISTANCE_DEVIATION_PERCENT: i64 = 10;
+
+#[derive(Deserialize)]
+struct AgeGradingTables {
+    /// source and license of the factors
+    description: String,
+    tables: Vec<AgeGradingTable>,
+}
+
+#[derive(Deserialize)]
+struct AgeGradingTable {
+    distance_meters: i32,
+    male: bool,
+    /// best possible time for this distance and gender in seconds
+    open_standard_seconds: f64,
+    /// pairs of age and factor, sorted by age
+    ///
+    /// Factors between the listed ages are interpolated linearly,
+    /// younger ages use a factor of `1.0`
+    factors: Vec<(i32, f64)>,
+}
+
+impl AgeGradingTable {
+    fn factor(&self, age: i32) -> f64 {
+        let mut previous = None::<(i32, f64)>;
+        for &(table_age, factor) in &self.factors {
+            if age <= table_age {
+                return match previous {
+                    Some((previous_age, previous_factor)) => {
+                        let ratio =
+                            f64::from(age - previous_age) / f64::from(table_age - previous_age);
+                        previous_factor + (factor - previous_factor) * ratio
+                    }
+                    None => 1.0,
+                };
+            }
+            previous = Some((table_age, factor));
+        }
+        previous.map_or(1.0, |(_, factor)| factor)
+    }
+
+    /// Whether the table can be used for a race over the given distance
+    fn covers(&self, distance_meters: i32) -> bool {
+        let deviation = (i64::from(distance_meters) - i64::from(self.distance_meters)).abs();
+        deviation * 100 <= i64::from(self.distance_meters) * MAX_DISTANCE_DEVIATION_PERCENT
+    }
+
+    /// The open standard for the given distance
+    ///
+    /// Standards for other distances than the one of the table are
+    /// scaled with Riegel's formula
+    fn open_standard_millis(&self, distance_meters: i32) -> f64 {
+        let ratio = f64::from(distance_meters) / f64::from(self.distance_meters);
+        self.open_standard_seconds * ratio.powf(1.06) * 1000.0
+    }
+}
+
+/// Age graded result of a finisher
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AgeGrade {
+    /// the time multiplied by the age factor in milliseconds
+    pub(crate) time: i64,
+    /// open standard relative to the age graded time in percent
+    pub(crate) percentage: f64,
+}
+
+/// Source and license of the factors, shown next to age graded results
+pub(crate) fn source() -> &'static str {
+    &TABLES.description
+}
+
+/// Compute the age grade of a time over the given distance
+///
+/// Returns `None` if there is no table for the gender and distance or the
+/// time is not positive
+pub(crate) fn age_grade(distance_meters: i32, male: bool, age: i32, time: i64) -> Option<AgeGrade> {
+    if distance_meters <= 0 || time <= 0 {
+        return None;
+    }
+    let table = TABLES
+        .tables
+        .iter()
+        .filter(|table| table.male == male && table.covers(distance_meters))
+        .min_by_key(|table| (distance_meters - table.distance_meters).abs())?;
+    let graded = time as f64 * table.factor(age);
+    Some(AgeGrade {
+        time: graded.round() as i64,
+        percentage: table.open_standard_millis(distance_meters) / graded * 100.0,
+    })
+}
@@ -1,5 +1,7 @@
 use crate::axum_ext::AcceptLanguage;
+use crate::database::Id;
 use crate::errors::Result;
+use crate::mail::Mailer;
 use crate::service_config::Config;
 use axum::response::Html;
 use axum_extra::TypedHeader;
@@ -15,6 +17,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use time::format_description;
 use time::macros::format_description;
+use tokio::sync::broadcast;
 
 // Localization data loaded at compile time
 fluent_templates::static_loader! {
@@ -33,6 +36,12 @@ pub struct State {
     pub templates: minijinja::Environment<'static>,
     /// base url path the application is served at
     pub base_url: Arc<str>,
+    /// scheme and host the application is reachable at from the outside
+    pub public_url: Arc<str>,
+    /// notifies about changed results, carries the id of the competition
+    pub results_updates: broadcast::Sender<Id>,
+    /// transport for mails to participants
+    pub mailer: Arc<dyn Mailer>,
 }
 
 impl State {
@@ -51,6 +60,8 @@ impl State {
         templates.set_loader(minijinja::path_loader(&config.template_dir));
         templates.add_filter("format_date", format_date);
         templates.add_filter("format_timestamp", format_timestamp);
+        templates.add_filter("format_duration", format_duration);
+        templates.add_filter("format_amount", format_amount);
         templates.add_function("translate", translate);
         let mut builder = deadpool_diesel::Pool::builder(manager);
         if is_test {
@@ -64,13 +75,32 @@ impl State {
             }))
             .build()
             .expect("Could not build the connection pool");
+        let (results_updates, _) = broadcast::channel(64);
+        let mailer = crate::mail::from_config(config).expect("Invalid mail configuration");
         Self {
             pool,
             templates,
             base_url: config.base_url.clone().into(),
+            public_url: match &config.public_url {
+                Some(url) => url.trim_end_matches('/').into(),
+                // mails sent via SMTP leave the host, so the links in them
+                // must not point to the address the application listens on
+                None if config.smtp_server.is_some() => {
+                    panic!("Sending mails via SMTP requires --public-url")
+                }
+                None => format!("http://{}:{}", config.address, config.port).into(),
+            },
+            results_updates,
+            mailer,
         }
     }
 
+    /// Notify all live result streams of a competition about changed results
+    pub fn notify_results_changed(&self, competition_id: Id) {
+        // sending only fails if nobody is listening, which is fine
+        let _ = self.results_updates.send(competition_id);
+    }
+
     pub async fn with_connection<T: Send + 'static>(
         &self,
         callback: impl FnOnce(&mut SqliteConnection) -> QueryResult<T> + Send + 'static,
@@ -147,6 +177,35 @@ fn format_timestamp(arg: ViaDeserialize<time::PrimitiveDateTime>) -> String {
     arg.0.format(&format).expect("Can format this timestamp")
 }
 
+/// Format a duration given in milliseconds as `[h:]mm:ss[.t]`
+///
+/// The tenths are only shown if they are not zero, e.g. 45:12.04 is shown
+/// as `45:12` and not as `45:12.0`
+pub(crate) fn format_duration(millis: i64) -> String {
+    let sign = if millis < 0 { "-" } else { "" };
+    let millis = millis.unsigned_abs();
+    let hours = millis / 3_600_000;
+    let minutes = (millis / 60_000) % 60;
+    let seconds = (millis / 1000) % 60;
+    let tenths = (millis % 1000) / 100;
+    let mut out = if hours > 0 {
+        format!("{sign}{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{sign}{minutes:02}:{seconds:02}")
+    };
+    if tenths != 0 {
+        out.push_str(&format!(".{tenths}"));
+    }
+    out
+}
+
+/// Format an amount given in cents as `x.yy`
+pub(crate) fn format_amount(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{sign}{}.{:02}", cents / 100, cents % 100)
+}
+
 fn translate<'a>(state: &minijinja::State<'_, 'a>, key: &'a str) -> String {
     let lang_keys = state
         .lookup("lang_keys")
@@ -207,13 +266,21 @@ impl AppState {
         name: &'static str,
         data: impl Serialize,
     ) -> Result<Html<String>> {
+        Ok(Html(self.render_text(name, data)?))
+    }
+
+    /// render a template with a given name and the given data as plain
+    /// text, e.g. for mails
+    ///
+    /// Only templates ending in `.html` are escaped
+    pub fn render_text(&self, name: &'static str, data: impl Serialize) -> Result<String> {
         let template = self.state.templates.get_template(name)?;
         let base_url = &self.state.base_url;
-        Ok(Html(template.render(TemplateData {
+        Ok(template.render(TemplateData {
             base_url,
             lang_keys: &self.lang_keys,
             inner: data,
-        })?))
+        })?)
     }
 
     /// Interact with a database connection
@@ -228,6 +295,27 @@ impl AppState {
         &self.state.base_url
     }
 
+    /// The absolute url of the application including the base url, used for
+    /// links outside of the application, e.g. in mails
+    pub fn public_url(&self) -> String {
+        format!("{}{}", self.state.public_url, self.state.base_url)
+    }
+
+    /// Notify all live result streams of a competition about changed results
+    pub fn notify_results_changed(&self, competition_id: Id) {
+        self.state.notify_results_changed(competition_id);
+    }
+
+    /// Receive the ids of competitions with changed results
+    pub fn subscribe_results(&self) -> broadcast::Receiver<Id> {
+        self.state.results_updates.subscribe()
+    }
+
+    /// The transport for mails to participants
+    pub fn mailer(&self) -> Arc<dyn Mailer> {
+        self.state.mailer.clone()
+    }
+
     pub fn translation(&self, key: &str) -> String {
         lookup_translation(&self.lang_keys, key, HashMap::new())
     }
@@ -0,0 +1,240 @@
+//! Award lists for the prize-giving
+//!
+//! The best finishers of each category and of each special category are
+//! awarded. Categories with fewer finishers than configured are merged with
+//! their neighbouring categories of the same race and gender. The groups are
+//! sorted by their ceremony order, groups without an order keep the order of
+//! the races and categories and come last. Races with published results are
+//! awarded based on the latest published version.
+use crate::database::schema::{
+    award_settings, categories, participants_in_special_category, races, special_categories, starts,
+};
+use crate::database::Id;
+use crate::results::{ShownEntry, ShownRanking};
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How many places are awarded and when categories are merged
+#[derive(Queryable, Selectable, Serialize, Debug, Clone, Copy)]
+#[diesel(table_name = award_settings)]
+#[diesel(check_for_backend(Sqlite))]
+pub(crate) struct AwardSettings {
+    /// number of awarded places per category
+    pub(crate) places: i32,
+    /// categories with fewer finishers are merged with their neighbours
+    pub(crate) min_finishers: i32,
+}
+
+impl Default for AwardSettings {
+    fn default() -> Self {
+        Self {
+            places: 3,
+            min_finishers: 0,
+        }
+    }
+}
+
+impl AwardSettings {
+    pub(crate) fn load(conn: &mut SqliteConnection, competition_id: Id) -> QueryResult<Self> {
+        Ok(award_settings::table
+            .find(competition_id)
+            .select(Self::as_select())
+            .first(conn)
+            .optional()?
+            .unwrap_or_default())
+    }
+}
+
+/// A category as needed for the awards
+#[derive(Queryable)]
+struct CategoryRow {
+    id: Id,
+    label: String,
+    from_age: i32,
+    male: bool,
+    race_id: Id,
+    ceremony_order: Option<i32>,
+}
+
+/// A special category as needed for the awards
+#[derive(Queryable)]
+struct SpecialCategoryRow {
+    id: Id,
+    name: String,
+    race_id: Id,
+    ceremony_order: Option<i32>,
+}
+
+/// An awarded finisher
+#[derive(Serialize, Debug)]
+pub(crate) struct Awardee {
+    pub(crate) place: usize,
+    pub(crate) bib: Option<i32>,
+    pub(crate) first_name: String,
+    pub(crate) last_name: String,
+    pub(crate) club: Option<String>,
+    /// ranking time in milliseconds
+    pub(crate) time: i64,
+}
+
+/// The awarded finishers of a category, of merged categories or of a
+/// special category
+#[derive(Serialize, Debug)]
+pub(crate) struct AwardGroup {
+    pub(crate) race: String,
+    /// label of the category, merged categories are joined by ` / `
+    pub(crate) label: String,
+    /// whether this is a special category
+    pub(crate) special: bool,
+    pub(crate) ceremony_order: Option<i32>,
+    /// number of finishers in the group
+    pub(crate) finishers: usize,
+    pub(crate) awardees: Vec<Awardee>,
+}
+
+/// Award the best finishers in their order
+fn award(finishers: &[&ShownEntry], places: i32) -> Vec<Awardee> {
+    let places = usize::try_from(places).unwrap_or_default();
+    crate::results::places(finishers.iter().map(|f| f.time))
+        .into_iter()
+        .zip(finishers)
+        .take_while(|(place, _)| *place <= places)
+        .map(|(place, finisher)| Awardee {
+            place,
+            bib: finisher.bib,
+            first_name: finisher.first_name.clone(),
+            last_name: finisher.last_name.clone(),
+            club: finisher.club.clone(),
+            time: finisher.time,
+        })
+        .collect()
+}
+
+/// Merge categories of the same gender with too few finishers with their
+/// neighbours
+///
+/// The categories need to be sorted by gender and age
+fn merge_small_categories(
+    categories: Vec<(&CategoryRow, usize)>,
+    min_finishers: usize,
+) -> Vec<Vec<(&CategoryRow, usize)>> {
+    let mut groups: Vec<Vec<(&CategoryRow, usize)>> = Vec::new();
+    for (category, count) in categories {
+        if let Some(last) = groups.last_mut() {
+            let last_count = last.iter().map(|(_, count)| count).sum::<usize>();
+            if last[0].0.male == category.male
+                && (last_count < min_finishers || count < min_finishers)
+            {
+                last.push((category, count));
+                continue;
+            }
+        }
+        groups.push(vec![(category, count)]);
+    }
+    groups
+}
+
+/// Compute the award list of a competition
+pub(crate) fn award_list(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    settings: AwardSettings,
+) -> QueryResult<Vec<AwardGroup>> {
+    let results = crate::results::load_shown_rankings(conn, competition_id)?;
+    let categories = categories::table
+        .inner_join(starts::table.inner_join(races::table))
+        .filter(races::competition_id.eq(competition_id))
+        .order_by((categories::male, categories::from_age, categories::id))
+        .select((
+            categories::id,
+            categories::label,
+            categories::from_age,
+            categories::male,
+            races::id,
+            categories::ceremony_order,
+        ))
+        .load::<CategoryRow>(conn)?;
+    let specials = special_categories::table
+        .inner_join(races::table)
+        .filter(races::competition_id.eq(competition_id))
+        .order_by(special_categories::id)
+        .select((
+            special_categories::id,
+            special_categories::name,
+            races::id,
+            special_categories::ceremony_order,
+        ))
+        .load::<SpecialCategoryRow>(conn)?;
+    let memberships = participants_in_special_category::table
+        .inner_join(special_categories::table.inner_join(races::table))
+        .filter(races::competition_id.eq(competition_id))
+        .select((
+            participants_in_special_category::special_category_id,
+            participants_in_special_category::participant_id,
+        ))
+        .load::<(Id, Id)>(conn)?
+        .into_iter()
+        .collect::<HashSet<_>>();
+    let min_finishers = usize::try_from(settings.min_finishers).unwrap_or_default();
+
+    let mut groups = Vec::new();
+    for race in results {
+        let ShownRanking {
+            race,
+            participants: finishers,
+        } = race;
+        let race_id = race.id;
+        let race_name = race.name;
+
+        let mut per_category = HashMap::<Id, usize>::new();
+        for category_id in finishers.iter().filter_map(|f| f.category_id) {
+            *per_category.entry(category_id).or_default() += 1;
+        }
+        let race_categories = categories
+            .iter()
+            .filter(|c| c.race_id == race_id)
+            .filter_map(|c| per_category.get(&c.id).map(|count| (c, *count)))
+            .collect::<Vec<_>>();
+        for merged in merge_small_categories(race_categories, min_finishers) {
+            let ids = merged.iter().map(|(c, _)| c.id).collect::<HashSet<_>>();
+            let members = finishers
+                .iter()
+                .filter(|f| f.category_id.is_some_and(|id| ids.contains(&id)))
+                .collect::<Vec<_>>();
+            groups.push(AwardGroup {
+                race: race_name.clone(),
+                label: merged
+                    .iter()
+                    .map(|(c, _)| c.label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" / "),
+                special: false,
+                ceremony_order: merged.iter().filter_map(|(c, _)| c.ceremony_order).min(),
+                finishers: members.len(),
+                awardees: award(&members, settings.places),
+            });
+        }
+        for special in specials.iter().filter(|s| s.race_id == race_id) {
+            let members = finishers
+                .iter()
+                .filter(|f| memberships.contains(&(special.id, f.id)))
+                .collect::<Vec<_>>();
+            if members.is_empty() {
+                continue;
+            }
+            groups.push(AwardGroup {
+                race: race_name.clone(),
+                label: special.name.clone(),
+                special: true,
+                ceremony_order: special.ceremony_order,
+                finishers: members.len(),
+                awardees: award(&members, settings.places),
+            });
+        }
+    }
+    // the sort is stable, so groups without an order keep the race order
+    groups.sort_by_key(|g| g.ceremony_order.unwrap_or(i32::MAX));
+    Ok(groups)
+}
@@ -0,0 +1,69 @@
+//! Parsing of exports of chip timing readers
+//!
+//! Readers export one read per line as `chip;timestamp;reader`. Commas and tabs
+//! are accepted as separator as well, so CSV exports can be imported as they are.
+//! Empty lines and lines starting with `#` are ignored, as is a header line.
+use crate::errors::{Error, Result};
+use time::macros::format_description;
+use time::PrimitiveDateTime;
+
+/// A single read of a chip by a timing reader
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ChipRead {
+    /// id of the chip as reported by the reader
+    pub(crate) chip: String,
+    /// time of the read
+    pub(crate) time: PrimitiveDateTime,
+    /// reader or antenna that registered the chip
+    pub(crate) reader: String,
+}
+
+/// Parse a single `chip;timestamp;reader` line
+pub(crate) fn parse_line(line: &str) -> std::result::Result<ChipRead, String> {
+    let separator = [';', ',', '\t']
+        .into_iter()
+        .find(|s| line.contains(*s))
+        .ok_or_else(|| "Expected `chip;timestamp;reader`".to_owned())?;
+    let fields = line
+        .split(separator)
+        .map(|f| f.trim().trim_matches('"'))
+        .collect::<Vec<_>>();
+    let [chip, time, reader] = fields[..] else {
+        return Err(format!("Expected 3 fields, got {}", fields.len()));
+    };
+    if chip.is_empty() {
+        return Err("The chip id must not be empty".into());
+    }
+    // readers use both `2026-02-18T11:00:00` and `2026-02-18 11:00:00`
+    let format = format_description!(
+        "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]]"
+    );
+    let time = PrimitiveDateTime::parse(&time.replacen('T', " ", 1), format)
+        .map_err(|e| format!("Invalid timestamp `{time}`: {e}"))?;
+    Ok(ChipRead {
+        chip: chip.to_owned(),
+        time,
+        reader: reader.to_owned(),
+    })
+}
+
+/// Parse a whole export file
+///
+/// A first line that cannot be parsed is treated as header, all other lines
+/// need to be valid
+pub(crate) fn parse_export(input: &str) -> Result<Vec<ChipRead>> {
+    let mut reads = Vec::new();
+    let lines = input
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    for (idx, (line_number, line)) in lines.enumerate() {
+        match parse_line(line) {
+            Ok(read) => reads.push(read),
+            Err(_) if idx == 0 => {}
+            Err(e) => return Err(Error::InvalidInput(format!("Line {line_number}: {e}"))),
+        }
+    }
+    Ok(reads)
+}
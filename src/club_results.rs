@@ -0,0 +1,189 @@
+//! Render the club results of a competition
+//!
+//! Clubs are scored per race based on the ranked results of the race, races
+//! with published results are scored based on the latest version. Which
+//! scoring is used is configured per race, races without a configuration
+//! are not part of the club results. Free-text club names are mapped to
+//! canonical clubs via `database::clubs`.
+use crate::app_state::{self, AppState};
+use crate::database::clubs::{ClubDirectory, ClubKey};
+use crate::database::schema::{club_scorings, competitions, races};
+use crate::database::shared_models::{ClubScoring, ClubScoringMode, Competition};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::results::{places, ShownRanking};
+use axum::extract::Path;
+use axum::response::Html;
+use axum::Router;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new().route(
+        "/{event_id}/club_results.html",
+        axum::routing::get(render_club_results),
+    )
+}
+
+/// A finisher that counts for the score of a club
+#[derive(Debug, Serialize)]
+struct ClubMember {
+    first_name: String,
+    last_name: String,
+    /// ranking time in milliseconds
+    time: i64,
+    /// overall place in the race
+    place: usize,
+}
+
+/// The score of a single club in a race
+#[derive(Debug, Serialize)]
+struct ClubResult {
+    name: String,
+    /// the finishers that count for the score, best first
+    members: Vec<ClubMember>,
+    /// number of all finishers of the club
+    finishers: usize,
+    /// the score, only set if the club has enough finishers
+    ///
+    /// That's a time in milliseconds or a sum of places
+    /// depending on the scoring mode
+    score: Option<i64>,
+    place: Option<usize>,
+}
+
+/// Club results for a single race
+#[derive(Debug, Serialize)]
+struct RaceClubResults {
+    race_name: String,
+    scoring: ClubScoring,
+    /// clubs ordered by their place, clubs without a score come last
+    clubs: Vec<ClubResult>,
+}
+
+/// Data used to render the club results
+///
+/// See `templates/club_results.html` for the relevant template
+#[derive(Serialize)]
+struct ClubResultListData {
+    races: Vec<RaceClubResults>,
+    competition_info: Competition,
+}
+
+/// Score all clubs of a race
+fn score_clubs(
+    results: ShownRanking,
+    scoring: ClubScoring,
+    clubs: &ClubDirectory,
+) -> RaceClubResults {
+    let counting = usize::try_from(scoring.counting).unwrap_or_default();
+    // participants are already ordered by their place
+    let mut by_club = HashMap::<ClubKey, ClubResult>::new();
+    for participant in results.participants {
+        let Some(key) = clubs.resolve(participant.club.as_deref()) else {
+            continue;
+        };
+        let club = by_club.entry(key).or_insert_with_key(|key| ClubResult {
+            // unknown clubs are named after the first spelling
+            name: clubs.name(key).map(str::to_owned).unwrap_or_else(|| {
+                participant
+                    .club
+                    .as_deref()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned()
+            }),
+            members: Vec::new(),
+            finishers: 0,
+            score: None,
+            place: None,
+        });
+        club.finishers += 1;
+        if club.members.len() < counting {
+            club.members.push(ClubMember {
+                first_name: participant.first_name,
+                last_name: participant.last_name,
+                time: participant.time,
+                place: participant.place,
+            });
+        }
+    }
+
+    let mut clubs = by_club
+        .into_values()
+        .map(|mut club| {
+            if counting > 0 && club.members.len() == counting {
+                club.score = Some(match scoring.mode {
+                    ClubScoringMode::TimeSum => club.members.iter().map(|m| m.time).sum(),
+                    ClubScoringMode::PlaceSum => club.members.iter().map(|m| m.place as i64).sum(),
+                });
+            }
+            club
+        })
+        .collect::<Vec<_>>();
+    clubs.sort_by(|a, b| {
+        a.score
+            .is_none()
+            .cmp(&b.score.is_none())
+            .then_with(|| a.score.cmp(&b.score))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let club_places = places(clubs.iter().filter_map(|c| c.score));
+    for (club, place) in clubs.iter_mut().zip(club_places) {
+        club.place = Some(place);
+    }
+
+    RaceClubResults {
+        race_name: results.race.name,
+        scoring,
+        clubs,
+    }
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_club_results(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let Some(competition) = competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)
+                .optional()?
+            else {
+                return QueryResult::Ok(None);
+            };
+            let scorings = club_scorings::table
+                .inner_join(races::table)
+                .filter(races::competition_id.eq(competition_id))
+                .select(ClubScoring::as_select())
+                .load(conn)?
+                .into_iter()
+                .map(|scoring| (scoring.race_id, scoring))
+                .collect::<HashMap<_, _>>();
+            let results = crate::results::load_shown_rankings(conn, competition_id)?;
+            let clubs = ClubDirectory::load(conn)?;
+            let races = results
+                .into_iter()
+                .filter_map(|results| {
+                    let scoring = *scorings.get(&results.race.id)?;
+                    Some(score_clubs(results, scoring, &clubs))
+                })
+                .collect::<Vec<_>>();
+            Ok(Some((competition, races)))
+        })
+        .await?;
+    let (competition_info, races) = data
+        .ok_or_else(|| Error::NotFound(format!("No competition for id {competition_id} found")))?;
+
+    state.render_template(
+        "club_results.html",
+        ClubResultListData {
+            races,
+            competition_info,
+        },
+    )
+}
@@ -0,0 +1,223 @@
+//! Routes for filing a correction request from the public results page
+use crate::app_state::{self, AppState};
+use crate::database::correction_requests::NewCorrectionRequest;
+use crate::database::schema::{bib_numbers, categories, participants, races, starts};
+use crate::database::shared_models::CorrectionKind;
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::{Form, Path};
+use axum::response::{Html, Redirect};
+use axum::Router;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::{Deserialize, Serialize};
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/participants/{participant_id}/correction_request.html",
+            axum::routing::get(render_correction_form),
+        )
+        .route(
+            "/participants/{participant_id}/correction_request",
+            axum::routing::post(file_correction_request),
+        )
+}
+
+/// The participant a correction request is filed for
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = participants)]
+#[diesel(check_for_backend(Sqlite))]
+struct ParticipantInfo {
+    id: Id,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    first_name: String,
+    last_name: String,
+    category_id: Id,
+    #[diesel(select_expression = categories::label)]
+    category: String,
+    #[diesel(select_expression = races::id)]
+    race_id: Id,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = races::competition_id)]
+    competition_id: Id,
+}
+
+/// Data used to render the correction form
+///
+/// see `templates/correction_request.html` for the template
+#[derive(Serialize)]
+struct CorrectionFormData {
+    participant: ParticipantInfo,
+    /// all categories of the race of the participant as `(id, label)`
+    categories: Vec<(Id, String)>,
+    kinds: [CorrectionKind; 3],
+}
+
+fn load_participant(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<Option<ParticipantInfo>> {
+    participants::table
+        .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+        .left_join(bib_numbers::table)
+        .filter(participants::id.eq(participant_id))
+        .select(ParticipantInfo::as_select())
+        .first(conn)
+        .optional()
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_correction_form(
+    state: AppState,
+    Path(participant_id): Path<Id>,
+) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let Some(participant) = load_participant(conn, participant_id)? else {
+                return QueryResult::Ok(None);
+            };
+            let categories = categories::table
+                .inner_join(starts::table)
+                .filter(starts::race_id.eq(participant.race_id))
+                .order_by(categories::id)
+                .select((categories::id, categories::label))
+                .load(conn)?;
+            Ok(Some((participant, categories)))
+        })
+        .await?;
+    let (participant, categories) =
+        data.ok_or_else(|| Error::NotFound(format!("No participant with id {participant_id}")))?;
+    state.render_template(
+        "correction_request.html",
+        CorrectionFormData {
+            participant,
+            categories,
+            kinds: CorrectionKind::ALL,
+        },
+    )
+}
+
+/// Parse a duration like `45:12`, `1:05:30` or `45:12.3` into milliseconds
+fn parse_duration(s: &str) -> Option<i64> {
+    let parts = s.trim().split(':').collect::<Vec<_>>();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [minutes, seconds] => ("0", *minutes, *seconds),
+        [hours, minutes, seconds] => (*hours, *minutes, *seconds),
+        _ => return None,
+    };
+    let hours = hours.parse::<i64>().ok().filter(|h| *h >= 0)?;
+    let minutes = minutes
+        .parse::<i64>()
+        .ok()
+        .filter(|m| (0..60).contains(m))?;
+    let (seconds, tenths) = seconds.split_once('.').unwrap_or((seconds, "0"));
+    let seconds = seconds
+        .parse::<i64>()
+        .ok()
+        .filter(|s| (0..60).contains(s))?;
+    let millis = format!("{tenths:0<3}").get(..3)?.parse::<i64>().ok()?;
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+/// Data returned from the correction form
+#[derive(Deserialize, Debug)]
+struct CorrectionForm {
+    kind: String,
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_string"
+    )]
+    requested_time: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::registration::parse_optional_number"
+    )]
+    requested_category_id: Option<Id>,
+    reason: String,
+    contact: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn file_correction_request(
+    state: AppState,
+    Path(participant_id): Path<Id>,
+    form: Form<CorrectionForm>,
+) -> Result<Redirect> {
+    let form = form.0;
+    let kind = form
+        .kind
+        .parse::<CorrectionKind>()
+        .map_err(Error::InvalidInput)?;
+    let reason = form.reason.trim().to_owned();
+    if reason.is_empty() {
+        return Err(Error::InvalidInput(String::from(
+            "A correction request needs a reason",
+        )));
+    }
+    let contact = form.contact.trim().to_owned();
+    if contact.is_empty() {
+        return Err(Error::InvalidInput(String::from(
+            "A correction request needs contact data",
+        )));
+    }
+    let requested_time_ms = match (kind, form.requested_time) {
+        (CorrectionKind::Time, Some(time)) => Some(
+            parse_duration(&time)
+                .ok_or_else(|| Error::InvalidInput(format!("Invalid time: {time}")))?,
+        ),
+        (CorrectionKind::Time, None) => {
+            return Err(Error::InvalidInput(String::from(
+                "A time correction needs the correct time",
+            )));
+        }
+        _ => None,
+    };
+    let requested_category_id = match kind {
+        CorrectionKind::Category => Some(form.requested_category_id.ok_or_else(|| {
+            Error::InvalidInput(String::from("A category correction needs a category"))
+        })?),
+        _ => None,
+    };
+
+    let competition_id = state
+        .with_connection(move |conn| {
+            let Some(participant) = load_participant(conn, participant_id)? else {
+                return QueryResult::Ok(Err(Error::NotFound(format!(
+                    "No participant with id {participant_id}"
+                ))));
+            };
+            if let Some(category_id) = requested_category_id {
+                let matches = crate::database::correction_requests::category_matches_race(
+                    conn,
+                    participant_id,
+                    category_id,
+                )?;
+                if !matches || category_id == participant.category_id {
+                    return Ok(Err(Error::InvalidInput(format!(
+                        "Category {category_id} is not a different category of race {}",
+                        participant.race
+                    ))));
+                }
+            }
+            crate::database::correction_requests::file_request(
+                conn,
+                &NewCorrectionRequest {
+                    participant_id,
+                    kind,
+                    requested_time_ms,
+                    requested_category_id,
+                    reason,
+                    contact,
+                },
+            )?;
+            Ok(Ok(participant.competition_id))
+        })
+        .await??;
+    let base_url = state.base_url();
+    Ok(Redirect::to(&format!(
+        "{base_url}/{competition_id}/results.html"
+    )))
+}
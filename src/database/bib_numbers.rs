@@ -0,0 +1,88 @@
+//! Assignment of bib numbers ("start numbers") to participants
+//!
+//! Each start can be configured with a range of bib numbers. Participants
+//! get the lowest free number of the range of their start, unless an admin
+//! sets a number explicitly. Bib numbers are unique per competition, which is
+//! enforced by the `bib_numbers` table.
+use super::schema::{bib_numbers, categories, participants, starts};
+use super::Id;
+use diesel::prelude::*;
+
+/// Assign a bib number to the given participant
+///
+/// * If `requested_bib` is set, that number is used as manual override
+/// * Otherwise an already assigned number is kept, unless the participant
+///   moved to a start with a range that does not contain it
+/// * Otherwise the lowest free number from the range of the participants start
+///   is assigned
+///
+/// Returns the bib number of the participant, which is `None` if there is no range
+/// configured for the relevant start or if that range is exhausted. A number
+/// outside the range is kept if the range is exhausted
+pub(crate) fn assign_bib_number(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    competition_id: Id,
+    requested_bib: Option<i32>,
+) -> QueryResult<Option<i32>> {
+    conn.transaction(|conn| {
+        if let Some(bib) = requested_bib {
+            diesel::insert_into(bib_numbers::table)
+                .values((
+                    bib_numbers::participant_id.eq(participant_id),
+                    bib_numbers::competition_id.eq(competition_id),
+                    bib_numbers::bib.eq(bib),
+                ))
+                .on_conflict(bib_numbers::participant_id)
+                .do_update()
+                .set(bib_numbers::bib.eq(bib))
+                .execute(conn)?;
+            return Ok(Some(bib));
+        }
+
+        let existing = bib_numbers::table
+            .find(participant_id)
+            .select(bib_numbers::bib)
+            .first::<i32>(conn)
+            .optional()?;
+        let range = participants::table
+            .inner_join(categories::table.inner_join(starts::table))
+            .filter(participants::id.eq(participant_id))
+            .select((starts::first_bib, starts::last_bib))
+            .first::<(Option<i32>, Option<i32>)>(conn)?;
+        let (Some(first_bib), Some(last_bib)) = range else {
+            return Ok(existing);
+        };
+        if existing.is_some_and(|bib| (first_bib..=last_bib).contains(&bib)) {
+            return Ok(existing);
+        }
+
+        let used = bib_numbers::table
+            .filter(bib_numbers::competition_id.eq(competition_id))
+            .filter(bib_numbers::bib.between(first_bib, last_bib))
+            .order_by(bib_numbers::bib)
+            .select(bib_numbers::bib)
+            .load::<i32>(conn)?;
+        // `used` is sorted, so the first number that does not match
+        // its expected position is a gap we can fill
+        let free = (first_bib..=last_bib)
+            .zip(used.iter().map(Some).chain(std::iter::repeat(None)))
+            .find(|(candidate, used)| Some(candidate) != *used)
+            .map(|(candidate, _)| candidate);
+
+        let Some(bib) = free else {
+            return Ok(existing);
+        };
+        diesel::insert_into(bib_numbers::table)
+            .values((
+                bib_numbers::participant_id.eq(participant_id),
+                bib_numbers::competition_id.eq(competition_id),
+                bib_numbers::bib.eq(bib),
+            ))
+            .on_conflict(bib_numbers::participant_id)
+            .do_update()
+            .set(bib_numbers::bib.eq(bib))
+            .execute(conn)?;
+        Ok(Some(bib))
+    })
+}
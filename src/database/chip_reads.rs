@@ -0,0 +1,206 @@
+//! Storing chip reads and turning them into finish and split times
+//!
+//! All reads are stored, even for chips that are not assigned to a participant
+//! yet. Once a chip is assigned its reads are applied: reads of the `start`
+//! reader are start mat reads, reads of a reader named like a timing point of
+//! the participants race are split times, all other reads are finish reads.
+//! The first read counts in all cases, later reads e.g. of a runner crossing
+//! the finish mat again are kept but ignored. Finish reads within
+//! [`MIN_FINISH_DURATION`] after the start, e.g. of a runner walking over the
+//! finish mat on the way to the start, are rejected.
+use super::schema::{
+    categories, chip_reads, chips, competitions, participants, races, start_reads, starts,
+    timing_points,
+};
+use super::Id;
+use crate::chip_timing::ChipRead;
+use diesel::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use time::{Duration, PrimitiveDateTime};
+
+/// Name of the reader at the start line
+pub(crate) const START_READER: &str = "start";
+
+/// Default window in which repeated reads of the same chip are ignored
+pub(crate) const DEFAULT_DEDUP_WINDOW: Duration = Duration::seconds(5);
+
+/// Finish reads earlier than this after the start are not a finish
+pub(crate) const MIN_FINISH_DURATION: Duration = Duration::seconds(30);
+
+/// Outcome of storing a batch of reads
+#[derive(Debug, Default)]
+pub(crate) struct ImportSummary {
+    /// number of newly stored reads
+    pub(crate) stored: usize,
+    /// number of reads ignored as duplicates
+    pub(crate) duplicates: usize,
+    /// chips without a participant assigned
+    pub(crate) unassigned: BTreeSet<String>,
+    /// number of finish reads rejected as too close to the start
+    pub(crate) rejected: usize,
+}
+
+/// Store chip reads for a competition and apply them to the assigned participants
+///
+/// A read is ignored if the same reader already registered the same chip within
+/// `dedup_window`, as readers report a chip multiple times while it passes
+pub(crate) fn store_reads(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    reads: &[ChipRead],
+    dedup_window: Duration,
+) -> QueryResult<ImportSummary> {
+    conn.transaction(|conn| {
+        let mut summary = ImportSummary::default();
+        let mut chips = BTreeSet::new();
+        for read in reads {
+            let duplicate = diesel::select(diesel::dsl::exists(
+                chip_reads::table
+                    .filter(chip_reads::competition_id.eq(competition_id))
+                    .filter(chip_reads::chip.eq(&read.chip))
+                    .filter(chip_reads::reader.eq(&read.reader))
+                    .filter(
+                        chip_reads::time
+                            .between(read.time - dedup_window, read.time + dedup_window),
+                    ),
+            ))
+            .get_result::<bool>(conn)?;
+            if duplicate {
+                summary.duplicates += 1;
+                continue;
+            }
+            diesel::insert_into(chip_reads::table)
+                .values((
+                    chip_reads::competition_id.eq(competition_id),
+                    chip_reads::chip.eq(&read.chip),
+                    chip_reads::time.eq(read.time),
+                    chip_reads::reader.eq(&read.reader),
+                ))
+                .execute(conn)?;
+            summary.stored += 1;
+            chips.insert(read.chip.clone());
+        }
+        for chip in chips {
+            match apply_chip(conn, competition_id, &chip)? {
+                Some(rejected) => summary.rejected += rejected,
+                None => {
+                    summary.unassigned.insert(chip);
+                }
+            }
+        }
+        Ok(summary)
+    })
+}
+
+/// Find the competition a single read belongs to
+///
+/// Reads do not contain the competition, so this uses the competitions the chip
+/// is assigned in. If there are several of them, or none, the competition
+/// taking place on the day of the read is used
+pub(crate) fn competition_for_read(
+    conn: &mut SqliteConnection,
+    read: &ChipRead,
+) -> QueryResult<Option<Id>> {
+    let assigned = chips::table
+        .filter(chips::chip.eq(&read.chip))
+        .select(chips::competition_id)
+        .load::<Id>(conn)?;
+    if let [competition_id] = assigned[..] {
+        return Ok(Some(competition_id));
+    }
+    let mut on_that_day = competitions::table
+        .filter(competitions::date.eq(read.time.date()))
+        .into_boxed();
+    if !assigned.is_empty() {
+        on_that_day = on_that_day.filter(competitions::id.eq_any(assigned));
+    }
+    let candidates = on_that_day
+        .select(competitions::id)
+        .limit(2)
+        .load::<Id>(conn)?;
+    match candidates[..] {
+        [competition_id] => Ok(Some(competition_id)),
+        _ => Ok(None),
+    }
+}
+
+/// Turn the stored reads of a chip into finish and split times
+///
+/// Finish reads before the start mat read, or the gun time if there is none,
+/// plus [`MIN_FINISH_DURATION`] are ignored. Returns the number of these
+/// rejected reads, or `None` if the chip is not assigned to a participant
+pub(crate) fn apply_chip(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    chip: &str,
+) -> QueryResult<Option<usize>> {
+    let Some(participant_id) = chips::table
+        .find((competition_id, chip))
+        .select(chips::participant_id)
+        .first::<Id>(conn)
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let (gun_time, start_time) = participants::table
+        .inner_join(categories::table.inner_join(starts::table))
+        .filter(participants::id.eq(participant_id))
+        .select((starts::gun_time, starts::time))
+        .first::<(Option<PrimitiveDateTime>, PrimitiveDateTime)>(conn)?;
+    let timing_points =
+        participants::table
+            .inner_join(categories::table.inner_join(
+                starts::table.inner_join(races::table.inner_join(timing_points::table)),
+            ))
+            .filter(participants::id.eq(participant_id))
+            .select((timing_points::name, timing_points::id))
+            .load::<(String, Id)>(conn)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+    let reads = chip_reads::table
+        .filter(chip_reads::competition_id.eq(competition_id))
+        .filter(chip_reads::chip.eq(chip))
+        .order_by(chip_reads::time)
+        .select((chip_reads::reader, chip_reads::time))
+        .load::<(String, PrimitiveDateTime)>(conn)?;
+
+    let start = reads
+        .iter()
+        .find(|(reader, _)| reader == START_READER)
+        .map(|(_, time)| *time);
+    let earliest_finish = start.unwrap_or(gun_time.unwrap_or(start_time)) + MIN_FINISH_DURATION;
+    let mut splits = HashMap::new();
+    let mut finish = None;
+    let mut rejected = 0;
+    for (reader, time) in reads {
+        if reader == START_READER {
+            continue;
+        } else if let Some(timing_point_id) = timing_points.get(&reader) {
+            splits.entry(*timing_point_id).or_insert(time);
+        } else if time < earliest_finish {
+            rejected += 1;
+        } else {
+            finish = finish.or(Some(time));
+        }
+    }
+    if let Some(start) = start {
+        diesel::insert_into(start_reads::table)
+            .values((
+                start_reads::participant_id.eq(participant_id),
+                start_reads::time.eq(start),
+            ))
+            .on_conflict(start_reads::participant_id)
+            .do_update()
+            .set(start_reads::time.eq(start))
+            .execute(conn)?;
+    }
+    for (timing_point_id, time) in splits {
+        super::time_records::record_split_time(conn, participant_id, timing_point_id, time)?;
+    }
+    if let Some(finish) = finish {
+        // a recorded finish time is never replaced, e.g. when a chip is
+        // applied again after new reads arrived
+        super::time_records::insert_missing_finish_time(conn, participant_id, finish)?;
+    }
+    Ok(Some(rejected))
+}
@@ -0,0 +1,69 @@
+//! Normalization of the free-text club names entered by participants
+//!
+//! Participants enter their club as free text, so the same club shows up
+//! with different spellings. Each spelling is normalized and then looked up
+//! in the aliases and names of the canonical clubs.
+use super::schema::{club_aliases, clubs};
+use super::{normalize_name, Id};
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+/// A club as used for the club results
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum ClubKey {
+    /// a canonical club from the `clubs` table
+    Club(Id),
+    /// a club name without a canonical club, keyed by the normalized name
+    Unknown(String),
+}
+
+/// All canonical clubs with their aliases
+pub(crate) struct ClubDirectory {
+    /// canonical names by club id
+    names: HashMap<Id, String>,
+    /// normalized names and aliases to club id
+    lookup: HashMap<String, Id>,
+}
+
+impl ClubDirectory {
+    pub(crate) fn load(conn: &mut SqliteConnection) -> QueryResult<Self> {
+        let names = clubs::table
+            .select((clubs::id, clubs::name))
+            .load::<(Id, String)>(conn)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let mut lookup = names
+            .iter()
+            .map(|(id, name)| (normalize_name(name), *id))
+            .collect::<HashMap<_, _>>();
+        // aliases take precedence over the canonical names of other clubs
+        lookup.extend(
+            club_aliases::table
+                .select((club_aliases::alias, club_aliases::club_id))
+                .load::<(String, Id)>(conn)?,
+        );
+        Ok(Self { names, lookup })
+    }
+
+    /// Resolve a free-text club name
+    ///
+    /// Returns `None` for participants without a club
+    pub(crate) fn resolve(&self, club: Option<&str>) -> Option<ClubKey> {
+        let normalized = normalize_name(club?);
+        if normalized.is_empty() {
+            return None;
+        }
+        Some(match self.lookup.get(&normalized) {
+            Some(id) => ClubKey::Club(*id),
+            None => ClubKey::Unknown(normalized),
+        })
+    }
+
+    /// The canonical name of a club, if there is one
+    pub(crate) fn name(&self, key: &ClubKey) -> Option<&str> {
+        match key {
+            ClubKey::Club(id) => self.names.get(id).map(String::as_str),
+            ClubKey::Unknown(_) => None,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Correction requests filed by participants
+//!
+//! Accepting a request applies the requested change: a wrong time is fixed
+//! with a time adjustment, a wrong category by moving the participant to the
+//! requested category. Other requests only record the decision, the admin
+//! applies those changes manually.
+use super::schema::{categories, correction_requests, participants, starts};
+use super::shared_models::{CorrectionKind, CorrectionRequest, CorrectionStatus};
+use super::Id;
+use diesel::prelude::*;
+use time::PrimitiveDateTime;
+
+/// A correction request as filed on the public results page
+#[derive(Debug)]
+pub(crate) struct NewCorrectionRequest {
+    pub(crate) participant_id: Id,
+    pub(crate) kind: CorrectionKind,
+    pub(crate) requested_time_ms: Option<i64>,
+    pub(crate) requested_category_id: Option<Id>,
+    pub(crate) reason: String,
+    pub(crate) contact: String,
+}
+
+/// Outcome of handling a correction request
+#[derive(Debug)]
+pub(crate) enum CorrectionOutcome {
+    /// the request was handled
+    Handled,
+    /// the request was already accepted or rejected
+    NotOpen,
+    /// the time cannot be corrected, as the participant has no ranking time
+    NotRanked,
+    /// the requested category does not belong to the race of the participant
+    InvalidCategory,
+}
+
+fn now() -> PrimitiveDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
+/// Whether the category belongs to the same race as the current category of
+/// the participant
+pub(crate) fn category_matches_race(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    category_id: Id,
+) -> QueryResult<bool> {
+    let race_id = participants::table
+        .inner_join(categories::table.inner_join(starts::table))
+        .filter(participants::id.eq(participant_id))
+        .select(starts::race_id)
+        .first::<Id>(conn)?;
+    diesel::select(diesel::dsl::exists(
+        categories::table
+            .inner_join(starts::table)
+            .filter(categories::id.eq(category_id))
+            .filter(starts::race_id.eq(race_id)),
+    ))
+    .get_result(conn)
+}
+
+/// Store a new correction request, returns its id
+pub(crate) fn file_request(
+    conn: &mut SqliteConnection,
+    request: &NewCorrectionRequest,
+) -> QueryResult<Id> {
+    diesel::insert_into(correction_requests::table)
+        .values((
+            correction_requests::participant_id.eq(request.participant_id),
+            correction_requests::kind.eq(request.kind),
+            correction_requests::requested_time_ms.eq(request.requested_time_ms),
+            correction_requests::requested_category_id.eq(request.requested_category_id),
+            correction_requests::reason.eq(&request.reason),
+            correction_requests::contact.eq(&request.contact),
+            correction_requests::created_at.eq(now()),
+            correction_requests::status.eq(CorrectionStatus::Open),
+        ))
+        .returning(correction_requests::id)
+        .get_result(conn)
+}
+
+/// Load a single correction request
+pub(crate) fn load_request(
+    conn: &mut SqliteConnection,
+    request_id: Id,
+) -> QueryResult<CorrectionRequest> {
+    correction_requests::table
+        .find(request_id)
+        .select(CorrectionRequest::as_select())
+        .first(conn)
+}
+
+/// Store the decision about an open request
+fn mark_handled(
+    conn: &mut SqliteConnection,
+    request_id: Id,
+    status: CorrectionStatus,
+    comment: Option<&str>,
+    handled_by: &str,
+) -> QueryResult<()> {
+    diesel::update(correction_requests::table.find(request_id))
+        .set((
+            correction_requests::status.eq(status),
+            correction_requests::admin_comment.eq(comment),
+            correction_requests::handled_by.eq(handled_by),
+            correction_requests::handled_at.eq(now()),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Accept a request and apply the requested change
+///
+/// `current_time` is the current ranking time of the participant in
+/// milliseconds, it is needed to turn a requested time into a time adjustment
+pub(crate) fn accept_request(
+    conn: &mut SqliteConnection,
+    request: &CorrectionRequest,
+    current_time: Option<i64>,
+    comment: Option<&str>,
+    handled_by: &str,
+) -> QueryResult<CorrectionOutcome> {
+    conn.transaction(|conn| {
+        if request.status != CorrectionStatus::Open {
+            return Ok(CorrectionOutcome::NotOpen);
+        }
+        match request.kind {
+            CorrectionKind::Time => {
+                let (Some(requested), Some(current)) = (request.requested_time_ms, current_time)
+                else {
+                    return Ok(CorrectionOutcome::NotRanked);
+                };
+                if requested != current {
+                    super::time_records::add_time_adjustment(
+                        conn,
+                        request.participant_id,
+                        requested - current,
+                        &format!("Correction request {}: {}", request.id, request.reason),
+                        handled_by,
+                    )?;
+                }
+            }
+            CorrectionKind::Category => {
+                let Some(category_id) = request.requested_category_id else {
+                    return Ok(CorrectionOutcome::InvalidCategory);
+                };
+                if !category_matches_race(conn, request.participant_id, category_id)? {
+                    return Ok(CorrectionOutcome::InvalidCategory);
+                }
+                diesel::update(participants::table.find(request.participant_id))
+                    .set(participants::category_id.eq(category_id))
+                    .execute(conn)?;
+            }
+            CorrectionKind::Other => {}
+        }
+        mark_handled(
+            conn,
+            request.id,
+            CorrectionStatus::Accepted,
+            comment,
+            handled_by,
+        )?;
+        Ok(CorrectionOutcome::Handled)
+    })
+}
+
+/// Reject a request without changing any data
+pub(crate) fn reject_request(
+    conn: &mut SqliteConnection,
+    request: &CorrectionRequest,
+    comment: &str,
+    handled_by: &str,
+) -> QueryResult<CorrectionOutcome> {
+    if request.status != CorrectionStatus::Open {
+        return Ok(CorrectionOutcome::NotOpen);
+    }
+    mark_handled(
+        conn,
+        request.id,
+        CorrectionStatus::Rejected,
+        Some(comment),
+        handled_by,
+    )?;
+    Ok(CorrectionOutcome::Handled)
+}
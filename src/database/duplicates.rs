@@ -0,0 +1,255 @@
+//! Detection and merging of duplicate registrations
+//!
+//! Two participants of the same competition are likely the same athlete if
+//! they share the normalized name, the birth year and the club. Club names
+//! are resolved via the [`ClubDirectory`], so known aliases of a club match
+//! as well.
+use super::clubs::{ClubDirectory, ClubKey};
+use super::persons::name_key;
+use super::schema::{
+    bib_numbers, categories, chips, correction_requests, participants,
+    participants_in_special_category, payments, races, split_times, start_reads, starts,
+    team_members, time_adjustments, time_records,
+};
+use super::shared_models::PaymentStatus;
+use super::Id;
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+/// The attributes compared to detect duplicates
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct DuplicateKey {
+    name: String,
+    birth_year: i32,
+    club: Option<ClubKey>,
+}
+
+impl DuplicateKey {
+    fn new(
+        clubs: &ClubDirectory,
+        first_name: &str,
+        last_name: &str,
+        birth_year: i32,
+        club: Option<&str>,
+    ) -> Self {
+        Self {
+            name: name_key(first_name, last_name),
+            birth_year,
+            club: clubs.resolve(club),
+        }
+    }
+}
+
+/// Find a participant of the competition that is likely the same athlete
+///
+/// `exclude` is the participant that is currently edited
+pub(crate) fn find_duplicate(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    first_name: &str,
+    last_name: &str,
+    birth_year: i32,
+    club: Option<&str>,
+    exclude: Option<Id>,
+) -> QueryResult<Option<Id>> {
+    let clubs = ClubDirectory::load(conn)?;
+    let key = DuplicateKey::new(&clubs, first_name, last_name, birth_year, club);
+    let candidates = participants::table
+        .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+        .filter(races::competition_id.eq(competition_id))
+        .filter(participants::birth_year.eq(birth_year))
+        .order_by(participants::id)
+        .select((
+            participants::id,
+            participants::first_name,
+            participants::last_name,
+            participants::club,
+        ))
+        .load::<(Id, String, String, Option<String>)>(conn)?;
+    Ok(candidates
+        .into_iter()
+        .filter(|(id, ..)| Some(*id) != exclude)
+        .find(|(_, first_name, last_name, club)| {
+            DuplicateKey::new(&clubs, first_name, last_name, birth_year, club.as_deref()) == key
+        })
+        .map(|(id, ..)| id))
+}
+
+/// Group all participants of a competition that are likely the same athlete
+///
+/// Only groups with at least two participants are returned. The participant
+/// ids of each group and the groups are ordered by the id of the first
+/// registration.
+pub(crate) fn duplicate_groups(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<Vec<Vec<Id>>> {
+    let clubs = ClubDirectory::load(conn)?;
+    let participants = participants::table
+        .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+        .filter(races::competition_id.eq(competition_id))
+        .order_by(participants::id)
+        .select((
+            participants::id,
+            participants::first_name,
+            participants::last_name,
+            participants::birth_year,
+            participants::club,
+        ))
+        .load::<(Id, String, String, i32, Option<String>)>(conn)?;
+    let mut groups = Vec::<Vec<Id>>::new();
+    let mut by_key = HashMap::<DuplicateKey, usize>::new();
+    for (id, first_name, last_name, birth_year, club) in participants {
+        let key = DuplicateKey::new(&clubs, &first_name, &last_name, birth_year, club.as_deref());
+        match by_key.get(&key) {
+            Some(idx) => groups[*idx].push(id),
+            None => {
+                by_key.insert(key, groups.len());
+                groups.push(vec![id]);
+            }
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    Ok(groups)
+}
+
+/// Whether the participant has timing data or is part of a relay team
+///
+/// Such participants are never removed by a merge
+fn has_results(conn: &mut SqliteConnection, participant_id: Id) -> QueryResult<bool> {
+    let has_time = diesel::select(diesel::dsl::exists(
+        time_records::table.filter(time_records::participant_id.eq(participant_id)),
+    ))
+    .get_result::<bool>(conn)?;
+    let has_splits = diesel::select(diesel::dsl::exists(
+        split_times::table.filter(split_times::participant_id.eq(participant_id)),
+    ))
+    .get_result::<bool>(conn)?;
+    let has_start = diesel::select(diesel::dsl::exists(
+        start_reads::table.filter(start_reads::participant_id.eq(participant_id)),
+    ))
+    .get_result::<bool>(conn)?;
+    let in_team = diesel::select(diesel::dsl::exists(
+        team_members::table.filter(team_members::participant_id.eq(participant_id)),
+    ))
+    .get_result::<bool>(conn)?;
+    Ok(has_time || has_splits || has_start || in_team)
+}
+
+/// Outcome of merging two participants
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum MergeOutcome {
+    Merged,
+    /// `from` has results and cannot be removed
+    HasResults,
+    /// both participants have a paid or refunded entry fee, which is only
+    /// stored once per participant
+    BothPaymentsSettled,
+}
+
+/// Merge the participant `from` into the participant `into`
+///
+/// Special categories, chips, time adjustments and correction requests of
+/// `from` are moved, the bib number only if `into` has none. The payment of
+/// `from` is moved if `into` has none or if it is already paid or refunded,
+/// so settled payments are never lost. If either of them was confirmed, the
+/// merged participant is confirmed. `from` is deleted afterwards and a freed
+/// spot is given to the waitlist of the race of `from`.
+///
+/// Nothing is changed unless the outcome is [`MergeOutcome::Merged`]
+pub(crate) fn merge_participants(
+    conn: &mut SqliteConnection,
+    into: Id,
+    from: Id,
+) -> QueryResult<MergeOutcome> {
+    conn.transaction(|conn| {
+        let into_waitlisted = participants::table
+            .find(into)
+            .select(participants::waitlisted_at.is_not_null())
+            .first::<bool>(conn)?;
+        // the duplicate might have registered for another race
+        let (from_waitlisted, from_race_id) = participants::table
+            .inner_join(categories::table.inner_join(starts::table))
+            .filter(participants::id.eq(from))
+            .select((participants::waitlisted_at.is_not_null(), starts::race_id))
+            .first::<(bool, Id)>(conn)?;
+        if has_results(conn, from)? {
+            return Ok(MergeOutcome::HasResults);
+        }
+        let payment_status = |conn: &mut SqliteConnection, participant_id: Id| {
+            payments::table
+                .find(participant_id)
+                .select(payments::status)
+                .first::<PaymentStatus>(conn)
+                .optional()
+        };
+        let into_payment = payment_status(conn, into)?;
+        let from_payment = payment_status(conn, from)?;
+        let settled = |status: Option<PaymentStatus>| {
+            status.is_some_and(|status| status != PaymentStatus::Open)
+        };
+        if settled(into_payment) && settled(from_payment) {
+            return Ok(MergeOutcome::BothPaymentsSettled);
+        }
+
+        let special_categories = participants_in_special_category::table
+            .filter(participants_in_special_category::participant_id.eq(from))
+            .select(participants_in_special_category::special_category_id)
+            .load::<Id>(conn)?;
+        for special_category_id in special_categories {
+            diesel::insert_or_ignore_into(participants_in_special_category::table)
+                .values((
+                    participants_in_special_category::participant_id.eq(into),
+                    participants_in_special_category::special_category_id.eq(special_category_id),
+                ))
+                .execute(conn)?;
+        }
+        diesel::delete(
+            participants_in_special_category::table
+                .filter(participants_in_special_category::participant_id.eq(from)),
+        )
+        .execute(conn)?;
+
+        let into_has_bib = diesel::select(diesel::dsl::exists(
+            bib_numbers::table.filter(bib_numbers::participant_id.eq(into)),
+        ))
+        .get_result::<bool>(conn)?;
+        if into_has_bib {
+            diesel::delete(bib_numbers::table.filter(bib_numbers::participant_id.eq(from)))
+                .execute(conn)?;
+        } else {
+            diesel::update(bib_numbers::table.filter(bib_numbers::participant_id.eq(from)))
+                .set(bib_numbers::participant_id.eq(into))
+                .execute(conn)?;
+        }
+        if from_payment.is_some() && (into_payment.is_none() || settled(from_payment)) {
+            diesel::delete(payments::table.find(into)).execute(conn)?;
+            diesel::update(payments::table.filter(payments::participant_id.eq(from)))
+                .set(payments::participant_id.eq(into))
+                .execute(conn)?;
+        }
+        diesel::update(chips::table.filter(chips::participant_id.eq(from)))
+            .set(chips::participant_id.eq(into))
+            .execute(conn)?;
+        diesel::update(time_adjustments::table.filter(time_adjustments::participant_id.eq(from)))
+            .set(time_adjustments::participant_id.eq(into))
+            .execute(conn)?;
+        diesel::update(
+            correction_requests::table.filter(correction_requests::participant_id.eq(from)),
+        )
+        .set(correction_requests::participant_id.eq(into))
+        .execute(conn)?;
+
+        if into_waitlisted && !from_waitlisted {
+            diesel::update(participants::table.find(into))
+                .set(participants::waitlisted_at.eq(None::<time::PrimitiveDateTime>))
+                .execute(conn)?;
+        }
+        diesel::delete(participants::table.find(from)).execute(conn)?;
+        // if `into` was waitlisted in the same race it took the spot already
+        if !from_waitlisted {
+            super::registrations::promote_waitlist(conn, from_race_id)?;
+        }
+        Ok(MergeOutcome::Merged)
+    })
+}
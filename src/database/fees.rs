@@ -0,0 +1,158 @@
+//! Entry fees and their payment
+//!
+//! Each race can have a fee schedule with a base fee, a surcharge for late
+//! registrations and discounts for young participants and for members of a
+//! club from the club list. The amount due is computed once when a
+//! participant registers and stored with the payment state, so later
+//! changes of the schedule do not affect existing registrations. Only if a
+//! participant switches to another race an open amount is computed again.
+//! The late surcharge always depends on the time of the registration,
+//! registrations without a known time never pay it.
+use super::clubs::{ClubDirectory, ClubKey};
+use super::schema::{categories, competitions, participants, payments, race_fees, races, starts};
+use super::shared_models::{FeeSchedule, PaymentStatus};
+use super::Id;
+use diesel::prelude::*;
+use time::PrimitiveDateTime;
+
+impl FeeSchedule {
+    /// The amount due in cents, it is never negative
+    ///
+    /// `age` is the age the participant reaches in the year of the
+    /// competition
+    pub(crate) fn amount(
+        &self,
+        registered_at: Option<PrimitiveDateTime>,
+        age: i32,
+        club_member: bool,
+    ) -> i32 {
+        let mut amount = self.base_fee;
+        if self
+            .late_from
+            .zip(registered_at)
+            .is_some_and(|(late, registered_at)| registered_at >= late)
+        {
+            amount += self.late_surcharge;
+        }
+        if self.youth_max_age.is_some_and(|max_age| age <= max_age) {
+            amount -= self.youth_discount;
+        }
+        if club_member {
+            amount -= self.club_discount;
+        }
+        amount.max(0)
+    }
+}
+
+/// Compute the amount due for a participant
+///
+/// Returns `None` if the race of the participant has no fee schedule
+fn amount_due(
+    conn: &mut SqliteConnection,
+    clubs: &ClubDirectory,
+    participant_id: Id,
+) -> QueryResult<Option<i32>> {
+    let (birth_year, club, registered_at, date, schedule) = participants::table
+        .inner_join(
+            categories::table.inner_join(
+                starts::table.inner_join(
+                    races::table
+                        .inner_join(competitions::table)
+                        .left_join(race_fees::table),
+                ),
+            ),
+        )
+        .filter(participants::id.eq(participant_id))
+        .select((
+            participants::birth_year,
+            participants::club,
+            participants::registered_at,
+            competitions::date,
+            Option::<FeeSchedule>::as_select(),
+        ))
+        .first::<(
+            i32,
+            Option<String>,
+            Option<PrimitiveDateTime>,
+            time::Date,
+            Option<FeeSchedule>,
+        )>(conn)?;
+    let club_member = matches!(clubs.resolve(club.as_deref()), Some(ClubKey::Club(_)));
+    Ok(schedule
+        .map(|schedule| schedule.amount(registered_at, date.year() - birth_year, club_member)))
+}
+
+/// Store the amount due for a newly registered participant
+///
+/// Participants of races without a fee schedule get no payment entry
+pub(crate) fn assign_fee(conn: &mut SqliteConnection, participant_id: Id) -> QueryResult<()> {
+    let clubs = ClubDirectory::load(conn)?;
+    if let Some(amount) = amount_due(conn, &clubs, participant_id)? {
+        diesel::insert_or_ignore_into(payments::table)
+            .values((
+                payments::participant_id.eq(participant_id),
+                payments::amount_due.eq(amount),
+            ))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Compute the amount due again after a participant switched to another race
+///
+/// Only open payments are changed, paid or refunded entries are already
+/// settled. Participants of a race without a fee schedule owe nothing.
+pub(crate) fn reassign_fee(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    previous_race_id: Id,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        if super::registrations::race_of_participant(conn, participant_id)? == previous_race_id {
+            return Ok(());
+        }
+        let status = payments::table
+            .find(participant_id)
+            .select(payments::status)
+            .first::<PaymentStatus>(conn)
+            .optional()?;
+        if status.is_some_and(|status| status != PaymentStatus::Open) {
+            return Ok(());
+        }
+        diesel::delete(payments::table.find(participant_id)).execute(conn)?;
+        assign_fee(conn, participant_id)
+    })
+}
+
+/// Compute the amount due for all participants of a competition that were
+/// registered without a fee, e.g. before the fee schedule was set up
+///
+/// Returns the number of new payment entries
+pub(crate) fn assign_missing_fees(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let clubs = ClubDirectory::load(conn)?;
+        let missing = participants::table
+            .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+            .left_join(payments::table)
+            .filter(races::competition_id.eq(competition_id))
+            .filter(payments::participant_id.is_null())
+            .select(participants::id)
+            .load::<Id>(conn)?;
+        let mut assigned = 0;
+        for participant_id in missing {
+            if let Some(amount) = amount_due(conn, &clubs, participant_id)? {
+                diesel::insert_into(payments::table)
+                    .values((
+                        payments::participant_id.eq(participant_id),
+                        payments::amount_due.eq(amount),
+                    ))
+                    .execute(conn)?;
+                assigned += 1;
+            }
+        }
+        Ok(assigned)
+    })
+}
@@ -0,0 +1,202 @@
+//! Capturing finish times as two separate lists
+//!
+//! Small races often record finish times with a stopwatch while someone else
+//! writes down the bibs in the order the participants crossed the finish line.
+//! Both lists are stored with a position and merged by that position: the Nth
+//! time belongs to the Nth bib. Positions are kept contiguous, inserting or
+//! deleting an entry shifts all following entries.
+use super::schema::{bib_numbers, finish_order_bibs, participants, stopwatch_times};
+use super::Id;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use time::PrimitiveDateTime;
+
+/// A single row of the merged lists
+///
+/// Either side might be missing if the lists have different lengths
+#[derive(Debug, Serialize)]
+pub(crate) struct MergeRow {
+    /// position in the finish order, starting at 1
+    pub(crate) position: usize,
+    pub(crate) stopwatch_time_id: Option<Id>,
+    pub(crate) time: Option<PrimitiveDateTime>,
+    pub(crate) finish_bib_id: Option<Id>,
+    pub(crate) bib: Option<i32>,
+    /// participant with the given bib, if there is one
+    pub(crate) participant_id: Option<Id>,
+    pub(crate) first_name: Option<String>,
+    pub(crate) last_name: Option<String>,
+}
+
+/// Add a stopwatch time
+///
+/// The time is appended to the list, unless a `position` is given. In that
+/// case the time is inserted at that position and all following times are moved
+/// back by one
+pub(crate) fn insert_stopwatch_time(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    position: Option<i32>,
+    time: PrimitiveDateTime,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let count = stopwatch_times::table
+            .filter(stopwatch_times::competition_id.eq(competition_id))
+            .count()
+            .get_result::<i64>(conn)? as i32;
+        let position = position.unwrap_or(count + 1).clamp(1, count + 1);
+        diesel::update(stopwatch_times::table)
+            .filter(stopwatch_times::competition_id.eq(competition_id))
+            .filter(stopwatch_times::position.ge(position))
+            .set(stopwatch_times::position.eq(stopwatch_times::position + 1))
+            .execute(conn)?;
+        diesel::insert_into(stopwatch_times::table)
+            .values((
+                stopwatch_times::competition_id.eq(competition_id),
+                stopwatch_times::position.eq(position),
+                stopwatch_times::time.eq(time),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Remove a stopwatch time and move all following times forward by one
+///
+/// Returns the id of the competition the time belonged to
+pub(crate) fn delete_stopwatch_time(conn: &mut SqliteConnection, id: Id) -> QueryResult<Id> {
+    conn.transaction(|conn| {
+        let (competition_id, position) = diesel::delete(stopwatch_times::table.find(id))
+            .returning((stopwatch_times::competition_id, stopwatch_times::position))
+            .get_result::<(Id, i32)>(conn)?;
+        diesel::update(stopwatch_times::table)
+            .filter(stopwatch_times::competition_id.eq(competition_id))
+            .filter(stopwatch_times::position.gt(position))
+            .set(stopwatch_times::position.eq(stopwatch_times::position - 1))
+            .execute(conn)?;
+        Ok(competition_id)
+    })
+}
+
+/// Add a bib to the finish order
+///
+/// Works like [`insert_stopwatch_time`]
+pub(crate) fn insert_finish_bib(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    position: Option<i32>,
+    bib: i32,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let count = finish_order_bibs::table
+            .filter(finish_order_bibs::competition_id.eq(competition_id))
+            .count()
+            .get_result::<i64>(conn)? as i32;
+        let position = position.unwrap_or(count + 1).clamp(1, count + 1);
+        diesel::update(finish_order_bibs::table)
+            .filter(finish_order_bibs::competition_id.eq(competition_id))
+            .filter(finish_order_bibs::position.ge(position))
+            .set(finish_order_bibs::position.eq(finish_order_bibs::position + 1))
+            .execute(conn)?;
+        diesel::insert_into(finish_order_bibs::table)
+            .values((
+                finish_order_bibs::competition_id.eq(competition_id),
+                finish_order_bibs::position.eq(position),
+                finish_order_bibs::bib.eq(bib),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Remove a bib from the finish order and move all following bibs forward by one
+///
+/// Returns the id of the competition the bib belonged to
+pub(crate) fn delete_finish_bib(conn: &mut SqliteConnection, id: Id) -> QueryResult<Id> {
+    conn.transaction(|conn| {
+        let (competition_id, position) = diesel::delete(finish_order_bibs::table.find(id))
+            .returning((
+                finish_order_bibs::competition_id,
+                finish_order_bibs::position,
+            ))
+            .get_result::<(Id, i32)>(conn)?;
+        diesel::update(finish_order_bibs::table)
+            .filter(finish_order_bibs::competition_id.eq(competition_id))
+            .filter(finish_order_bibs::position.gt(position))
+            .set(finish_order_bibs::position.eq(finish_order_bibs::position - 1))
+            .execute(conn)?;
+        Ok(competition_id)
+    })
+}
+
+/// Pair the Nth stopwatch time with the Nth bib of a competition
+pub(crate) fn load_merge(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<Vec<MergeRow>> {
+    let times = stopwatch_times::table
+        .filter(stopwatch_times::competition_id.eq(competition_id))
+        .order_by((stopwatch_times::position, stopwatch_times::id))
+        .select((stopwatch_times::id, stopwatch_times::time))
+        .load::<(Id, PrimitiveDateTime)>(conn)?;
+    let bibs = finish_order_bibs::table
+        .filter(finish_order_bibs::competition_id.eq(competition_id))
+        .order_by((finish_order_bibs::position, finish_order_bibs::id))
+        .select((finish_order_bibs::id, finish_order_bibs::bib))
+        .load::<(Id, i32)>(conn)?;
+    let participants = bib_numbers::table
+        .inner_join(participants::table)
+        .filter(bib_numbers::competition_id.eq(competition_id))
+        .filter(bib_numbers::bib.eq_any(bibs.iter().map(|(_, bib)| *bib)))
+        .select((
+            bib_numbers::bib,
+            (
+                participants::id,
+                participants::first_name,
+                participants::last_name,
+            ),
+        ))
+        .load::<(i32, (Id, String, String))>(conn)?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let rows = (0..times.len().max(bibs.len()))
+        .map(|idx| {
+            let time = times.get(idx);
+            let bib = bibs.get(idx);
+            let participant = bib.and_then(|(_, bib)| participants.get(bib));
+            MergeRow {
+                position: idx + 1,
+                stopwatch_time_id: time.map(|(id, _)| *id),
+                time: time.map(|(_, time)| *time),
+                finish_bib_id: bib.map(|(id, _)| *id),
+                bib: bib.map(|(_, bib)| *bib),
+                participant_id: participant.map(|(id, _, _)| *id),
+                first_name: participant.map(|(_, first_name, _)| first_name.clone()),
+                last_name: participant.map(|(_, _, last_name)| last_name.clone()),
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Write the merged times as finish times of the matched participants
+///
+/// Rows without a time or without a known bib are skipped, as are participants
+/// that already have a finish time. Returns the number of recorded finish
+/// times
+pub(crate) fn apply_merge(conn: &mut SqliteConnection, competition_id: Id) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let rows = load_merge(conn, competition_id)?;
+        let mut recorded = 0;
+        for row in rows {
+            if let (Some(participant_id), Some(time)) = (row.participant_id, row.time) {
+                if super::time_records::insert_missing_finish_time(conn, participant_id, time)? {
+                    recorded += 1;
+                }
+            }
+        }
+        Ok(recorded)
+    })
+}
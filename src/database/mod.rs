@@ -1,6 +1,38 @@
+pub mod bib_numbers;
+pub mod chip_reads;
+pub mod clubs;
+pub mod correction_requests;
+pub mod duplicates;
+pub mod fees;
+pub mod finish_order;
+pub mod persons;
+pub mod registrations;
+pub mod result_versions;
 pub mod schema;
+pub mod secrets;
 pub mod shared_models;
+pub mod teams;
 pub mod test_data;
+pub mod time_records;
 
 /// The id type of the application
 pub type Id = i32;
+
+/// Normalize a free-text name for comparison
+///
+/// This ignores case, punctuation and repeated whitespace,
+/// so `"SV  Musterstadt e.V."` and `"sv musterstadt e v"` are the same
+pub(crate) fn normalize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().collect::<String>()
+            } else {
+                String::from(" ")
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,167 @@
+//! Identity of athletes across competitions
+//!
+//! Every registration creates a new `participants` row. Participants with
+//! the same normalized name and birth year are linked to the same person,
+//! so their results can be combined across competitions. An optional email
+//! address tells apart different persons with the same name and birth year.
+//! Mismatches are fixed by merging or splitting persons via the admin pages.
+use super::schema::{participants, persons};
+use super::{normalize_name, Id};
+use diesel::prelude::*;
+
+/// The key used to match participants to persons
+pub(crate) fn name_key(first_name: &str, last_name: &str) -> String {
+    normalize_name(&format!("{first_name} {last_name}"))
+}
+
+/// Email addresses are compared case insensitive
+fn normalize_email(email: Option<&str>) -> Option<String> {
+    email
+        .map(|e| e.trim().to_lowercase())
+        .filter(|e| !e.is_empty())
+}
+
+/// Create a new person from the data of a participant
+fn create_person(
+    conn: &mut SqliteConnection,
+    first_name: &str,
+    last_name: &str,
+    birth_year: i32,
+    email: Option<&str>,
+) -> QueryResult<Id> {
+    diesel::insert_into(persons::table)
+        .values((
+            persons::first_name.eq(first_name),
+            persons::last_name.eq(last_name),
+            persons::birth_year.eq(birth_year),
+            persons::name_key.eq(name_key(first_name, last_name)),
+            persons::email.eq(email),
+        ))
+        .returning(persons::id)
+        .get_result(conn)
+}
+
+/// Find the person for the given name and birth year or create a new one
+///
+/// With an email address a person with the same address is preferred,
+/// persons with a different address never match. Persons without an
+/// address take over the address of the participant
+fn find_or_create_person(
+    conn: &mut SqliteConnection,
+    first_name: &str,
+    last_name: &str,
+    birth_year: i32,
+    email: Option<&str>,
+) -> QueryResult<Id> {
+    let email = normalize_email(email);
+    let candidates = persons::table
+        .filter(persons::name_key.eq(name_key(first_name, last_name)))
+        .filter(persons::birth_year.eq(birth_year))
+        .order_by(persons::id)
+        .select((persons::id, persons::email))
+        .load::<(Id, Option<String>)>(conn)?;
+    let Some(email) = email else {
+        return match candidates.first() {
+            Some((person_id, _)) => Ok(*person_id),
+            None => create_person(conn, first_name, last_name, birth_year, None),
+        };
+    };
+    if let Some((person_id, _)) = candidates
+        .iter()
+        .find(|(_, e)| e.as_deref() == Some(email.as_str()))
+    {
+        return Ok(*person_id);
+    }
+    if let Some((person_id, _)) = candidates.iter().find(|(_, e)| e.is_none()) {
+        diesel::update(persons::table.find(person_id))
+            .set(persons::email.eq(&email))
+            .execute(conn)?;
+        return Ok(*person_id);
+    }
+    create_person(conn, first_name, last_name, birth_year, Some(&email))
+}
+
+/// Link a single participant to a matching person
+///
+/// This is called for each new registration
+pub(crate) fn link_participant(conn: &mut SqliteConnection, participant_id: Id) -> QueryResult<Id> {
+    let (first_name, last_name, birth_year, email) = participants::table
+        .find(participant_id)
+        .select((
+            participants::first_name,
+            participants::last_name,
+            participants::birth_year,
+            participants::email,
+        ))
+        .first::<(String, String, i32, Option<String>)>(conn)?;
+    let person_id =
+        find_or_create_person(conn, &first_name, &last_name, birth_year, email.as_deref())?;
+    diesel::update(participants::table.find(participant_id))
+        .set(participants::person_id.eq(person_id))
+        .execute(conn)?;
+    Ok(person_id)
+}
+
+/// Link all participants without a person to a matching person
+///
+/// Returns the number of linked participants
+pub(crate) fn link_participants(conn: &mut SqliteConnection) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let unlinked = participants::table
+            .filter(participants::person_id.is_null())
+            .select(participants::id)
+            .load::<Id>(conn)?;
+        for participant_id in &unlinked {
+            link_participant(conn, *participant_id)?;
+        }
+        Ok(unlinked.len())
+    })
+}
+
+/// Merge the person `from` into the person `into`
+///
+/// All participants of `from` are moved and `from` is deleted
+pub(crate) fn merge_persons(conn: &mut SqliteConnection, into: Id, from: Id) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        // make sure both persons exist before changing anything
+        persons::table
+            .find(into)
+            .select(persons::id)
+            .first::<Id>(conn)?;
+        persons::table
+            .find(from)
+            .select(persons::id)
+            .first::<Id>(conn)?;
+        diesel::update(participants::table.filter(participants::person_id.eq(from)))
+            .set(participants::person_id.eq(into))
+            .execute(conn)?;
+        diesel::delete(persons::table.find(from)).execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Split a participant from its person into a new person
+///
+/// Returns the id of the new person
+pub(crate) fn split_participant(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<Id> {
+    conn.transaction(|conn| {
+        let (first_name, last_name, birth_year, email) = participants::table
+            .find(participant_id)
+            .select((
+                participants::first_name,
+                participants::last_name,
+                participants::birth_year,
+                participants::email,
+            ))
+            .first::<(String, String, i32, Option<String>)>(conn)?;
+        let email = normalize_email(email.as_deref());
+        let person_id = create_person(conn, &first_name, &last_name, birth_year, email.as_deref())?;
+        diesel::update(participants::table.find(participant_id))
+            .set(participants::person_id.eq(person_id))
+            .execute(conn)?;
+        Ok(person_id)
+    })
+}
@@ -0,0 +1,291 @@
+//! Registration windows, capacity limits and the waitlist
+//!
+//! Races and starts can limit the number of confirmed participants. New
+//! registrations for a full race or start are put on the waitlist, which is
+//! ordered by the time of the registration. Whenever a spot becomes free the
+//! waitlist is promoted in order. The members of a relay team are only
+//! waitlisted and promoted together.
+use super::schema::{categories, participants, races, starts, team_members};
+use super::shared_models::Competition;
+use super::Id;
+use diesel::prelude::*;
+use serde::Serialize;
+use time::PrimitiveDateTime;
+
+/// Whether a competition accepts registrations
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RegistrationWindow {
+    NotYetOpen,
+    Open,
+    Closed,
+}
+
+impl RegistrationWindow {
+    pub(crate) fn of(competition: &Competition, now: PrimitiveDateTime) -> Self {
+        if competition
+            .registration_opens
+            .is_some_and(|opens| now < opens)
+        {
+            Self::NotYetOpen
+        } else if competition
+            .registration_closes
+            .is_some_and(|closes| now >= closes)
+        {
+            Self::Closed
+        } else {
+            Self::Open
+        }
+    }
+}
+
+pub(crate) fn now() -> PrimitiveDateTime {
+    let now = time::OffsetDateTime::now_utc();
+    PrimitiveDateTime::new(now.date(), now.time())
+}
+
+/// Number of confirmed participants of a race
+fn confirmed_in_race(conn: &mut SqliteConnection, race_id: Id) -> QueryResult<i64> {
+    participants::table
+        .inner_join(categories::table.inner_join(starts::table))
+        .filter(starts::race_id.eq(race_id))
+        .filter(participants::waitlisted_at.is_null())
+        .count()
+        .get_result(conn)
+}
+
+/// Number of confirmed participants of a start
+fn confirmed_in_start(conn: &mut SqliteConnection, start_id: Id) -> QueryResult<i64> {
+    participants::table
+        .inner_join(categories::table)
+        .filter(categories::start_id.eq(start_id))
+        .filter(participants::waitlisted_at.is_null())
+        .count()
+        .get_result(conn)
+}
+
+/// Free spots of a race, `None` if the race is not limited
+pub(crate) fn free_spots_in_race(
+    conn: &mut SqliteConnection,
+    race_id: Id,
+) -> QueryResult<Option<i64>> {
+    let capacity = races::table
+        .find(race_id)
+        .select(races::capacity)
+        .first::<Option<i32>>(conn)?;
+    let Some(capacity) = capacity else {
+        return Ok(None);
+    };
+    let confirmed = confirmed_in_race(conn, race_id)?;
+    Ok(Some((i64::from(capacity) - confirmed).max(0)))
+}
+
+/// Free spots for the participants of a start, this respects the limits of
+/// the start and of its race
+fn free_spots_in_start(conn: &mut SqliteConnection, start_id: Id) -> QueryResult<Option<i64>> {
+    let (race_id, capacity) = starts::table
+        .find(start_id)
+        .select((starts::race_id, starts::capacity))
+        .first::<(Id, Option<i32>)>(conn)?;
+    let in_race = free_spots_in_race(conn, race_id)?;
+    let in_start = match capacity {
+        Some(capacity) => Some((i64::from(capacity) - confirmed_in_start(conn, start_id)?).max(0)),
+        None => None,
+    };
+    Ok(match (in_race, in_start) {
+        (Some(race), Some(start)) => Some(race.min(start)),
+        (race, start) => race.or(start),
+    })
+}
+
+/// The start of a participant
+pub(crate) fn start_of_participant(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<Id> {
+    participants::table
+        .inner_join(categories::table)
+        .filter(participants::id.eq(participant_id))
+        .select(categories::start_id)
+        .first(conn)
+}
+
+/// The race of a participant
+pub(crate) fn race_of_participant(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<Id> {
+    participants::table
+        .inner_join(categories::table.inner_join(starts::table))
+        .filter(participants::id.eq(participant_id))
+        .select(starts::race_id)
+        .first(conn)
+}
+
+/// Put a newly registered participant on the waitlist if the race or the
+/// start is already full
+///
+/// Returns whether the participant was put on the waitlist
+pub(crate) fn waitlist_if_full(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<bool> {
+    conn.transaction(|conn| {
+        let start_id = start_of_participant(conn, participant_id)?;
+        // the new participant is already counted as confirmed
+        let full = over_capacity(conn, start_id)?;
+        if full {
+            diesel::update(participants::table.find(participant_id))
+                .set(participants::waitlisted_at.eq(now()))
+                .execute(conn)?;
+        }
+        Ok(full)
+    })
+}
+
+/// Put a newly registered relay team on the waitlist if the race or the
+/// start of any member is already full
+///
+/// Returns whether the team was put on the waitlist
+pub(crate) fn waitlist_team_if_full(
+    conn: &mut SqliteConnection,
+    member_ids: &[Id],
+) -> QueryResult<bool> {
+    conn.transaction(|conn| {
+        let mut full = false;
+        for member_id in member_ids {
+            full |= waitlist_if_full(conn, *member_id)?;
+        }
+        if full {
+            diesel::update(participants::table.filter(participants::id.eq_any(member_ids)))
+                .set(participants::waitlisted_at.eq(now()))
+                .execute(conn)?;
+        }
+        Ok(full)
+    })
+}
+
+/// Update the waitlist after a participant changed their registration
+///
+/// If the participant moved to another start, a confirmed participant is
+/// put on the waitlist if the new start or race is full, a waitlisted
+/// participant may be promoted in the new race. The spot in the previous race
+/// is given to its waitlist.
+pub(crate) fn start_changed(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    previous_start_id: Id,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        let start_id = start_of_participant(conn, participant_id)?;
+        if start_id == previous_start_id {
+            return Ok(());
+        }
+        let race_of_start = |conn: &mut SqliteConnection, start_id: Id| {
+            starts::table
+                .find(start_id)
+                .select(starts::race_id)
+                .first::<Id>(conn)
+        };
+        let waitlisted = participants::table
+            .find(participant_id)
+            .select(participants::waitlisted_at.is_not_null())
+            .first::<bool>(conn)?;
+        if waitlisted {
+            let race_id = race_of_start(conn, start_id)?;
+            promote_waitlist(conn, race_id)?;
+        } else {
+            waitlist_if_full(conn, participant_id)?;
+        }
+        let previous_race_id = race_of_start(conn, previous_start_id)?;
+        promote_waitlist(conn, previous_race_id)?;
+        Ok(())
+    })
+}
+
+/// Whether the confirmed participants exceed the capacity of the start or
+/// of its race
+fn over_capacity(conn: &mut SqliteConnection, start_id: Id) -> QueryResult<bool> {
+    let (race_id, start_capacity, race_capacity) = starts::table
+        .inner_join(races::table)
+        .filter(starts::id.eq(start_id))
+        .select((races::id, starts::capacity, races::capacity))
+        .first::<(Id, Option<i32>, Option<i32>)>(conn)?;
+    if let Some(capacity) = start_capacity {
+        if confirmed_in_start(conn, start_id)? > i64::from(capacity) {
+            return Ok(true);
+        }
+    }
+    if let Some(capacity) = race_capacity {
+        if confirmed_in_race(conn, race_id)? > i64::from(capacity) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether the race and the starts have enough free spots for the given
+/// participants and their starts
+fn has_room(conn: &mut SqliteConnection, race_id: Id, entries: &[(Id, Id)]) -> QueryResult<bool> {
+    let needed = i64::try_from(entries.len()).unwrap_or(i64::MAX);
+    if free_spots_in_race(conn, race_id)?.is_some_and(|free| free < needed) {
+        return Ok(false);
+    }
+    for (_, start_id) in entries {
+        let needed = entries.iter().filter(|(_, s)| s == start_id).count();
+        let needed = i64::try_from(needed).unwrap_or(i64::MAX);
+        if free_spots_in_start(conn, *start_id)?.is_some_and(|free| free < needed) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Confirm waitlisted participants of a race in order as long as there are
+/// free spots
+///
+/// A participant whose start is full does not block participants of other
+/// starts behind them. Relay teams are only confirmed if there is room for
+/// all waitlisted members. Returns the ids of the confirmed participants
+pub(crate) fn promote_waitlist(conn: &mut SqliteConnection, race_id: Id) -> QueryResult<Vec<Id>> {
+    conn.transaction(|conn| {
+        let waitlist = participants::table
+            .inner_join(categories::table.inner_join(starts::table))
+            .left_join(team_members::table)
+            .filter(starts::race_id.eq(race_id))
+            .filter(participants::waitlisted_at.is_not_null())
+            .order_by((participants::waitlisted_at, participants::id))
+            .select((
+                participants::id,
+                starts::id,
+                team_members::team_id.nullable(),
+            ))
+            .load::<(Id, Id, Option<Id>)>(conn)?;
+        let mut promoted = Vec::new();
+        for (participant_id, start_id, team_id) in &waitlist {
+            if promoted.contains(participant_id) {
+                continue;
+            }
+            if free_spots_in_race(conn, race_id)? == Some(0) {
+                break;
+            }
+            let entries = match team_id {
+                Some(team_id) => waitlist
+                    .iter()
+                    .filter(|(.., t)| *t == Some(*team_id))
+                    .map(|(p, s, _)| (*p, *s))
+                    .collect::<Vec<_>>(),
+                None => vec![(*participant_id, *start_id)],
+            };
+            if !has_room(conn, race_id, &entries)? {
+                continue;
+            }
+            let ids = entries.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+            diesel::update(participants::table.filter(participants::id.eq_any(&ids)))
+                .set(participants::waitlisted_at.eq(None::<PrimitiveDateTime>))
+                .execute(conn)?;
+            promoted.extend(ids);
+        }
+        Ok(promoted)
+    })
+}
@@ -0,0 +1,101 @@
+//! Published versions of the results of a race
+//!
+//! Publishing stores the computed ranking of a race as JSON. Versions are
+//! never changed afterwards, later corrections need a new version.
+use super::schema::{races, result_versions};
+use super::shared_models::ResultVersion;
+use super::Id;
+use diesel::dsl;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use time::PrimitiveDateTime;
+
+/// A version that is about to be published
+pub(crate) struct NewResultVersion<'a> {
+    pub(crate) race_id: Id,
+    /// the ranked results as JSON
+    pub(crate) results: &'a str,
+    pub(crate) note: &'a str,
+    /// the changes compared to the previous version as JSON
+    pub(crate) changes: &'a str,
+    pub(crate) published_by: &'a str,
+}
+
+/// Store a new version of the results of a race
+///
+/// Returns the number of the new version, the first version is 1
+pub(crate) fn publish_version(
+    conn: &mut SqliteConnection,
+    new_version: &NewResultVersion<'_>,
+) -> QueryResult<i32> {
+    conn.transaction(|conn| {
+        let version = latest_version(conn, new_version.race_id)?.unwrap_or_default() + 1;
+        let now = time::OffsetDateTime::now_utc();
+        diesel::insert_into(result_versions::table)
+            .values((
+                result_versions::race_id.eq(new_version.race_id),
+                result_versions::version.eq(version),
+                result_versions::results.eq(new_version.results),
+                result_versions::note.eq(new_version.note),
+                result_versions::changes.eq(new_version.changes),
+                result_versions::published_by.eq(new_version.published_by),
+                result_versions::published_at.eq(PrimitiveDateTime::new(now.date(), now.time())),
+            ))
+            .execute(conn)?;
+        Ok(version)
+    })
+}
+
+/// The number of the latest version of a race, `None` if the results of the
+/// race were never published
+pub(crate) fn latest_version(conn: &mut SqliteConnection, race_id: Id) -> QueryResult<Option<i32>> {
+    result_versions::table
+        .filter(result_versions::race_id.eq(race_id))
+        .select(dsl::max(result_versions::version))
+        .get_result(conn)
+}
+
+/// All versions of a race, the latest first
+pub(crate) fn load_versions(
+    conn: &mut SqliteConnection,
+    race_id: Id,
+) -> QueryResult<Vec<ResultVersion>> {
+    result_versions::table
+        .filter(result_versions::race_id.eq(race_id))
+        .order_by(result_versions::version.desc())
+        .select(ResultVersion::as_select())
+        .load(conn)
+}
+
+/// A single version of a race
+pub(crate) fn load_version(
+    conn: &mut SqliteConnection,
+    race_id: Id,
+    version: i32,
+) -> QueryResult<Option<ResultVersion>> {
+    result_versions::table
+        .filter(result_versions::race_id.eq(race_id))
+        .filter(result_versions::version.eq(version))
+        .select(ResultVersion::as_select())
+        .first(conn)
+        .optional()
+}
+
+/// The latest version of all published races of a competition, keyed by
+/// race id
+pub(crate) fn latest_versions(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<HashMap<Id, ResultVersion>> {
+    let versions = result_versions::table
+        .inner_join(races::table)
+        .filter(races::competition_id.eq(competition_id))
+        .order_by(result_versions::version)
+        .select(ResultVersion::as_select())
+        .load::<ResultVersion>(conn)?;
+    // later versions replace earlier ones
+    Ok(versions
+        .into_iter()
+        .map(|version| (version.race_id, version))
+        .collect())
+}
@@ -1,5 +1,21 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    award_settings (competition_id) {
+        competition_id -> Integer,
+        places -> Integer,
+        min_finishers -> Integer,
+    }
+}
+
+diesel::table! {
+    bib_numbers (participant_id) {
+        participant_id -> Integer,
+        competition_id -> Integer,
+        bib -> Integer,
+    }
+}
+
 diesel::table! {
     categories (id) {
         id -> Integer,
@@ -8,6 +24,47 @@ diesel::table! {
         to_age -> Integer,
         male -> Bool,
         start_id -> Integer,
+        ceremony_order -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    chip_reads (id) {
+        id -> Integer,
+        competition_id -> Integer,
+        chip -> Text,
+        time -> Timestamp,
+        reader -> Text,
+    }
+}
+
+diesel::table! {
+    chips (competition_id, chip) {
+        competition_id -> Integer,
+        chip -> Text,
+        participant_id -> Integer,
+    }
+}
+
+diesel::table! {
+    club_aliases (alias) {
+        alias -> Text,
+        club_id -> Integer,
+    }
+}
+
+diesel::table! {
+    club_scorings (race_id) {
+        race_id -> Integer,
+        mode -> Text,
+        counting -> Integer,
+    }
+}
+
+diesel::table! {
+    clubs (id) {
+        id -> Integer,
+        name -> Text,
     }
 }
 
@@ -19,6 +76,34 @@ diesel::table! {
         date -> Date,
         location -> Text,
         announcement -> Text,
+        registration_opens -> Nullable<Timestamp>,
+        registration_closes -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    correction_requests (id) {
+        id -> Integer,
+        participant_id -> Integer,
+        kind -> Text,
+        requested_time_ms -> Nullable<BigInt>,
+        requested_category_id -> Nullable<Integer>,
+        reason -> Text,
+        contact -> Text,
+        created_at -> Timestamp,
+        status -> Text,
+        admin_comment -> Nullable<Text>,
+        handled_by -> Nullable<Text>,
+        handled_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    finish_order_bibs (id) {
+        id -> Integer,
+        competition_id -> Integer,
+        position -> Integer,
+        bib -> Integer,
     }
 }
 
@@ -31,6 +116,12 @@ diesel::table! {
         category_id -> Integer,
         consent_agb -> Bool,
         birth_year -> Integer,
+        status -> Text,
+        status_reason -> Nullable<Text>,
+        person_id -> Nullable<Integer>,
+        email -> Nullable<Text>,
+        waitlisted_at -> Nullable<Timestamp>,
+        registered_at -> Nullable<Timestamp>,
     }
 }
 
@@ -41,11 +132,88 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    payments (participant_id) {
+        participant_id -> Integer,
+        amount_due -> Integer,
+        status -> Text,
+        method -> Nullable<Text>,
+        reference -> Nullable<Text>,
+        paid_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    persons (id) {
+        id -> Integer,
+        first_name -> Text,
+        last_name -> Text,
+        birth_year -> Integer,
+        name_key -> Text,
+        email -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    race_fees (race_id) {
+        race_id -> Integer,
+        base_fee -> Integer,
+        late_surcharge -> Integer,
+        late_from -> Nullable<Timestamp>,
+        youth_discount -> Integer,
+        youth_max_age -> Nullable<Integer>,
+        club_discount -> Integer,
+    }
+}
+
 diesel::table! {
     races (id) {
         id -> Integer,
         name -> Text,
         competition_id -> Integer,
+        ranking_mode -> Text,
+        team_size -> Nullable<Integer>,
+        distance_meters -> Nullable<Integer>,
+        elevation_gain_meters -> Nullable<Integer>,
+        surface -> Nullable<Text>,
+        course_description -> Nullable<Text>,
+        capacity -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    result_versions (id) {
+        id -> Integer,
+        race_id -> Integer,
+        version -> Integer,
+        results -> Text,
+        note -> Text,
+        changes -> Text,
+        published_by -> Text,
+        published_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    secrets (name) {
+        name -> Text,
+        value -> Binary,
+    }
+}
+
+diesel::table! {
+    series (id) {
+        id -> Integer,
+        name -> Text,
+        points_table -> Text,
+        counting_results -> Integer,
+    }
+}
+
+diesel::table! {
+    series_competitions (series_id, competition_id) {
+        series_id -> Integer,
+        competition_id -> Integer,
     }
 }
 
@@ -63,6 +231,22 @@ diesel::table! {
         short_name -> Text,
         name -> Text,
         race_id -> Integer,
+        ceremony_order -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    split_times (participant_id, timing_point_id) {
+        participant_id -> Integer,
+        timing_point_id -> Integer,
+        time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    start_reads (participant_id) {
+        participant_id -> Integer,
+        time -> Timestamp,
     }
 }
 
@@ -72,6 +256,75 @@ diesel::table! {
         name -> Text,
         time -> Timestamp,
         race_id -> Integer,
+        first_bib -> Nullable<Integer>,
+        last_bib -> Nullable<Integer>,
+        gun_time -> Nullable<Timestamp>,
+        capacity -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    stopwatch_times (id) {
+        id -> Integer,
+        competition_id -> Integer,
+        position -> Integer,
+        time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    team_categories (id) {
+        id -> Integer,
+        label -> Text,
+        composition -> Text,
+        from_combined_age -> Integer,
+        to_combined_age -> Integer,
+        race_id -> Integer,
+    }
+}
+
+diesel::table! {
+    team_members (team_id, leg) {
+        team_id -> Integer,
+        participant_id -> Integer,
+        leg -> Integer,
+    }
+}
+
+diesel::table! {
+    teams (id) {
+        id -> Integer,
+        name -> Text,
+        race_id -> Integer,
+        team_category_id -> Integer,
+    }
+}
+
+diesel::table! {
+    time_adjustments (id) {
+        id -> Integer,
+        participant_id -> Integer,
+        delta_ms -> BigInt,
+        reason -> Text,
+        created_by -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    time_records (id) {
+        id -> Integer,
+        participant_id -> Integer,
+        finish_time -> Timestamp,
+    }
+}
+
+diesel::table! {
+    timing_points (id) {
+        id -> Integer,
+        race_id -> Integer,
+        name -> Text,
+        position -> Integer,
     }
 }
 
@@ -83,22 +336,76 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(award_settings -> competitions (competition_id));
+diesel::joinable!(bib_numbers -> competitions (competition_id));
+diesel::joinable!(bib_numbers -> participants (participant_id));
 diesel::joinable!(categories -> starts (start_id));
+diesel::joinable!(chip_reads -> competitions (competition_id));
+diesel::joinable!(chips -> competitions (competition_id));
+diesel::joinable!(chips -> participants (participant_id));
+diesel::joinable!(club_aliases -> clubs (club_id));
+diesel::joinable!(club_scorings -> races (race_id));
+diesel::joinable!(correction_requests -> categories (requested_category_id));
+diesel::joinable!(correction_requests -> participants (participant_id));
+diesel::joinable!(finish_order_bibs -> competitions (competition_id));
 diesel::joinable!(participants -> categories (category_id));
+diesel::joinable!(participants -> persons (person_id));
 diesel::joinable!(participants_in_special_category -> participants (participant_id));
 diesel::joinable!(participants_in_special_category -> special_categories (special_category_id));
+diesel::joinable!(payments -> participants (participant_id));
+diesel::joinable!(race_fees -> races (race_id));
 diesel::joinable!(races -> competitions (competition_id));
+diesel::joinable!(result_versions -> races (race_id));
+diesel::joinable!(series_competitions -> competitions (competition_id));
+diesel::joinable!(series_competitions -> series (series_id));
 diesel::joinable!(special_categories -> races (race_id));
+diesel::joinable!(split_times -> participants (participant_id));
+diesel::joinable!(split_times -> timing_points (timing_point_id));
+diesel::joinable!(start_reads -> participants (participant_id));
 diesel::joinable!(starts -> races (race_id));
+diesel::joinable!(stopwatch_times -> competitions (competition_id));
+diesel::joinable!(team_categories -> races (race_id));
+diesel::joinable!(team_members -> participants (participant_id));
+diesel::joinable!(team_members -> teams (team_id));
+diesel::joinable!(teams -> races (race_id));
+diesel::joinable!(teams -> team_categories (team_category_id));
+diesel::joinable!(time_adjustments -> participants (participant_id));
+diesel::joinable!(time_records -> participants (participant_id));
+diesel::joinable!(timing_points -> races (race_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    award_settings,
+    bib_numbers,
     categories,
+    chip_reads,
+    chips,
+    club_aliases,
+    club_scorings,
+    clubs,
     competitions,
+    correction_requests,
+    finish_order_bibs,
     participants,
     participants_in_special_category,
+    payments,
+    persons,
+    race_fees,
     races,
+    result_versions,
+    secrets,
+    series,
+    series_competitions,
     session_records,
     special_categories,
+    split_times,
+    start_reads,
     starts,
+    stopwatch_times,
+    team_categories,
+    team_members,
+    teams,
+    time_adjustments,
+    time_records,
+    timing_points,
     users,
 );
@@ -0,0 +1,26 @@
+//! Server side secrets
+//!
+//! A secret is generated randomly the first time it is needed and stays the
+//! same afterwards, so signed links remain valid across restarts
+use super::schema::secrets;
+use diesel::prelude::*;
+use rand::RngCore;
+
+/// Load the secret with the given name, creates it if it does not exist yet
+pub(crate) fn secret(conn: &mut SqliteConnection, name: &str) -> QueryResult<Vec<u8>> {
+    if let Some(value) = secrets::table
+        .find(name)
+        .select(secrets::value)
+        .first(conn)
+        .optional()?
+    {
+        return Ok(value);
+    }
+    let mut value = vec![0; 32];
+    rand::thread_rng().fill_bytes(&mut value);
+    // another request might have created the secret in the meantime
+    diesel::insert_or_ignore_into(secrets::table)
+        .values((secrets::name.eq(name), secrets::value.eq(value)))
+        .execute(conn)?;
+    secrets::table.find(name).select(secrets::value).first(conn)
+}
@@ -1,9 +1,60 @@
 use super::Id;
 use crate::database::schema::{
-    competitions, participants, participants_in_special_category, races, special_categories,
+    club_scorings, competitions, correction_requests, participants,
+    participants_in_special_category, race_fees, races, result_versions, series,
+    special_categories, team_categories, time_adjustments, timing_points,
 };
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
 use diesel::prelude::*;
-use serde::Serialize;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteValue};
+use serde::{Deserialize, Serialize};
+
+/// Store a field-less enum as text in the database
+///
+/// Takes the enum and a table of its variants with their text
+/// representation, which needs to match the serde representation. This
+/// implements `ALL`, `as_str`, `FromStr`, `ToSql` and `FromSql` for the enum
+macro_rules! text_enum {
+    ($name:ident, $label:literal, { $($variant:ident => $text:literal,)+ }) => {
+        impl $name {
+            pub const ALL: [Self; [$($text),+].len()] = [$(Self::$variant),+];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $text,)+
+                }
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::ALL
+                    .into_iter()
+                    .find(|value| value.as_str() == s)
+                    .ok_or_else(|| format!(concat!("Unknown ", $label, ": {}"), s))
+            }
+        }
+
+        impl ToSql<Text, Sqlite> for $name {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+                out.set_value(self.as_str());
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Sqlite> for $name {
+            fn from_sql(bytes: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+                let value = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
+                Ok(value.parse()?)
+            }
+        }
+    };
+}
 
 #[derive(Queryable, Selectable, Serialize, Debug, Identifiable)]
 #[diesel(table_name = competitions)]
@@ -15,6 +66,10 @@ pub struct Competition {
     date: time::Date,
     location: String,
     announcement: String,
+    /// registration is possible from this time on, `None` if it is always open
+    pub registration_opens: Option<time::PrimitiveDateTime>,
+    /// registration is possible until this time, `None` if it is always open
+    pub registration_closes: Option<time::PrimitiveDateTime>,
 }
 
 #[derive(Queryable, Selectable, Serialize, Debug, Identifiable)]
@@ -32,6 +87,72 @@ pub struct Race {
     pub id: Id,
     pub name: String,
     competition_id: Id,
+    /// which time is used to rank the participants of this race
+    pub ranking_mode: RankingMode,
+    /// number of members per team for relay races
+    pub team_size: Option<i32>,
+    /// length of the course in meters
+    pub distance_meters: Option<i32>,
+    /// total ascent of the course in meters
+    pub elevation_gain_meters: Option<i32>,
+    /// e.g. road, trail or track
+    pub surface: Option<String>,
+    pub course_description: Option<String>,
+}
+
+/// An intermediate timing point of a race, e.g. the 5 km mark of a 10 km race
+#[derive(Queryable, Selectable, Associations, Serialize, Debug, Identifiable, Clone)]
+#[diesel(table_name = timing_points)]
+#[diesel(belongs_to(Race))]
+pub struct TimingPoint {
+    pub id: Id,
+    pub name: String,
+    /// order of the timing point within the race
+    pub position: i32,
+    race_id: Id,
+}
+
+/// A category for relay teams of a race
+///
+/// Teams are matched by the genders of their members and by
+/// the combined age of all members
+#[derive(Queryable, Selectable, Associations, Serialize, Debug, Identifiable, Clone)]
+#[diesel(table_name = team_categories)]
+#[diesel(belongs_to(Race))]
+pub struct TeamCategory {
+    pub id: Id,
+    pub label: String,
+    pub composition: TeamComposition,
+    pub from_combined_age: i32,
+    pub to_combined_age: i32,
+    race_id: Id,
+}
+
+/// A race with its timing points ordered by their position
+#[derive(Serialize, Debug)]
+pub struct RaceWithTimingPoints {
+    #[serde(flatten)]
+    pub race: Race,
+    pub timing_points: Vec<TimingPoint>,
+}
+
+impl RaceWithTimingPoints {
+    /// Load the timing points for the given races
+    pub fn load(conn: &mut SqliteConnection, races: Vec<Race>) -> QueryResult<Vec<Self>> {
+        let timing_points = TimingPoint::belonging_to(&races)
+            .order_by(timing_points::position)
+            .select(TimingPoint::as_select())
+            .load(conn)?;
+        Ok(timing_points
+            .grouped_by(&races)
+            .into_iter()
+            .zip(races)
+            .map(|(timing_points, race)| Self {
+                race,
+                timing_points,
+            })
+            .collect())
+    }
 }
 
 #[derive(Queryable, Selectable, Associations, Serialize, Debug, Identifiable, Clone)]
@@ -56,6 +177,289 @@ pub struct SpecialCategoryPerParticipant {
     participant_id: Id,
 }
 
+/// The status of a participant in a competition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ParticipantStatus {
+    Registered,
+    CheckedIn,
+    Started,
+    Finished,
+    /// did not finish
+    Dnf,
+    /// did not start
+    Dns,
+    /// disqualified
+    Dsq,
+}
+
+text_enum!(ParticipantStatus, "participant status", {
+    Registered => "registered",
+    CheckedIn => "checked_in",
+    Started => "started",
+    Finished => "finished",
+    Dnf => "dnf",
+    Dns => "dns",
+    Dsq => "dsq",
+});
+
+impl ParticipantStatus {
+    /// Participants with these states are excluded from the ranking
+    pub const NON_FINISHERS: [Self; 3] = [Self::Dnf, Self::Dns, Self::Dsq];
+}
+
+/// The time used to rank participants of a race
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, AsExpression, FromSqlRow, Serialize, Deserialize,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingMode {
+    /// time since the start signal of the start
+    Gun,
+    /// time since the participant crossed the start line
+    #[default]
+    Net,
+}
+
+text_enum!(RankingMode, "ranking mode", {
+    Gun => "gun",
+    Net => "net",
+});
+
+/// Which genders a team needs to consist of to fit into a team category
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamComposition {
+    /// only male members
+    Male,
+    /// only female members
+    Female,
+    /// at least one male and one female member
+    Mixed,
+    /// no restriction
+    Any,
+}
+
+text_enum!(TeamComposition, "team composition", {
+    Male => "male",
+    Female => "female",
+    Mixed => "mixed",
+    Any => "any",
+});
+
+impl TeamComposition {
+    /// The composition of a team with the given genders of its members
+    pub fn of_team(male: impl IntoIterator<Item = bool>) -> Self {
+        let (mut has_male, mut has_female) = (false, false);
+        for male in male {
+            has_male |= male;
+            has_female |= !male;
+        }
+        match (has_male, has_female) {
+            (true, false) => Self::Male,
+            (false, true) => Self::Female,
+            _ => Self::Mixed,
+        }
+    }
+}
+
+/// How the clubs of a race are scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ClubScoringMode {
+    /// sum of the times of the fastest finishers, lowest sum wins
+    TimeSum,
+    /// sum of the overall places of the best finishers, lowest sum wins
+    PlaceSum,
+}
+
+text_enum!(ClubScoringMode, "club scoring mode", {
+    TimeSum => "time_sum",
+    PlaceSum => "place_sum",
+});
+
+/// What a correction request is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionKind {
+    /// the ranking time is wrong
+    Time,
+    /// the participant is in the wrong category
+    Category,
+    /// anything else, e.g. a misspelled name
+    Other,
+}
+
+text_enum!(CorrectionKind, "correction kind", {
+    Time => "time",
+    Category => "category",
+    Other => "other",
+});
+
+/// Processing state of a correction request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionStatus {
+    /// not handled yet
+    Open,
+    /// the requested change was applied
+    Accepted,
+    /// the request was rejected with a comment
+    Rejected,
+}
+
+text_enum!(CorrectionStatus, "correction status", {
+    Open => "open",
+    Accepted => "accepted",
+    Rejected => "rejected",
+});
+
+/// Payment state of the entry fee of a participant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    /// the entry fee was not paid yet
+    Open,
+    Paid,
+    /// the entry fee was paid back, e.g. after a cancelled competition
+    Refunded,
+}
+
+text_enum!(PaymentStatus, "payment status", {
+    Open => "open",
+    Paid => "paid",
+    Refunded => "refunded",
+});
+
+/// A correction of the results requested by a participant
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = correction_requests)]
+pub struct CorrectionRequest {
+    pub id: Id,
+    pub participant_id: Id,
+    pub kind: CorrectionKind,
+    /// the ranking time the participant expects in milliseconds
+    pub requested_time_ms: Option<i64>,
+    /// the category the participant expects
+    pub requested_category_id: Option<Id>,
+    pub reason: String,
+    /// email address or phone number of the participant
+    pub contact: String,
+    pub created_at: time::PrimitiveDateTime,
+    pub status: CorrectionStatus,
+    /// explanation of the admin, required for rejected requests
+    pub admin_comment: Option<String>,
+    /// name of the admin user that handled the request
+    pub handled_by: Option<String>,
+    pub handled_at: Option<time::PrimitiveDateTime>,
+}
+
+/// The club scoring configuration of a race
+#[derive(Queryable, Selectable, Serialize, Debug, Clone, Copy)]
+#[diesel(table_name = club_scorings)]
+pub struct ClubScoring {
+    pub race_id: Id,
+    pub mode: ClubScoringMode,
+    /// number of finishers per club that count for the score
+    pub counting: i32,
+}
+
+/// The entry fees of a race, all amounts are in cents
+#[derive(Queryable, Selectable, Serialize, Debug, Clone, Copy)]
+#[diesel(table_name = race_fees)]
+pub struct FeeSchedule {
+    pub race_id: Id,
+    pub base_fee: i32,
+    /// added for registrations from `late_from` on
+    pub late_surcharge: i32,
+    pub late_from: Option<time::PrimitiveDateTime>,
+    /// subtracted for participants up to `youth_max_age`
+    pub youth_discount: i32,
+    pub youth_max_age: Option<i32>,
+    /// subtracted for members of a club from the club list
+    pub club_discount: i32,
+}
+
+/// A series of competitions with a combined ranking, e.g. a running cup
+#[derive(Queryable, Selectable, Serialize, Debug, Identifiable)]
+#[diesel(table_name = series)]
+pub struct Series {
+    pub id: Id,
+    pub name: String,
+    /// comma separated points for the category places
+    pub points_table: String,
+    /// number of competitions that count for the standings
+    pub counting_results: i32,
+}
+
+impl Series {
+    /// Parse a comma separated points table like `100,95,90`
+    pub fn parse_points_table(points_table: &str) -> Result<Vec<i32>, String> {
+        points_table
+            .split(',')
+            .map(|points| {
+                points
+                    .trim()
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|points| *points >= 0)
+                    .ok_or_else(|| format!("Invalid points in points table: {points}"))
+            })
+            .collect()
+    }
+
+    /// The points for each place within a category, starting with the winner
+    ///
+    /// Places beyond the points table get no points
+    pub fn points(&self) -> Vec<i32> {
+        Self::parse_points_table(&self.points_table).unwrap_or_default()
+    }
+}
+
+/// A correction of the recorded finish time of a participant
+#[derive(Queryable, Selectable, Serialize, Debug)]
+#[diesel(table_name = time_adjustments)]
+pub struct TimeAdjustment {
+    pub id: Id,
+    pub participant_id: Id,
+    /// added to the recorded finish time in milliseconds, negative values
+    /// make the time shorter
+    pub delta_ms: i64,
+    /// why the time was corrected
+    pub reason: String,
+    /// name of the admin user that stored the correction
+    pub created_by: String,
+    pub created_at: time::PrimitiveDateTime,
+}
+
+/// A published version of the results of a race
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = result_versions)]
+pub struct ResultVersion {
+    pub id: Id,
+    pub race_id: Id,
+    /// counts up from 1 for each race
+    pub version: i32,
+    /// the ranked results of the race as JSON
+    #[serde(skip)]
+    pub results: String,
+    /// why the results were published again
+    pub note: String,
+    /// changes compared to the previous version as JSON
+    #[serde(skip)]
+    pub changes: String,
+    /// name of the admin user that published the version
+    pub published_by: String,
+    pub published_at: time::PrimitiveDateTime,
+}
+
 fn ymd_date<S>(d: &time::Date, ser: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -0,0 +1,165 @@
+//! Registration of relay teams
+//!
+//! Each member of a team is stored as an ordinary participant with a category
+//! of the relay race. The team itself gets a team category based on the
+//! genders and the combined age of its members. If the race is full the
+//! whole team is put on the waitlist.
+//!
+//! Only the first leg gets a bib number, which the team wears, as bib numbers
+//! identify a single participant e.g. in finish orders. The later legs are
+//! timed per participant instead: by a chip assigned to the member or by a
+//! finish time the admin records for the member.
+use super::schema::{
+    categories, participants, races, starts, team_categories, team_members, teams,
+};
+use super::shared_models::TeamComposition;
+use super::Id;
+use diesel::prelude::*;
+
+/// A single member of a new team
+#[derive(Debug, Clone)]
+pub(crate) struct NewTeamMember {
+    pub(crate) first_name: String,
+    pub(crate) last_name: String,
+    pub(crate) birth_year: i32,
+    pub(crate) male: bool,
+    /// optional email address for the confirmation mail
+    pub(crate) email: Option<String>,
+}
+
+/// Outcome of a team registration
+#[derive(Debug)]
+pub(crate) enum TeamRegistration {
+    /// the team was registered, with the ids of its members in leg order
+    Registered(Vec<Id>),
+    /// the race is not a relay race of the competition
+    InvalidRace,
+    /// the number of members does not match the team size of the race
+    WrongTeamSize(i32),
+    /// there is no category in the race for the given member
+    NoCategory(String),
+    /// there is no team category matching the team
+    NoTeamCategory,
+    /// the given member is already registered for the competition
+    Duplicate(NewTeamMember),
+}
+
+/// Register a team with its members in the order of their legs
+pub(crate) fn register_team(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+    race_id: Id,
+    name: &str,
+    club: Option<&str>,
+    members: &[NewTeamMember],
+) -> QueryResult<TeamRegistration> {
+    conn.transaction(|conn| {
+        let team_size = races::table
+            .find(race_id)
+            .filter(races::competition_id.eq(competition_id))
+            .select(races::team_size)
+            .first::<Option<i32>>(conn)
+            .optional()?
+            .flatten();
+        let Some(team_size) = team_size else {
+            return Ok(TeamRegistration::InvalidRace);
+        };
+        if usize::try_from(team_size).ok() != Some(members.len()) {
+            return Ok(TeamRegistration::WrongTeamSize(team_size));
+        }
+
+        let registered_at = super::registrations::now();
+        let current_year = registered_at.year();
+        let mut category_ids = Vec::with_capacity(members.len());
+        for member in members {
+            let duplicate = super::duplicates::find_duplicate(
+                conn,
+                competition_id,
+                &member.first_name,
+                &member.last_name,
+                member.birth_year,
+                club,
+                None,
+            )?;
+            if duplicate.is_some() {
+                return Ok(TeamRegistration::Duplicate(member.clone()));
+            }
+            let age = current_year - member.birth_year;
+            let category_id = categories::table
+                .inner_join(starts::table)
+                .filter(starts::race_id.eq(race_id))
+                .filter(categories::male.eq(member.male))
+                .filter(categories::from_age.le(age))
+                .filter(categories::to_age.ge(age))
+                .select(categories::id)
+                .first::<Id>(conn)
+                .optional()?;
+            let Some(category_id) = category_id else {
+                return Ok(TeamRegistration::NoCategory(format!(
+                    "{} {}",
+                    member.first_name, member.last_name
+                )));
+            };
+            category_ids.push(category_id);
+        }
+
+        let composition = TeamComposition::of_team(members.iter().map(|m| m.male));
+        let combined_age = members
+            .iter()
+            .map(|m| current_year - m.birth_year)
+            .sum::<i32>();
+        let team_category_id = team_categories::table
+            .filter(team_categories::race_id.eq(race_id))
+            .filter(team_categories::composition.eq_any([composition, TeamComposition::Any]))
+            .filter(team_categories::from_combined_age.le(combined_age))
+            .filter(team_categories::to_combined_age.ge(combined_age))
+            .order_by(team_categories::id)
+            .select(team_categories::id)
+            .first::<Id>(conn)
+            .optional()?;
+        let Some(team_category_id) = team_category_id else {
+            return Ok(TeamRegistration::NoTeamCategory);
+        };
+
+        let team_id = diesel::insert_into(teams::table)
+            .values((
+                teams::name.eq(name),
+                teams::race_id.eq(race_id),
+                teams::team_category_id.eq(team_category_id),
+            ))
+            .returning(teams::id)
+            .get_result::<Id>(conn)?;
+        let mut member_ids = Vec::with_capacity(members.len());
+        for ((leg, member), category_id) in (1..).zip(members).zip(category_ids) {
+            let participant_id = diesel::insert_into(participants::table)
+                .values((
+                    participants::first_name.eq(&member.first_name),
+                    participants::last_name.eq(&member.last_name),
+                    participants::club.eq(club),
+                    participants::birth_year.eq(member.birth_year),
+                    participants::category_id.eq(category_id),
+                    participants::consent_agb.eq(true),
+                    participants::email.eq(&member.email),
+                    participants::registered_at.eq(registered_at),
+                ))
+                .returning(participants::id)
+                .get_result::<Id>(conn)?;
+            diesel::insert_into(team_members::table)
+                .values((
+                    team_members::team_id.eq(team_id),
+                    team_members::participant_id.eq(participant_id),
+                    team_members::leg.eq(leg),
+                ))
+                .execute(conn)?;
+            // the team wears the bib of the first leg
+            if leg == 1 {
+                super::bib_numbers::assign_bib_number(conn, participant_id, competition_id, None)?;
+            }
+            super::persons::link_participant(conn, participant_id)?;
+            super::fees::assign_fee(conn, participant_id)?;
+            member_ids.push(participant_id);
+        }
+        super::registrations::waitlist_team_if_full(conn, &member_ids)?;
+        Ok(TeamRegistration::Registered(member_ids))
+    })
+}
@@ -172,6 +172,18 @@ pub(crate) fn insert_test_data(conn: &mut SqliteConnection) -> QueryResult<()> {
             .load_iter(conn)?
             .collect::<QueryResult<HashMap<String, Id>>>()?;
 
+        // every start gets its own range of 100 bib numbers
+        let mut start_ids = start_map.values().copied().collect::<Vec<_>>();
+        start_ids.sort();
+        for (idx, start_id) in (1..).zip(start_ids) {
+            diesel::update(starts::table.find(start_id))
+                .set((
+                    starts::first_bib.eq(idx * 100),
+                    starts::last_bib.eq(idx * 100 + 99),
+                ))
+                .execute(conn)?;
+        }
+
         let categories_400m = NewCategory::clone_for_femal([
             NewCategory::new("U4 m", 0, 3, true, start_map["400m"]),
             NewCategory::new("U6 m", 4, 5, true, start_map["400m"]),
@@ -296,6 +308,11 @@ pub(crate) fn insert_test_data(conn: &mut SqliteConnection) -> QueryResult<()> {
             ))
             .execute(conn)?;
 
+        let participant_ids = participants::table.select(participants::id).load::<Id>(conn)?;
+        for participant_id in participant_ids {
+            crate::database::bib_numbers::assign_bib_number(conn, participant_id, competition_id, None)?;
+        }
+
         let password = "admin";
         let salt = SaltString::generate(&mut rand::rngs::OsRng);
         let password_hash = Argon2::default()
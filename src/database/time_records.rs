@@ -0,0 +1,149 @@
+//! Storing finish and split times of participants
+//!
+//! Recording a finish time also updates the status of the participant.
+//! Recorded finish times are never changed or removed, corrections are
+//! stored as separate time adjustments and the results apply their sum to
+//! the recorded time
+use super::schema::{
+    categories, participants, races, split_times, starts, time_adjustments, time_records,
+};
+use super::shared_models::{ParticipantStatus, TimeAdjustment};
+use super::Id;
+use diesel::prelude::*;
+use std::collections::HashMap;
+use time::PrimitiveDateTime;
+
+/// Store the first finish time of a participant
+///
+/// A participant is marked as finished, unless an official already set the
+/// participant to a state that excludes them from the ranking. An already
+/// recorded time is never replaced, this fails with a unique violation
+/// instead. Corrections need to be stored as time adjustments
+pub(crate) fn insert_finish_time(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    finish_time: PrimitiveDateTime,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        diesel::insert_into(time_records::table)
+            .values((
+                time_records::participant_id.eq(participant_id),
+                time_records::finish_time.eq(finish_time),
+            ))
+            .execute(conn)?;
+        mark_finished(conn, participant_id)
+    })
+}
+
+/// Store the finish time of a participant unless a time is already recorded
+///
+/// This is used for automatically captured times, e.g. chip reads, where a
+/// later read must not replace the recorded time. Returns whether the time
+/// was stored
+pub(crate) fn insert_missing_finish_time(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    finish_time: PrimitiveDateTime,
+) -> QueryResult<bool> {
+    conn.transaction(|conn| {
+        let inserted = diesel::insert_or_ignore_into(time_records::table)
+            .values((
+                time_records::participant_id.eq(participant_id),
+                time_records::finish_time.eq(finish_time),
+            ))
+            .execute(conn)?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+        mark_finished(conn, participant_id)?;
+        Ok(true)
+    })
+}
+
+fn mark_finished(conn: &mut SqliteConnection, participant_id: Id) -> QueryResult<()> {
+    diesel::update(participants::table.find(participant_id))
+        .filter(participants::status.ne_all(ParticipantStatus::NON_FINISHERS))
+        .set(participants::status.eq(ParticipantStatus::Finished))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Store a correction of the finish time of a participant
+///
+/// A positive delta adds time, e.g. for a time penalty
+pub(crate) fn add_time_adjustment(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    delta_ms: i64,
+    reason: &str,
+    created_by: &str,
+) -> QueryResult<()> {
+    let now = time::OffsetDateTime::now_utc();
+    diesel::insert_into(time_adjustments::table)
+        .values((
+            time_adjustments::participant_id.eq(participant_id),
+            time_adjustments::delta_ms.eq(delta_ms),
+            time_adjustments::reason.eq(reason),
+            time_adjustments::created_by.eq(created_by),
+            time_adjustments::created_at.eq(PrimitiveDateTime::new(now.date(), now.time())),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// All adjustments of a participant, oldest first
+pub(crate) fn load_time_adjustments(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<Vec<TimeAdjustment>> {
+    time_adjustments::table
+        .filter(time_adjustments::participant_id.eq(participant_id))
+        .order_by((time_adjustments::created_at, time_adjustments::id))
+        .select(TimeAdjustment::as_select())
+        .load(conn)
+}
+
+/// The summed adjustments of all participants of a competition, keyed by
+/// participant id
+///
+/// Participants without adjustments are missing
+pub(crate) fn summed_time_adjustments(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<HashMap<Id, i64>> {
+    let adjustments = time_adjustments::table
+        .inner_join(
+            participants::table
+                .inner_join(categories::table.inner_join(starts::table.inner_join(races::table))),
+        )
+        .filter(races::competition_id.eq(competition_id))
+        .select((time_adjustments::participant_id, time_adjustments::delta_ms))
+        .load::<(Id, i64)>(conn)?;
+    let mut sums = HashMap::new();
+    for (participant_id, delta_ms) in adjustments {
+        *sums.entry(participant_id).or_default() += delta_ms;
+    }
+    Ok(sums)
+}
+
+/// Store the time a participant passed an intermediate timing point
+///
+/// An already recorded time for the same timing point is replaced
+pub(crate) fn record_split_time(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+    timing_point_id: Id,
+    time: PrimitiveDateTime,
+) -> QueryResult<()> {
+    diesel::insert_into(split_times::table)
+        .values((
+            split_times::participant_id.eq(participant_id),
+            split_times::timing_point_id.eq(timing_point_id),
+            split_times::time.eq(time),
+        ))
+        .on_conflict((split_times::participant_id, split_times::timing_point_id))
+        .do_update()
+        .set(split_times::time.eq(time))
+        .execute(conn)?;
+    Ok(())
+}
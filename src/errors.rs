@@ -30,6 +30,14 @@ pub enum Error {
     NotFound(String),
     #[error("Received invalid input: {0}")]
     InvalidInput(String),
+    #[error("Invalid upload: {0}")]
+    MultipartError(#[from] axum::extract::multipart::MultipartError),
+    #[error("Invalid JSON data: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Cannot send mail: {0}")]
+    MailError(String),
+    #[error("IO Error: {0}")]
+    IoError(#[from] std::io::Error),
 }
 
 impl From<deadpool_diesel::InteractError> for Error {
@@ -38,6 +46,24 @@ impl From<deadpool_diesel::InteractError> for Error {
     }
 }
 
+impl From<lettre::address::AddressError> for Error {
+    fn from(value: lettre::address::AddressError) -> Self {
+        Self::MailError(value.to_string())
+    }
+}
+
+impl From<lettre::error::Error> for Error {
+    fn from(value: lettre::error::Error) -> Self {
+        Self::MailError(value.to_string())
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for Error {
+    fn from(value: lettre::transport::smtp::Error) -> Self {
+        Self::MailError(value.to_string())
+    }
+}
+
 impl From<argon2::password_hash::Error> for Error {
     fn from(_value: argon2::password_hash::Error) -> Self {
         Self::HashError
@@ -56,11 +82,18 @@ impl IntoResponse for Error {
             Error::NotFound(_) | Error::DieselError(diesel::result::Error::NotFound) => {
                 StatusCode::NOT_FOUND
             }
-            Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            Error::DieselError(diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            )) => StatusCode::CONFLICT,
+            Error::InvalidInput(_) | Error::MultipartError(_) => StatusCode::BAD_REQUEST,
             Error::PoolInteractError(_)
             | Error::DieselError(_)
             | Error::PoolError(_)
             | Error::HashError
+            | Error::JsonError(_)
+            | Error::MailError(_)
+            | Error::IoError(_)
             | Error::TemplateError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         let mut resp = Json(ErrorResponse {
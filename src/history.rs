@@ -0,0 +1,136 @@
+//! Render the history of a person across all competitions
+//!
+//! Places and times are taken from the latest published version of the
+//! results if the race has one
+use crate::app_state::{self, AppState};
+use crate::database::schema::{categories, competitions, participants, persons, races, starts};
+use crate::database::shared_models::{Competition, ParticipantStatus};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::Html;
+use axum::Router;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new().route(
+        "/persons/{person_id}/history.html",
+        axum::routing::get(render_history),
+    )
+}
+
+#[derive(Queryable, Serialize)]
+struct PersonInfo {
+    first_name: String,
+    last_name: String,
+    birth_year: i32,
+}
+
+/// A single participation of the person
+#[derive(Serialize)]
+struct HistoryEntry {
+    competition: Competition,
+    race: String,
+    category: String,
+    club: Option<String>,
+    status: ParticipantStatus,
+    /// ranking time in milliseconds, only set for ranked finishers
+    time: Option<i64>,
+    place: Option<usize>,
+    category_place: Option<usize>,
+}
+
+/// Data used to render the history page
+///
+/// See `templates/person_history.html` for the relevant template
+#[derive(Serialize)]
+struct HistoryData {
+    person: PersonInfo,
+    /// all participations, the latest first
+    entries: Vec<HistoryEntry>,
+}
+
+/// Load all participations of a person with their results
+fn load_history(conn: &mut SqliteConnection, person_id: Id) -> QueryResult<Vec<HistoryEntry>> {
+    let participations = participants::table
+        .inner_join(
+            categories::table
+                .inner_join(starts::table.inner_join(races::table.inner_join(competitions::table))),
+        )
+        .filter(participants::person_id.eq(person_id))
+        .order_by((competitions::date.desc(), competitions::id.desc()))
+        .select((
+            participants::id,
+            Competition::as_select(),
+            races::name,
+            categories::label,
+            participants::club,
+            participants::status,
+        ))
+        .load::<(
+            Id,
+            Competition,
+            String,
+            String,
+            Option<String>,
+            ParticipantStatus,
+        )>(conn)?;
+
+    let mut ranked = HashMap::<Id, (i64, usize, usize)>::new();
+    let mut loaded_competitions = Vec::new();
+    for (_, competition, ..) in &participations {
+        if loaded_competitions.contains(&competition.id) {
+            continue;
+        }
+        loaded_competitions.push(competition.id);
+        for race in crate::results::load_shown_rankings(conn, competition.id)? {
+            ranked.extend(
+                race.participants
+                    .into_iter()
+                    .map(|entry| (entry.id, (entry.time, entry.place, entry.category_place))),
+            );
+        }
+    }
+
+    Ok(participations
+        .into_iter()
+        .map(
+            |(participant_id, competition, race, category, club, status)| {
+                let result = ranked.get(&participant_id);
+                HistoryEntry {
+                    competition,
+                    race,
+                    category,
+                    club,
+                    status,
+                    time: result.map(|(time, ..)| *time),
+                    place: result.map(|(_, place, _)| *place),
+                    category_place: result.map(|(.., category_place)| *category_place),
+                }
+            },
+        )
+        .collect())
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_history(state: AppState, Path(person_id): Path<Id>) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let Some(person) = persons::table
+                .find(person_id)
+                .select((persons::first_name, persons::last_name, persons::birth_year))
+                .first::<PersonInfo>(conn)
+                .optional()?
+            else {
+                return QueryResult::Ok(None);
+            };
+            let entries = load_history(conn, person_id)?;
+            Ok(Some(HistoryData { person, entries }))
+        })
+        .await?;
+    let data =
+        data.ok_or_else(|| Error::NotFound(format!("No person for id {person_id} found")))?;
+    state.render_template("person_history.html", data)
+}
@@ -20,13 +20,27 @@ use service_config::Config;
 use tower_http::trace::TraceLayer;
 
 pub mod admin;
+mod age_grading;
 pub mod app_state;
+mod awards;
+mod chip_timing;
+mod club_results;
 mod competition_overview;
+mod correction_requests;
 pub mod database;
 pub mod errors;
+mod history;
+pub mod mail;
+mod my_registration;
+mod pdf;
 mod registration;
 mod registration_list;
+mod result_versions;
+mod results;
+mod series;
 pub mod service_config;
+mod team_registration;
+mod timing_listener;
 
 mod axum_ext;
 
@@ -52,6 +66,22 @@ pub async fn setup(config: Config) -> (Router, app_state::State) {
             .expect("Failed to insert test data")
             .expect("Failed to insert test data");
     }
+
+    if config.timing_listener {
+        let token = config
+            .timing_token
+            .clone()
+            .filter(|token| !token.is_empty())
+            .expect("The timing listener requires a token, set --timing-token or TIMING_TOKEN");
+        let address = timing_listener::spawn(
+            state.clone(),
+            (config.timing_address, config.timing_port).into(),
+            token,
+        )
+        .await
+        .expect("Failed to start the timing listener");
+        tracing::info!("Listening for timing readers at {address}");
+    }
     // Session layer.
     let session_store = SqliteSessionStore::new(state.pool.clone());
     let session_layer = SessionManagerLayer::new(session_store);
@@ -68,7 +98,15 @@ pub async fn setup(config: Config) -> (Router, app_state::State) {
             axum::routing::get(self::competition_overview::render),
         )
         .merge(registration::routes())
+        .merge(my_registration::routes())
         .merge(registration_list::routes())
+        .merge(team_registration::routes())
+        .merge(results::routes())
+        .merge(result_versions::routes())
+        .merge(correction_requests::routes())
+        .merge(club_results::routes())
+        .merge(series::routes())
+        .merge(history::routes())
         .nest("/admin", admin::routes());
     let router = if base_url.is_empty() {
         router
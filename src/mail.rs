@@ -0,0 +1,143 @@
+//! Sending emails to participants
+//!
+//! The mails are sent via a [`Mailer`]. In production this is usually the
+//! [`SmtpMailer`], which hands the mails to an SMTP relay. Without a
+//! configured SMTP server the [`FileMailer`] logs the mails and optionally
+//! appends them to a file, which is also useful for tests.
+use crate::errors::{Error, Result};
+use crate::service_config::Config;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A plain text mail
+#[derive(Debug, Clone)]
+pub struct Mail {
+    /// address of the recipient
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// A transport for mails
+#[async_trait::async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+/// Send mails via an SMTP relay
+///
+/// The connection is encrypted via STARTTLS, unless it is explicitly
+/// configured to be insecure for a relay running on the same host
+pub struct SmtpMailer {
+    from: lettre::message::Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        server: &str,
+        port: Option<u16>,
+        insecure: bool,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self> {
+        let mut transport = if insecure {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(server)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(server)?
+        };
+        if let Some(port) = port {
+            transport = transport.port(port);
+        }
+        if let Some((user, password)) = credentials {
+            transport = transport.credentials(Credentials::new(user, password));
+        }
+        Ok(Self {
+            from: from.parse()?,
+            transport: transport.build(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(mail.to.parse()?)
+            .subject(&mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body.clone())?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Log mails instead of sending them
+///
+/// If a path is given the mails are also appended to that file
+pub struct FileMailer {
+    path: Option<PathBuf>,
+    /// serializes writes to the file
+    lock: Mutex<()>,
+}
+
+impl FileMailer {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: &Mail) -> Result<()> {
+        tracing::info!(to = mail.to, subject = mail.subject, "Not sending mail");
+        if let Some(path) = &self.path {
+            let _guard = self
+                .lock
+                .lock()
+                .map_err(|e| Error::MailError(e.to_string()))?;
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            write!(
+                file,
+                "To: {}\nSubject: {}\n\n{}\n\n",
+                mail.to, mail.subject, mail.body
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Setup the mailer from the configuration
+pub fn from_config(config: &Config) -> Result<Arc<dyn Mailer>> {
+    Ok(match &config.smtp_server {
+        Some(server) => {
+            let password = match &config.smtp_password_file {
+                Some(path) => Some(
+                    std::fs::read_to_string(path)?
+                        .trim_end_matches(['\r', '\n'])
+                        .to_owned(),
+                ),
+                None => config.smtp_password.clone(),
+            };
+            Arc::new(SmtpMailer::new(
+                server,
+                config.smtp_port,
+                config.smtp_insecure,
+                config.smtp_user.clone().zip(password),
+                &config.mail_from,
+            )?)
+        }
+        None => Arc::new(FileMailer::new(config.mail_file.clone())),
+    })
+}
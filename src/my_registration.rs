@@ -0,0 +1,300 @@
+//! Routes for participants to edit or cancel their own registration
+//!
+//! Participants receive a link with a signed token in their confirmation
+//! mail. The token is the id of the participant followed by an HMAC of that
+//! id, so it cannot be guessed without the server side secret and does not
+//! need to be stored.
+use crate::app_state::{self, AppState};
+use crate::database::schema::{
+    categories, participants, payments, races, starts, team_members, time_records,
+};
+use crate::database::shared_models::PaymentStatus;
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::registration::RegistrationForm;
+use axum::extract::{Form, Path};
+use axum::response::{Html, Redirect};
+use axum::Router;
+use diesel::prelude::*;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/{event_id}/my_registration/{token}",
+            axum::routing::get(render_my_registration).post(update_my_registration),
+        )
+        .route(
+            "/{event_id}/my_registration/{token}/confirmation.html",
+            axum::routing::get(render_confirmation),
+        )
+        .route(
+            "/{event_id}/my_registration/{token}/cancel.html",
+            axum::routing::get(render_cancel_registration),
+        )
+        .route(
+            "/{event_id}/my_registration/{token}/cancel",
+            axum::routing::post(cancel_registration),
+        )
+}
+
+/// name of the secret used to sign the tokens
+const TOKEN_SECRET: &str = "registration_token";
+
+fn token_mac(secret: &[u8], participant_id: Id) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(format!("registration:{participant_id}").as_bytes());
+    mac
+}
+
+/// The token for the self-service links of a participant
+pub(crate) fn registration_token(
+    conn: &mut SqliteConnection,
+    participant_id: Id,
+) -> QueryResult<String> {
+    let secret = crate::database::secrets::secret(conn, TOKEN_SECRET)?;
+    let signature = token_mac(&secret, participant_id)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    Ok(format!("{participant_id}-{signature}"))
+}
+
+/// Check the token, returns the id of the participant if it is valid
+fn verify_token(conn: &mut SqliteConnection, token: &str) -> QueryResult<Option<Id>> {
+    let Some((participant_id, signature)) = token.split_once('-') else {
+        return Ok(None);
+    };
+    let Ok(participant_id) = participant_id.parse::<Id>() else {
+        return Ok(None);
+    };
+    if signature.len() % 2 != 0 || !signature.is_ascii() {
+        return Ok(None);
+    }
+    let Ok(signature) = (0..signature.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&signature[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return Ok(None);
+    };
+    let secret = crate::database::secrets::secret(conn, TOKEN_SECRET)?;
+    Ok(token_mac(&secret, participant_id)
+        .verify_slice(&signature)
+        .is_ok()
+        .then_some(participant_id))
+}
+
+/// The participant a token belongs to
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = participants)]
+struct RegisteredParticipant {
+    id: Id,
+    first_name: String,
+    last_name: String,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = races::competition_id)]
+    competition_id: Id,
+    #[serde(skip)]
+    #[diesel(select_expression = races::id)]
+    race_id: Id,
+    #[serde(skip)]
+    #[diesel(select_expression = starts::id)]
+    start_id: Id,
+}
+
+/// Resolve the token to the participant, fails with not found for invalid
+/// tokens and tokens of other competitions
+async fn participant_for_token(
+    state: &AppState,
+    event_id: Id,
+    token: String,
+) -> Result<RegisteredParticipant> {
+    state
+        .with_connection(move |conn| {
+            let Some(participant_id) = verify_token(conn, &token)? else {
+                return Ok(None);
+            };
+            participants::table
+                .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+                .filter(participants::id.eq(participant_id))
+                .filter(races::competition_id.eq(event_id))
+                .select(RegisteredParticipant::as_select())
+                .first(conn)
+                .optional()
+        })
+        .await?
+        .ok_or_else(|| Error::NotFound(String::from("No registration for this link")))
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_my_registration(
+    state: AppState,
+    Path((event_id, token)): Path<(Id, String)>,
+) -> Result<Html<String>> {
+    let participant = participant_for_token(&state, event_id, token.clone()).await?;
+    let (mut data, _) =
+        crate::admin::participants::load_participant_by_id(&state, participant.id).await?;
+    data.id = Some(participant.id);
+    crate::registration::render_registration_page_with_optional_data(
+        state,
+        event_id,
+        Some(data),
+        "edit_registration",
+        format!("{event_id}/my_registration/{token}"),
+    )
+    .await
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn update_my_registration(
+    state: AppState,
+    Path((event_id, token)): Path<(Id, String)>,
+    form_data: Form<RegistrationForm>,
+) -> Result<Redirect> {
+    let participant = participant_for_token(&state, event_id, token.clone()).await?;
+    crate::registration::ensure_registration_open(&state, event_id).await?;
+    let participant_id = participant.id;
+    let mut form_data = form_data.0;
+    // participants cannot choose their bib number
+    form_data.bib = None;
+    form_data
+        .into_database(&state, event_id, Some(participant_id), false)
+        .await?;
+    let base_url = state.base_url();
+    Ok(Redirect::to(&format!(
+        "{base_url}/{event_id}/my_registration/{token}"
+    )))
+}
+
+/// The entry fee of a participant
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = payments)]
+struct Payment {
+    /// in cents
+    amount_due: i32,
+    status: PaymentStatus,
+}
+
+/// Data used to render the confirmation page
+///
+/// see `templates/registration_confirmation.html` for the template
+#[derive(Serialize)]
+struct ConfirmationPageData {
+    participant: RegisteredParticipant,
+    token: String,
+    /// whether the participant is on the waitlist
+    waitlisted: bool,
+    /// `None` for races without entry fees
+    payment: Option<Payment>,
+}
+
+/// The page participants are sent to after their registration
+#[axum::debug_handler(state = app_state::State)]
+async fn render_confirmation(
+    state: AppState,
+    Path((event_id, token)): Path<(Id, String)>,
+) -> Result<Html<String>> {
+    let participant = participant_for_token(&state, event_id, token.clone()).await?;
+    let participant_id = participant.id;
+    let (waitlisted, payment) = state
+        .with_connection(move |conn| {
+            participants::table
+                .left_join(payments::table)
+                .filter(participants::id.eq(participant_id))
+                .select((
+                    participants::waitlisted_at.is_not_null(),
+                    Option::<Payment>::as_select(),
+                ))
+                .first::<(bool, Option<Payment>)>(conn)
+        })
+        .await?;
+    state.render_template(
+        "registration_confirmation.html",
+        ConfirmationPageData {
+            participant,
+            token,
+            waitlisted,
+            payment,
+        },
+    )
+}
+
+/// Data used to render the cancel page
+///
+/// see `templates/cancel_registration.html` for the template
+#[derive(Serialize)]
+struct CancelPageData {
+    participant: RegisteredParticipant,
+    token: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_cancel_registration(
+    state: AppState,
+    Path((event_id, token)): Path<(Id, String)>,
+) -> Result<Html<String>> {
+    let participant = participant_for_token(&state, event_id, token.clone()).await?;
+    state.render_template(
+        "cancel_registration.html",
+        CancelPageData { participant, token },
+    )
+}
+
+/// Withdraw the registration by removing the participant
+///
+/// This is only possible as long as there is no finish time for the
+/// participant, the participant is not part of a relay team and the entry
+/// fee is neither paid nor refunded, so the organizer keeps the record of
+/// the payment. The freed spot is given to the waitlist of the race.
+#[axum::debug_handler(state = app_state::State)]
+async fn cancel_registration(
+    state: AppState,
+    Path((event_id, token)): Path<(Id, String)>,
+) -> Result<Redirect> {
+    let participant = participant_for_token(&state, event_id, token).await?;
+    let participant_id = participant.id;
+    let race_id = participant.race_id;
+    state
+        .with_connection(move |conn| {
+            conn.transaction(|conn| {
+                let finished = diesel::select(diesel::dsl::exists(
+                    time_records::table.filter(time_records::participant_id.eq(participant_id)),
+                ))
+                .get_result::<bool>(conn)?;
+                let in_team = diesel::select(diesel::dsl::exists(
+                    team_members::table.filter(team_members::participant_id.eq(participant_id)),
+                ))
+                .get_result::<bool>(conn)?;
+                if finished || in_team {
+                    return Ok(Err(Error::InvalidInput(String::from(
+                        "Registrations with a finish time or of relay teams cannot be cancelled",
+                    ))));
+                }
+                let paid = diesel::select(diesel::dsl::exists(
+                    payments::table
+                        .filter(payments::participant_id.eq(participant_id))
+                        .filter(payments::status.ne(PaymentStatus::Open)),
+                ))
+                .get_result::<bool>(conn)?;
+                if paid {
+                    return Ok(Err(Error::InvalidInput(String::from(
+                        "Registrations with a paid entry fee cannot be cancelled, please contact the organizer",
+                    ))));
+                }
+                diesel::delete(participants::table.find(participant_id)).execute(conn)?;
+                crate::database::registrations::promote_waitlist(conn, race_id)?;
+                QueryResult::Ok(Ok(()))
+            })
+        })
+        .await??;
+    let base_url = state.base_url();
+    Ok(Redirect::to(&format!(
+        "{base_url}/{event_id}/registration_list.html"
+    )))
+}
@@ -0,0 +1,120 @@
+//! A minimal PDF writer for printable lists
+//!
+//! Only lines of plain text in the standard Helvetica fonts are supported.
+//! That is enough for the lists used at the finish area and avoids a large
+//! PDF dependency. Characters that are not part of Latin-1 are printed as `?`.
+
+/// A single line of a document
+pub(crate) enum PdfLine {
+    /// a bold line, e.g. the name of a category
+    Heading(String),
+    /// a regular line
+    Text(String),
+    /// an empty line
+    Blank,
+}
+
+const PAGE_WIDTH: i32 = 595;
+const PAGE_HEIGHT: i32 = 842;
+const MARGIN: i32 = 50;
+const LEADING: i32 = 14;
+
+/// Encode text as PDF string literal in the `WinAnsiEncoding`
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        let byte = u8::try_from(u32::from(c)).unwrap_or(b'?');
+        if matches!(byte, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        // control characters would end up as garbage in the output
+        out.push(if byte < b' ' { b' ' } else { byte });
+    }
+    out.push(b')');
+    out
+}
+
+/// The content stream of a single page
+fn page_content(lines: &[PdfLine]) -> Vec<u8> {
+    let mut content = Vec::new();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    for line in lines {
+        let (font, size, text) = match line {
+            PdfLine::Heading(text) => ("F2", 12, text),
+            PdfLine::Text(text) => ("F1", 10, text),
+            PdfLine::Blank => {
+                y -= LEADING;
+                continue;
+            }
+        };
+        content.extend_from_slice(format!("BT /{font} {size} Tf {MARGIN} {y} Td ").as_bytes());
+        content.extend_from_slice(&pdf_string(text));
+        content.extend_from_slice(b" Tj ET\n");
+        y -= LEADING;
+    }
+    content
+}
+
+/// Render the lines on as many A4 pages as needed
+pub(crate) fn document(lines: &[PdfLine]) -> Vec<u8> {
+    let lines_per_page = usize::try_from((PAGE_HEIGHT - 2 * MARGIN) / LEADING).unwrap_or(1);
+    let pages = if lines.is_empty() {
+        vec![lines]
+    } else {
+        lines.chunks(lines_per_page).collect()
+    };
+
+    // objects 1 to 4 are the catalog, the page tree and the two fonts,
+    // followed by a page and its content stream for each page
+    let kids = (0..pages.len())
+        .map(|page| format!("{} 0 R", 5 + 2 * page))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut objects = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{kids}] /Count {} >>", pages.len()).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (page, page_lines) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                6 + 2 * page
+            )
+            .into_bytes(),
+        );
+        let content = page_content(page_lines);
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(&content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (idx, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", idx + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+    out
+}
@@ -1,7 +1,10 @@
 //! Routes for handling the registration of a new participant
 use crate::app_state::{self, AppState};
-use crate::database::schema::{categories, competitions, participants, races, starts};
-use crate::database::shared_models::{Competition, Race, SpecialCategories};
+use crate::database::registrations::RegistrationWindow;
+use crate::database::schema::{
+    bib_numbers, categories, competitions, participants, payments, races, starts,
+};
+use crate::database::shared_models::{Competition, Race, SpecialCategories, TimeAdjustment};
 use crate::database::Id;
 use crate::errors::{Error, Result};
 use axum::extract::Path;
@@ -43,6 +46,9 @@ pub struct ParticipantForForm {
     #[diesel(select_expression = races::id.nullable())]
     pub race_id: Option<Id>,
     consent_agb: bool,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    pub bib: Option<i32>,
+    email: Option<String>,
 }
 
 /// Existing participant data used for the update form via the admin
@@ -52,6 +58,10 @@ pub struct ParticipantWithSpecialCategories {
     #[serde(flatten)]
     pub participant: ParticipantForForm,
     pub special_categories: Vec<Id>,
+    /// id of the participant, `None` while adding a new participant
+    pub id: Option<Id>,
+    /// corrections of the finish time, oldest first
+    pub time_adjustments: Vec<TimeAdjustment>,
 }
 
 /// Data used to render the participant form
@@ -80,6 +90,8 @@ struct RegistrationPageData {
     /// The target uri the form posts data to
     /// `base_url` is automatically prepended by the template
     target_uri: String,
+    /// whether new participants can register right now
+    registration_window: RegistrationWindow,
 }
 
 /// Data for a specific race with minimal and maximal age for this race
@@ -96,6 +108,8 @@ struct RaceWithSpecialCategory {
     #[serde(flatten)]
     race: RaceWithMinMaxAge,
     special_categories: Vec<SpecialCategories>,
+    /// free spots of the race, `None` if the race is not limited
+    remaining_spots: Option<i64>,
 }
 
 impl HasTable for RaceWithMinMaxAge {
@@ -128,6 +142,12 @@ pub struct RegistrationForm {
     /// More data about the participant
     #[serde(flatten)]
     pub new_participant: NewParticipant,
+    /// Bib number of the participant
+    ///
+    /// This is only set via the admin pages to override the automatically
+    /// assigned number
+    #[serde(default, deserialize_with = "parse_optional_number")]
+    pub bib: Option<i32>,
     /// For which special categories the participant registered for
     #[serde(flatten)]
     pub special_categories: HashMap<Id, String>,
@@ -152,6 +172,9 @@ pub struct NewParticipant {
     #[diesel(column_name = "birth_year")]
     #[serde(deserialize_with = "parse_string")]
     pub age: i32,
+    /// optional email address, used to recognize returning participants
+    #[serde(default, deserialize_with = "parse_optional_string")]
+    pub email: Option<String>,
 }
 
 fn parse_checkbox<'de, D>(d: D) -> Result<bool, D::Error>
@@ -170,10 +193,36 @@ where
     s.parse().map_err(serde::de::Error::custom)
 }
 
+pub(crate) fn parse_optional_string<'de, D>(d: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // percent-encoded values cannot be borrowed from the request body
+    let s = String::deserialize(d)?;
+    let s = s.trim();
+    Ok((!s.is_empty()).then(|| s.to_owned()))
+}
+
+pub(crate) fn parse_optional_number<'de, D>(d: D) -> Result<Option<i32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(d)?;
+    if s.is_empty() {
+        Ok(None)
+    } else {
+        s.parse().map(Some).map_err(serde::de::Error::custom)
+    }
+}
+
 impl RegistrationForm {
     /// Are the provided registration form data valid
     fn is_valid(&self) -> Result<()> {
-        if !self.new_participant.consent {
+        if self.bib.is_some_and(|bib| bib <= 0) {
+            Err(Error::InvalidInput(String::from(
+                "Bib numbers need to be positive",
+            )))
+        } else if !self.new_participant.consent {
             tracing::debug!(?self);
             Err(Error::InvalidInput(String::from(
                 "Expect that you consent to the \
@@ -184,14 +233,33 @@ impl RegistrationForm {
         }
     }
 
+    /// The error for a registration of an athlete who is already registered
+    /// for the competition
+    fn duplicate_error(&self, state: &AppState) -> Error {
+        let birth_year = self.new_participant.age.to_string();
+        Error::InvalidInput(state.translation_with_params(
+            "duplicate_registration",
+            HashMap::from([
+                ("first_name", self.new_participant.firstname.as_str()),
+                ("last_name", self.new_participant.lastname.as_str()),
+                ("birth_year", birth_year.as_str()),
+            ]),
+        ))
+    }
+
     /// Insert the registration form data into the database
     ///
     /// If a `participant_id` is provided we need to handle an update
     /// otherwise it's an insert of existing data
-    #[expect(
-        clippy::unused_async,
-        reason = "Implementing the todo will make the function async"
-    )]
+    ///
+    /// The waitlist is updated in the same transaction: a new participant is
+    /// put on the waitlist if the start or race is full, an edit moving the
+    /// participant to another start is handled by
+    /// [`crate::database::registrations::start_changed`]
+    ///
+    /// Registrations of an athlete who is already registered for the
+    /// competition are rejected, unless they are entered by an admin. These
+    /// are stored and show up in the duplicates report instead
     pub async fn into_database(
         self,
         state: &AppState,
@@ -201,10 +269,18 @@ impl RegistrationForm {
         // first iteration
         // (otherwise: Just perform an update instead of in insert if that's set)
         participant_id: Option<Id>,
-    ) -> Result<()> {
+        by_admin: bool,
+    ) -> Result<Id> {
         self.is_valid()?;
+        let duplicate_error = self.duplicate_error(state);
+        let first_name = self.new_participant.firstname.clone();
+        let last_name = self.new_participant.lastname.clone();
+        let birth_year = self.new_participant.age;
+        let club = self.new_participant.club.clone();
         let age = time::OffsetDateTime::now_utc().year() - self.new_participant.age;
         let special_categories_id = self.special_categories.keys().copied().collect::<Vec<_>>();
+        let requested_bib = self.bib;
+        let edited_participant_id = participant_id;
 
         // for inserting/updating participant data we need to perform several database related operations
         //
@@ -219,9 +295,173 @@ impl RegistrationForm {
         //        + You can skip that in the first iteration
         // 3. Insert participant
         // 4. Insert special category mapping
+        // 5. Return the id of the inserted/updated participant, the bib number
+        //    and the entry fee are assigned, a new participant is linked to
+        //    a person and the waitlist is updated below based on that id
+        let participant_id = state
+            .with_connection(move |conn| {
+                conn.transaction(|conn| {
+                    let duplicate = crate::database::duplicates::find_duplicate(
+                        conn,
+                        competition_id,
+                        &first_name,
+                        &last_name,
+                        birth_year,
+                        Some(&club),
+                        edited_participant_id,
+                    )?;
+                    if let Some(duplicate) = duplicate {
+                        if !by_admin {
+                            return Ok(Err(duplicate_error));
+                        }
+                        tracing::info!(
+                            duplicate,
+                            "Admin registered a likely duplicate, it is listed in the duplicates report"
+                        );
+                    }
+
+                    // an edit might switch the race or start, which changes
+                    // the entry fee and the waitlist
+                    let previous = match edited_participant_id {
+                        Some(participant_id) => Some((
+                            crate::database::registrations::race_of_participant(
+                                conn,
+                                participant_id,
+                            )?,
+                            crate::database::registrations::start_of_participant(
+                                conn,
+                                participant_id,
+                            )?,
+                        )),
+                        None => None,
+                    };
 
-        todo!("Insert the new participant into the database")
+                    let participant_id: Id = todo!("Insert the new participant into the database");
+
+                    crate::database::bib_numbers::assign_bib_number(
+                        conn,
+                        participant_id,
+                        competition_id,
+                        requested_bib,
+                    )?;
+                    // edits keep their person, an admin might have split or
+                    // merged it manually, and their time of registration
+                    match previous {
+                        Some((previous_race_id, previous_start_id)) => {
+                            crate::database::fees::reassign_fee(
+                                conn,
+                                participant_id,
+                                previous_race_id,
+                            )?;
+                            crate::database::registrations::start_changed(
+                                conn,
+                                participant_id,
+                                previous_start_id,
+                            )?;
+                        }
+                        None => {
+                            diesel::update(participants::table.find(participant_id))
+                                .set(
+                                    participants::registered_at
+                                        .eq(crate::database::registrations::now()),
+                                )
+                                .execute(conn)?;
+                            crate::database::persons::link_participant(conn, participant_id)?;
+                            crate::database::fees::assign_fee(conn, participant_id)?;
+                            crate::database::registrations::waitlist_if_full(conn, participant_id)?;
+                        }
+                    }
+                    QueryResult::Ok(Ok(participant_id))
+                })
+            })
+            .await??;
+        Ok(participant_id)
+    }
+}
+
+/// The registered participant as shown in the confirmation mail
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = participants)]
+struct ConfirmedParticipant {
+    first_name: String,
+    last_name: String,
+    #[serde(skip)]
+    email: Option<String>,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = categories::label)]
+    category: String,
+    #[diesel(select_expression = races::name)]
+    race: String,
+    #[diesel(select_expression = competitions::name)]
+    competition: String,
+    /// whether the participant is on the waitlist
+    #[diesel(select_expression = participants::waitlisted_at.is_not_null())]
+    waitlisted: bool,
+    /// entry fee in cents, `None` for races without fees
+    #[diesel(select_expression = payments::amount_due.nullable())]
+    amount_due: Option<i32>,
+}
+
+/// Data used to render the confirmation mail
+///
+/// see `templates/mail_registration_confirmation.txt` for the template
+#[derive(Serialize)]
+struct ConfirmationData {
+    #[serde(flatten)]
+    participant: ConfirmedParticipant,
+    /// self-service link to edit the registration
+    edit_url: String,
+    /// self-service link to cancel the registration
+    cancel_url: String,
+}
+
+/// Send a confirmation mail to a newly registered participant
+///
+/// Participants without email address do not get a mail. The registration
+/// is already stored, so a failure to send the mail is only logged
+pub(crate) async fn send_confirmation(
+    state: &AppState,
+    event_id: Id,
+    participant_id: Id,
+) -> Result<()> {
+    let (participant, token) = state
+        .with_connection(move |conn| {
+            let participant = participants::table
+                .inner_join(categories::table.inner_join(
+                    starts::table.inner_join(races::table.inner_join(competitions::table)),
+                ))
+                .left_join(bib_numbers::table)
+                .left_join(payments::table)
+                .filter(participants::id.eq(participant_id))
+                .select(ConfirmedParticipant::as_select())
+                .first(conn)?;
+            let token = crate::my_registration::registration_token(conn, participant_id)?;
+            QueryResult::Ok((participant, token))
+        })
+        .await?;
+    let Some(to) = participant.email.clone() else {
+        return Ok(());
+    };
+    let subject = state.translation_with_params(
+        "mail_registration_subject",
+        HashMap::from([("competition", participant.competition.as_str())]),
+    );
+    let edit_url = format!("{}/{event_id}/my_registration/{token}", state.public_url());
+    let data = ConfirmationData {
+        participant,
+        cancel_url: format!("{edit_url}/cancel.html"),
+        edit_url,
+    };
+    let body = state.render_text("mail_registration_confirmation.txt", data)?;
+    let mail = crate::mail::Mail { to, subject, body };
+    if let Err(e) = state.mailer().send(&mail).await {
+        tracing::warn!(
+            participant_id,
+            "Failed to send the registration confirmation: {e}"
+        );
     }
+    Ok(())
 }
 
 /// Load data relevant for the registration form for a certain competition
@@ -256,11 +496,16 @@ fn load_competition_data(
             .grouped_by(&races)
             .into_iter()
             .zip(races)
-            .map(|(special_categories, race)| RaceWithSpecialCategory {
-                race,
-                special_categories,
+            .map(|(special_categories, race)| {
+                let remaining_spots =
+                    crate::database::registrations::free_spots_in_race(conn, race.race.id)?;
+                QueryResult::Ok(RaceWithSpecialCategory {
+                    race,
+                    special_categories,
+                    remaining_spots,
+                })
             })
-            .collect();
+            .collect::<QueryResult<_>>()?;
 
         QueryResult::Ok(Some((competition, races)))
     } else {
@@ -310,23 +555,60 @@ pub async fn render_registration_page_with_optional_data(
             participant,
             head_title: state.translation(&format!("short_{title}")),
             title: state.translation_with_params(title, params),
+            registration_window: RegistrationWindow::of(
+                &competition,
+                crate::database::registrations::now(),
+            ),
             event: competition,
             target_uri,
         },
     )
 }
 
+/// Reject registrations and changes of registrations outside of the
+/// registration window of the competition
+pub(crate) async fn ensure_registration_open(state: &AppState, competition_id: Id) -> Result<()> {
+    let competition = state
+        .with_connection(move |conn| {
+            competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)
+        })
+        .await?;
+    match RegistrationWindow::of(&competition, crate::database::registrations::now()) {
+        RegistrationWindow::Open => Ok(()),
+        RegistrationWindow::NotYetOpen => Err(Error::InvalidInput(String::from(
+            "The registration is not open yet",
+        ))),
+        RegistrationWindow::Closed => Err(Error::InvalidInput(String::from(
+            "The registration is closed",
+        ))),
+    }
+}
+
 /// Handle adding a new participant
 #[axum::debug_handler(state = app_state::State)]
 async fn add_participant(
     state: AppState,
-    event_id: Path<Id>,
+    Path(event_id): Path<Id>,
     form_data: axum::extract::Form<RegistrationForm>,
 ) -> Result<Redirect> {
-    form_data.0.into_database(&state, event_id.0, None).await?;
+    ensure_registration_open(&state, event_id).await?;
+    let mut form_data = form_data.0;
+    // bib numbers are always assigned automatically for public registrations
+    form_data.bib = None;
+    let participant_id = form_data
+        .into_database(&state, event_id, None, false)
+        .await?;
+    send_confirmation(&state, event_id, participant_id).await?;
+    let token = state
+        .with_connection(move |conn| {
+            crate::my_registration::registration_token(conn, participant_id)
+        })
+        .await?;
     let base_url = state.base_url();
     Ok(Redirect::to(&format!(
-        "{base_url}/{}/registration_list.html",
-        event_id.0
+        "{base_url}/{event_id}/my_registration/{token}/confirmation.html"
     )))
 }
@@ -1,6 +1,6 @@
 //! Render a list of all participants for a specific competition grouped by races
 use crate::app_state::{self, AppState};
-use crate::database::schema::{categories, participants, races, starts};
+use crate::database::schema::{bib_numbers, categories, participants, races, starts};
 use crate::database::shared_models::{
     Competition, Race, SpecialCategories, SpecialCategoryPerParticipant,
 };
@@ -29,6 +29,9 @@ pub struct ParticipantEntry {
     /// id of the participant
     #[serde(skip)]
     id: Id,
+    /// bib number of the participant, if already assigned
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
     /// first name of the participant
     first_name: String,
     /// last name of the participant
@@ -47,6 +50,9 @@ pub struct ParticipantEntry {
     #[serde(skip)]
     #[diesel(select_expression = races::name)]
     race_name: String,
+    /// whether the participant is on the waitlist of a full race or start
+    #[diesel(select_expression = participants::waitlisted_at.is_not_null())]
+    waitlisted: bool,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -123,6 +129,7 @@ async fn render_registration_list(
     // * Participants relate to an competition through a chain of tables
     //  `participants` -> `categories` -> `starts` -> `races` ( -> `competitions`)
     //     + We need to join these tables in that order
+    //     + Bib numbers are optional, so `bib_numbers` needs to be left joined
     //     + Again we cant to order the result by category, racename, age, name
     //  * For `special_categories` we want to use the Associations API from diesel
     //     + Start with this if the other part work
@@ -0,0 +1,195 @@
+//! Render the published versions of the results of a race
+//!
+//! Each version after the first one comes with a changelog listing all
+//! participants whose place or time differs from the previous version
+use crate::app_state::{self, AppState};
+use crate::database::schema::{competitions, races};
+use crate::database::shared_models::{Competition, Race, ResultVersion};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::results::{RaceResults, ResultListData, ShownRaceResults, ShownRanking};
+use axum::extract::Path;
+use axum::response::Html;
+use axum::Router;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/races/{race_id}/result_versions.html",
+            axum::routing::get(list_versions),
+        )
+        .route(
+            "/races/{race_id}/result_versions/{version}/results.html",
+            axum::routing::get(render_version),
+        )
+}
+
+/// A participant with a different place or time than in the previous version
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ResultChange {
+    bib: Option<i32>,
+    first_name: String,
+    last_name: String,
+    /// `None` if the participant was not ranked in the previous version
+    previous_place: Option<usize>,
+    /// ranking time in the previous version in milliseconds
+    previous_time: Option<i64>,
+    /// `None` if the participant is no longer ranked
+    place: Option<usize>,
+    /// ranking time in milliseconds
+    time: Option<i64>,
+}
+
+/// Compare two serialized [`RaceResults`]
+///
+/// Changed participants are listed by their new place, participants that
+/// are no longer ranked come last. The results are read with
+/// [`ShownRanking::from_results`], as the previous version might have been
+/// published in an older format
+pub(crate) fn result_changes(
+    previous: &serde_json::Value,
+    current: &serde_json::Value,
+) -> Vec<ResultChange> {
+    let finishers = |results| {
+        ShownRanking::from_results(results)
+            .map(|ranking| ranking.participants)
+            .unwrap_or_default()
+    };
+    let mut previous = finishers(previous)
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect::<HashMap<_, _>>();
+
+    let mut changes = Vec::new();
+    for participant in finishers(current) {
+        let before = previous.remove(&participant.id);
+        if before
+            .as_ref()
+            .is_some_and(|b| b.place == participant.place && b.time == participant.time)
+        {
+            continue;
+        }
+        changes.push(ResultChange {
+            bib: participant.bib,
+            first_name: participant.first_name,
+            last_name: participant.last_name,
+            previous_place: before.as_ref().map(|b| b.place),
+            previous_time: before.as_ref().map(|b| b.time),
+            place: Some(participant.place),
+            time: Some(participant.time),
+        });
+    }
+    let mut removed = previous.into_values().collect::<Vec<_>>();
+    removed.sort_by_key(|p| p.place);
+    changes.extend(removed.into_iter().map(|participant| ResultChange {
+        bib: participant.bib,
+        first_name: participant.first_name,
+        last_name: participant.last_name,
+        previous_place: Some(participant.place),
+        previous_time: Some(participant.time),
+        place: None,
+        time: None,
+    }));
+    changes
+}
+
+/// The current ranking of a single race and the id of its competition
+pub(crate) fn live_results(
+    conn: &mut SqliteConnection,
+    race_id: Id,
+) -> QueryResult<(Id, RaceResults)> {
+    let competition_id = races::table
+        .find(race_id)
+        .select(races::competition_id)
+        .first::<Id>(conn)?;
+    let results = crate::results::load_results(conn, competition_id)?
+        .into_iter()
+        .find(|r| r.race.race.id == race_id)
+        // races without starts have no results
+        .ok_or(diesel::result::Error::NotFound)?;
+    Ok((competition_id, results))
+}
+
+/// A published version with its parsed changelog
+#[derive(Serialize)]
+pub(crate) struct VersionWithChanges {
+    #[serde(flatten)]
+    pub(crate) version: ResultVersion,
+    pub(crate) changes: Vec<ResultChange>,
+}
+
+impl VersionWithChanges {
+    pub(crate) fn parse(version: ResultVersion) -> serde_json::Result<Self> {
+        Ok(Self {
+            changes: serde_json::from_str(&version.changes)?,
+            version,
+        })
+    }
+}
+
+/// Data used to render the version list
+///
+/// See `templates/result_versions.html` for the relevant template
+#[derive(Serialize)]
+struct VersionListData {
+    race: Race,
+    /// all versions, the latest first
+    versions: Vec<VersionWithChanges>,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn list_versions(state: AppState, Path(race_id): Path<Id>) -> Result<Html<String>> {
+    let (race, versions) = state
+        .with_connection(move |conn| {
+            let race = races::table
+                .find(race_id)
+                .select(Race::as_select())
+                .first(conn)?;
+            let versions = crate::database::result_versions::load_versions(conn, race_id)?;
+            QueryResult::Ok((race, versions))
+        })
+        .await?;
+    let versions = versions
+        .into_iter()
+        .map(VersionWithChanges::parse)
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    state.render_template("result_versions.html", VersionListData { race, versions })
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_version(
+    state: AppState,
+    Path((race_id, version)): Path<(Id, i32)>,
+) -> Result<Html<String>> {
+    let (competition_info, shown, latest) = state
+        .with_connection(move |conn| {
+            let competition = races::table
+                .inner_join(competitions::table)
+                .filter(races::id.eq(race_id))
+                .select(Competition::as_select())
+                .first(conn)?;
+            let shown = crate::database::result_versions::load_version(conn, race_id, version)?;
+            let latest = crate::database::result_versions::latest_version(conn, race_id)?;
+            QueryResult::Ok((competition, shown, latest))
+        })
+        .await?;
+    let shown = shown.ok_or_else(|| {
+        Error::NotFound(format!(
+            "No version {version} of the results of race {race_id}"
+        ))
+    })?;
+    state.render_template(
+        "results.html",
+        ResultListData {
+            races: vec![ShownRaceResults {
+                results: serde_json::from_str(&shown.results)?,
+                superseded: latest.is_some_and(|latest| latest > shown.version),
+                version: Some(shown),
+            }],
+            competition_info,
+        },
+    )
+}
@@ -0,0 +1,1017 @@
+//! Render the result list of a specific competition grouped by races
+//!
+//! Participants are ranked overall, by gender and by category for each race.
+//! For races with intermediate timing points each segment is ranked as well.
+//! Relay teams are ranked separately by the sum of their leg times.
+//! Races with published results show the latest published version instead
+//! of the live ranking.
+//! Clients can subscribe to a stream of server-sent events to get notified
+//! about changed results
+use crate::app_state::{self, AppState};
+use crate::database::schema::{
+    bib_numbers, categories, competitions, participants, races, split_times, start_reads, starts,
+    team_categories, team_members, teams, time_records, timing_points,
+};
+use crate::database::shared_models::{
+    Competition, ParticipantStatus, Race, RaceWithTimingPoints, RankingMode, ResultVersion,
+};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::Path;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::Html;
+use axum::Router;
+use diesel::dsl;
+use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use time::PrimitiveDateTime;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/{event_id}/results.html",
+            axum::routing::get(render_results),
+        )
+        .route(
+            "/{event_id}/results/stream",
+            axum::routing::get(stream_results),
+        )
+}
+
+/// Data for a participant with a finish time
+#[derive(Queryable, Selectable, Debug, Serialize)]
+#[diesel(table_name = participants)]
+#[diesel(check_for_backend(Sqlite))]
+pub(crate) struct ResultEntry {
+    /// id of the participant
+    pub(crate) id: Id,
+    /// bib number of the participant
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    /// first name of the participant
+    pub(crate) first_name: String,
+    /// last name of the participant
+    pub(crate) last_name: String,
+    /// club of the participant
+    pub(crate) club: Option<String>,
+    /// person the participant belongs to, used to link the history
+    person_id: Option<Id>,
+    /// birth year of the participant
+    pub(crate) birth_year: i32,
+    /// id of the category of the participant
+    #[diesel(select_expression = categories::id)]
+    pub(crate) category_id: Id,
+    /// label of the category of the participant
+    #[diesel(select_expression = categories::label)]
+    pub(crate) category: String,
+    /// whether the category of the participant is a male category
+    #[diesel(select_expression = categories::male)]
+    pub(crate) male: bool,
+    /// id of the race the participant participates in
+    #[serde(skip)]
+    #[diesel(select_expression = races::id)]
+    race_id: Id,
+    /// scheduled time of the start of the participant
+    #[serde(skip)]
+    #[diesel(select_expression = starts::time)]
+    start_time: PrimitiveDateTime,
+    /// actual time of the start signal, if recorded
+    #[serde(skip)]
+    #[diesel(select_expression = starts::gun_time)]
+    gun_time: Option<PrimitiveDateTime>,
+    /// time the participant crossed the start mat, if recorded
+    #[serde(skip)]
+    #[diesel(select_expression = start_reads::time.nullable())]
+    start_read: Option<PrimitiveDateTime>,
+    /// finish time of the participant
+    ///
+    /// This is the recorded finish time until the time adjustments are
+    /// applied in [`load_results`]
+    #[serde(skip)]
+    #[diesel(select_expression = time_records::finish_time)]
+    finish_time: PrimitiveDateTime,
+}
+
+impl ResultEntry {
+    /// The start signal, falls back to the scheduled start time
+    fn gun_start(&self) -> PrimitiveDateTime {
+        self.gun_time.unwrap_or(self.start_time)
+    }
+
+    /// The individual start of the participant, falls back to the start signal
+    fn net_start(&self) -> PrimitiveDateTime {
+        self.start_read.unwrap_or_else(|| self.gun_start())
+    }
+}
+
+/// Data for a participant that is excluded from the ranking
+#[derive(Queryable, Selectable, Debug, Serialize)]
+#[diesel(table_name = participants)]
+#[diesel(check_for_backend(Sqlite))]
+pub(crate) struct NonFinisherEntry {
+    /// id of the participant
+    id: Id,
+    /// bib number of the participant
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    /// first name of the participant
+    first_name: String,
+    /// last name of the participant
+    last_name: String,
+    /// club of the participant
+    club: Option<String>,
+    /// birth year of the participant
+    birth_year: i32,
+    /// label of the category of the participant
+    #[diesel(select_expression = categories::label)]
+    category: String,
+    /// why the participant is not ranked
+    status: ParticipantStatus,
+    /// optional explanation for the status
+    status_reason: Option<String>,
+    /// id of the race the participant participates in
+    #[serde(skip)]
+    #[diesel(select_expression = races::id)]
+    race_id: Id,
+}
+
+/// Time of a participant at a timing point or at the finish
+#[derive(Debug, Serialize, Default)]
+pub(crate) struct SplitResult {
+    /// time since the start in milliseconds, if the participant was recorded
+    elapsed: Option<i64>,
+    /// time since the previous timing point in milliseconds
+    ///
+    /// This is only set if both timing points were recorded
+    segment: Option<i64>,
+    /// place within all participants with a time for this segment
+    segment_place: Option<usize>,
+}
+
+/// Recorded split times, keyed by participant id and timing point id
+type SplitTimes = HashMap<(Id, Id), PrimitiveDateTime>;
+
+/// A participant with the computed time and places
+#[derive(Debug, Serialize)]
+pub(crate) struct RankedEntry {
+    #[serde(flatten)]
+    pub(crate) participant: ResultEntry,
+    /// time used for the ranking in milliseconds
+    pub(crate) time: i64,
+    /// time since the start signal in milliseconds
+    pub(crate) gun_time: i64,
+    /// time since the participant crossed the start line in milliseconds
+    pub(crate) net_time: i64,
+    /// overall place in the race
+    pub(crate) place: usize,
+    /// place within all participants of the same gender in the race
+    pub(crate) gender_place: usize,
+    /// place within the category
+    pub(crate) category_place: usize,
+    /// gap to the winner of the race in milliseconds
+    gap: i64,
+    /// gap to the winner of the category in milliseconds
+    category_gap: i64,
+    /// one entry per timing point of the race, in the order of the timing points
+    splits: Vec<SplitResult>,
+    /// segment from the last timing point to the finish
+    last_segment: SplitResult,
+    /// ranking time multiplied by the age factor in milliseconds
+    ///
+    /// This is only set if the distance of the race is known
+    age_graded_time: Option<i64>,
+    /// age grade in percent
+    age_grade: Option<f64>,
+    /// place within the race ranked by age grade
+    age_grade_place: Option<usize>,
+    /// average time per kilometer in milliseconds
+    pace: Option<i64>,
+    /// average speed in km/h
+    speed: Option<f64>,
+    /// sum of all time adjustments of the participant in milliseconds
+    ///
+    /// The times above already contain the adjustment
+    time_adjustment: Option<i64>,
+}
+
+impl RankedEntry {
+    /// The split result of the given segment, the segment after the last
+    /// timing point is the one up to the finish
+    fn segment_mut(&mut self, segment: usize) -> &mut SplitResult {
+        if segment < self.splits.len() {
+            &mut self.splits[segment]
+        } else {
+            &mut self.last_segment
+        }
+    }
+}
+
+/// A member of a relay team with the recorded times
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = team_members)]
+#[diesel(check_for_backend(Sqlite))]
+struct TeamMemberEntry {
+    team_id: Id,
+    participant_id: Id,
+    /// position of the member within the team
+    leg: i32,
+    #[diesel(select_expression = participants::first_name)]
+    first_name: String,
+    #[diesel(select_expression = participants::last_name)]
+    last_name: String,
+    #[diesel(select_expression = participants::club)]
+    club: Option<String>,
+    #[diesel(select_expression = bib_numbers::bib.nullable())]
+    bib: Option<i32>,
+    #[diesel(select_expression = participants::status)]
+    status: ParticipantStatus,
+    #[diesel(select_expression = starts::time)]
+    start_time: PrimitiveDateTime,
+    #[diesel(select_expression = starts::gun_time)]
+    gun_time: Option<PrimitiveDateTime>,
+    #[diesel(select_expression = start_reads::time.nullable())]
+    start_read: Option<PrimitiveDateTime>,
+    #[diesel(select_expression = time_records::finish_time.nullable())]
+    finish_time: Option<PrimitiveDateTime>,
+}
+
+impl TeamMemberEntry {
+    /// The finish time, unless the member is excluded from the ranking
+    fn ranked_finish(&self) -> Option<PrimitiveDateTime> {
+        self.finish_time
+            .filter(|_| !ParticipantStatus::NON_FINISHERS.contains(&self.status))
+    }
+
+    /// The start of the first leg according to the ranking mode of the race
+    fn race_start(&self, ranking_mode: RankingMode) -> PrimitiveDateTime {
+        let gun_start = self.gun_time.unwrap_or(self.start_time);
+        match ranking_mode {
+            RankingMode::Gun => gun_start,
+            RankingMode::Net => self.start_read.unwrap_or(gun_start),
+        }
+    }
+}
+
+/// A member of a relay team with the time of their leg
+#[derive(Debug, Serialize)]
+pub(crate) struct TeamLeg {
+    /// position of the member within the team
+    leg: i32,
+    first_name: String,
+    last_name: String,
+    status: ParticipantStatus,
+    /// time of this leg in milliseconds, if the member and
+    /// the previous member finished
+    time: Option<i64>,
+}
+
+/// A relay team with its legs and places
+#[derive(Debug, Serialize)]
+pub(crate) struct TeamResult {
+    name: String,
+    /// bib number of the team, that's the bib of the first leg
+    bib: Option<i32>,
+    /// club of the first leg
+    club: Option<String>,
+    team_category_id: Id,
+    team_category: String,
+    /// all members ordered by their leg
+    legs: Vec<TeamLeg>,
+    /// sum of all leg times in milliseconds, only set if every leg finished
+    time: Option<i64>,
+    /// overall place of the team in the race
+    place: Option<usize>,
+    /// place within the team category
+    category_place: Option<usize>,
+    /// gap to the winning team in milliseconds
+    gap: Option<i64>,
+}
+
+/// A category with at least one finisher
+#[derive(Debug, Serialize)]
+pub(crate) struct CategoryInfo {
+    pub(crate) id: Id,
+    pub(crate) label: String,
+}
+
+/// Ranked results for a single race
+#[derive(Debug, Serialize)]
+pub(crate) struct RaceResults {
+    pub(crate) race: RaceWithTimingPoints,
+    /// all categories with finishers, ordered as they are defined
+    pub(crate) categories: Vec<CategoryInfo>,
+    /// all finishers ordered by their overall place
+    pub(crate) participants: Vec<RankedEntry>,
+    /// participants that did not finish, did not start or are disqualified
+    pub(crate) non_finishers: Vec<NonFinisherEntry>,
+    /// whether gun and net time differ for any participant
+    pub(crate) has_net_times: bool,
+    /// whether the finishers have an age grade
+    pub(crate) has_age_grading: bool,
+    /// source of the age grading factors, set if there are age grades
+    pub(crate) age_grading_source: Option<String>,
+    /// team categories with classified teams, ordered as they are defined
+    pub(crate) team_categories: Vec<CategoryInfo>,
+    /// relay teams ordered by their place, teams with missing legs come last
+    pub(crate) teams: Vec<TeamResult>,
+}
+
+/// Results of a race as shown on the result list
+#[derive(Serialize)]
+pub(crate) struct ShownRaceResults {
+    /// the serialized [`RaceResults`], either live or from a published version
+    #[serde(flatten)]
+    pub(crate) results: serde_json::Value,
+    /// the published version, `None` for provisional results
+    pub(crate) version: Option<ResultVersion>,
+    /// whether a newer version than the shown one exists
+    pub(crate) superseded: bool,
+}
+
+/// Data used to render the result list
+///
+/// See `templates/results.html` for the relevant template
+#[derive(Serialize)]
+pub(crate) struct ResultListData {
+    /// race specific results
+    pub(crate) races: Vec<ShownRaceResults>,
+    /// general information about the competition
+    pub(crate) competition_info: Competition,
+}
+
+/// The time between two timestamps in milliseconds
+pub(crate) fn elapsed_time(start: PrimitiveDateTime, finish: PrimitiveDateTime) -> i64 {
+    milliseconds(finish - start)
+}
+
+/// A duration in whole milliseconds, saturating at the limits of `i64`
+pub(crate) fn milliseconds(duration: time::Duration) -> i64 {
+    i64::try_from(duration.whole_milliseconds()).unwrap_or(if duration.is_negative() {
+        i64::MIN
+    } else {
+        i64::MAX
+    })
+}
+
+/// Compute places for a list of times sorted in ascending order
+///
+/// Equal times share the same place, the following place is skipped
+/// accordingly (1, 2, 2, 4)
+pub(crate) fn places(sorted_times: impl IntoIterator<Item = i64>) -> Vec<usize> {
+    let mut last_time = None;
+    let mut last_place = 0;
+    sorted_times
+        .into_iter()
+        .enumerate()
+        .map(|(idx, time)| {
+            if last_time != Some(time) {
+                last_place = idx + 1;
+                last_time = Some(time);
+            }
+            last_place
+        })
+        .collect()
+}
+
+/// Compute places for the subset of `entries` selected by `group`
+///
+/// `entries` need to be sorted by time. The returned vector contains
+/// a place for each entry in `entries`, as well as the time of the group leader
+fn places_by_group<K: std::hash::Hash + Eq>(
+    entries: &[(ResultEntry, i64)],
+    group: impl Fn(&ResultEntry) -> K,
+) -> Vec<(usize, i64)> {
+    let mut groups = HashMap::<K, Vec<usize>>::new();
+    for (idx, (entry, _)) in entries.iter().enumerate() {
+        groups.entry(group(entry)).or_default().push(idx);
+    }
+    let mut out = vec![(0, 0); entries.len()];
+    for indices in groups.into_values() {
+        let leader_time = entries[indices[0]].1;
+        let group_places = places(indices.iter().map(|idx| entries[*idx].1));
+        for (idx, place) in indices.into_iter().zip(group_places) {
+            out[idx] = (place, leader_time);
+        }
+    }
+    out
+}
+
+/// Compute split results for a single participant
+///
+/// Returns one entry per timing point and the last segment up to the finish.
+/// Segment places are filled in by `rank_segments`
+fn split_results(
+    entry: &ResultEntry,
+    race: &RaceWithTimingPoints,
+    split_times: &SplitTimes,
+) -> (Vec<SplitResult>, SplitResult) {
+    let start = entry.net_start();
+    let mut previous = Some(start);
+    let splits = race
+        .timing_points
+        .iter()
+        .map(|point| {
+            let time = split_times.get(&(entry.id, point.id)).copied();
+            let result = SplitResult {
+                elapsed: time.map(|t| elapsed_time(start, t)),
+                segment: previous.zip(time).map(|(p, t)| elapsed_time(p, t)),
+                segment_place: None,
+            };
+            previous = time;
+            result
+        })
+        .collect();
+    let last_segment = SplitResult {
+        elapsed: Some(elapsed_time(start, entry.finish_time)),
+        segment: previous.map(|p| elapsed_time(p, entry.finish_time)),
+        segment_place: None,
+    };
+    (splits, last_segment)
+}
+
+/// Rank each segment across all participants that have a time for it
+fn rank_segments(participants: &mut [RankedEntry], segment_count: usize) {
+    for segment in 0..=segment_count {
+        let mut times = participants
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(idx, p)| p.segment_mut(segment).segment.map(|time| (idx, time)))
+            .collect::<Vec<_>>();
+        times.sort_by_key(|(_, time)| *time);
+        let segment_places = places(times.iter().map(|(_, time)| *time));
+        for ((idx, _), place) in times.into_iter().zip(segment_places) {
+            participants[idx].segment_mut(segment).segment_place = Some(place);
+        }
+    }
+}
+
+/// Average pace in milliseconds per kilometer and speed in km/h
+fn pace_and_speed(distance_meters: i32, time: i64) -> Option<(i64, f64)> {
+    if distance_meters <= 0 || time <= 0 {
+        return None;
+    }
+    let kilometers = f64::from(distance_meters) / 1000.0;
+    let pace = (time as f64 / kilometers).round() as i64;
+    let speed = kilometers / (time as f64 / 3_600_000.0);
+    Some((pace, speed))
+}
+
+/// Rank all finishers with an age grade, the highest percentage wins
+fn rank_age_grades(participants: &mut [RankedEntry]) {
+    // compare with a precision of 0.01 percent, so equal grades share a place
+    let mut grades = participants
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, p)| {
+            p.age_grade
+                .map(|grade| (idx, -(grade * 100.0).round() as i64))
+        })
+        .collect::<Vec<_>>();
+    grades.sort_by_key(|(_, grade)| *grade);
+    let grade_places = places(grades.iter().map(|(_, grade)| *grade));
+    for ((idx, _), place) in grades.into_iter().zip(grade_places) {
+        participants[idx].age_grade_place = Some(place);
+    }
+}
+
+/// Compute the leg times of a team and rank all teams of a race
+///
+/// The first leg is measured from the start, every further leg from the
+/// finish of the previous leg
+fn rank_teams(
+    ranking_mode: RankingMode,
+    teams: Vec<(Id, String, Id, String)>,
+    members_per_team: &mut HashMap<Id, Vec<TeamMemberEntry>>,
+) -> (Vec<CategoryInfo>, Vec<TeamResult>) {
+    let mut teams = teams
+        .into_iter()
+        .map(|(id, name, team_category_id, team_category)| {
+            let members = members_per_team.remove(&id).unwrap_or_default();
+            let bib = members.first().and_then(|m| m.bib);
+            let club = members.first().and_then(|m| m.club.clone());
+            let mut previous = members.first().map(|m| m.race_start(ranking_mode));
+            let legs = members
+                .into_iter()
+                .map(|member| {
+                    let finish = member.ranked_finish();
+                    let time = previous.zip(finish).map(|(p, f)| elapsed_time(p, f));
+                    previous = finish;
+                    TeamLeg {
+                        leg: member.leg,
+                        first_name: member.first_name,
+                        last_name: member.last_name,
+                        status: member.status,
+                        time,
+                    }
+                })
+                .collect::<Vec<_>>();
+            let time = if legs.is_empty() {
+                None
+            } else {
+                legs.iter().map(|l| l.time).sum::<Option<i64>>()
+            };
+            TeamResult {
+                name,
+                bib,
+                club,
+                team_category_id,
+                team_category,
+                legs,
+                time,
+                place: None,
+                category_place: None,
+                gap: None,
+            }
+        })
+        .collect::<Vec<_>>();
+    // classified teams first, then by time and name
+    teams.sort_by(|a, b| {
+        a.time
+            .is_none()
+            .cmp(&b.time.is_none())
+            .then_with(|| a.time.cmp(&b.time))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let classified = teams.iter().filter(|t| t.time.is_some()).count();
+    let overall = places(teams.iter().filter_map(|t| t.time));
+    let leader_time = teams.first().and_then(|t| t.time).unwrap_or_default();
+    let mut category_times = HashMap::<Id, Vec<usize>>::new();
+    for (idx, team) in teams[..classified].iter().enumerate() {
+        category_times
+            .entry(team.team_category_id)
+            .or_default()
+            .push(idx);
+    }
+    for indices in category_times.values() {
+        let category_places = places(indices.iter().filter_map(|idx| teams[*idx].time));
+        for (idx, place) in indices.iter().zip(category_places) {
+            teams[*idx].category_place = Some(place);
+        }
+    }
+    for (team, place) in teams.iter_mut().zip(overall) {
+        team.place = Some(place);
+        team.gap = team.time.map(|time| time - leader_time);
+    }
+
+    let mut categories = teams[..classified]
+        .iter()
+        .map(|t| (t.team_category_id, t.team_category.clone()))
+        .collect::<HashMap<_, _>>()
+        .into_iter()
+        .map(|(id, label)| CategoryInfo { id, label })
+        .collect::<Vec<_>>();
+    categories.sort_by_key(|c| c.id);
+    (categories, teams)
+}
+
+/// Rank all finishers of a single race
+fn rank_race(
+    race: RaceWithTimingPoints,
+    entries: Vec<ResultEntry>,
+    non_finishers: Vec<NonFinisherEntry>,
+    split_times: &SplitTimes,
+    time_adjustments: &HashMap<Id, i64>,
+    (team_categories, teams): (Vec<CategoryInfo>, Vec<TeamResult>),
+) -> RaceResults {
+    let ranking_mode = race.race.ranking_mode;
+    let mut entries = entries
+        .into_iter()
+        .map(|e| {
+            let time = match ranking_mode {
+                RankingMode::Gun => elapsed_time(e.gun_start(), e.finish_time),
+                RankingMode::Net => elapsed_time(e.net_start(), e.finish_time),
+            };
+            (e, time)
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a, a_time), (b, b_time)| {
+        a_time
+            .cmp(b_time)
+            .then_with(|| a.last_name.cmp(&b.last_name))
+            .then_with(|| a.first_name.cmp(&b.first_name))
+    });
+
+    let overall = places(entries.iter().map(|(_, time)| *time));
+    let by_gender = places_by_group(&entries, |e| e.male);
+    let by_category = places_by_group(&entries, |e| e.category_id);
+    let leader_time = entries.first().map(|(_, time)| *time).unwrap_or_default();
+
+    let mut categories = entries
+        .iter()
+        .map(|(e, _)| (e.category_id, e.category.clone()))
+        .collect::<HashMap<_, _>>()
+        .into_iter()
+        .map(|(id, label)| CategoryInfo { id, label })
+        .collect::<Vec<_>>();
+    categories.sort_by_key(|c| c.id);
+
+    let mut participants = entries
+        .into_iter()
+        .zip(overall)
+        .zip(by_gender)
+        .zip(by_category)
+        .map(
+            |(
+                (((participant, time), place), (gender_place, _)),
+                (category_place, category_leader_time),
+            )| {
+                let (splits, last_segment) = split_results(&participant, &race, split_times);
+                let age = participant.finish_time.year() - participant.birth_year;
+                let age_grade = race.race.distance_meters.and_then(|distance| {
+                    crate::age_grading::age_grade(distance, participant.male, age, time)
+                });
+                let pace_and_speed = race
+                    .race
+                    .distance_meters
+                    .and_then(|distance| pace_and_speed(distance, time));
+                RankedEntry {
+                    gun_time: elapsed_time(participant.gun_start(), participant.finish_time),
+                    net_time: elapsed_time(participant.net_start(), participant.finish_time),
+                    time_adjustment: time_adjustments.get(&participant.id).copied(),
+                    participant,
+                    time,
+                    place,
+                    gender_place,
+                    category_place,
+                    gap: time - leader_time,
+                    category_gap: time - category_leader_time,
+                    splits,
+                    last_segment,
+                    age_graded_time: age_grade.map(|grade| grade.time),
+                    age_grade: age_grade.map(|grade| grade.percentage),
+                    age_grade_place: None,
+                    pace: pace_and_speed.map(|(pace, _)| pace),
+                    speed: pace_and_speed.map(|(_, speed)| speed),
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+    if !race.timing_points.is_empty() {
+        rank_segments(&mut participants, race.timing_points.len());
+    }
+    let has_net_times = participants.iter().any(|p| p.gun_time != p.net_time);
+    rank_age_grades(&mut participants);
+    let has_age_grading = participants.iter().any(|p| p.age_grade.is_some());
+    let age_grading_source = has_age_grading.then(|| crate::age_grading::source().to_owned());
+
+    RaceResults {
+        race,
+        categories,
+        participants,
+        non_finishers,
+        has_net_times,
+        has_age_grading,
+        age_grading_source,
+        team_categories,
+        teams,
+    }
+}
+
+/// Load and rank the results of all races of a competition
+///
+/// This walks the same `races` -> `starts` -> `categories` -> `participants`
+/// chain as the registration list, but only ranks participants with a
+/// recorded finish time. Participants that did not finish, did not start or are
+/// disqualified are listed separately. Members of relay teams are only
+/// ranked as part of their team. Time adjustments are added to the recorded
+/// finish times before ranking
+pub(crate) fn load_results(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<Vec<RaceResults>> {
+    let races = races::table
+        .inner_join(starts::table)
+        .filter(races::competition_id.eq(competition_id))
+        .group_by(races::id)
+        .order_by((dsl::min(starts::time), races::id))
+        .select(Race::as_select())
+        .load::<Race>(conn)?;
+    let races = RaceWithTimingPoints::load(conn, races)?;
+
+    let time_adjustments =
+        crate::database::time_records::summed_time_adjustments(conn, competition_id)?;
+    let adjust = |participant_id: Id, finish_time: PrimitiveDateTime| {
+        time_adjustments
+            .get(&participant_id)
+            .map_or(finish_time, |delta| {
+                finish_time + time::Duration::milliseconds(*delta)
+            })
+    };
+
+    let mut entries = participants::table
+        .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+        .inner_join(time_records::table)
+        .left_join(bib_numbers::table)
+        .left_join(start_reads::table)
+        .left_join(team_members::table)
+        .filter(races::competition_id.eq(competition_id))
+        .filter(team_members::team_id.is_null())
+        .filter(participants::status.ne_all(ParticipantStatus::NON_FINISHERS))
+        .select(ResultEntry::as_select())
+        .load::<ResultEntry>(conn)?;
+    for entry in &mut entries {
+        entry.finish_time = adjust(entry.id, entry.finish_time);
+    }
+
+    let non_finishers = participants::table
+        .inner_join(categories::table.inner_join(starts::table.inner_join(races::table)))
+        .left_join(bib_numbers::table)
+        .left_join(team_members::table)
+        .filter(races::competition_id.eq(competition_id))
+        .filter(team_members::team_id.is_null())
+        .filter(participants::status.eq_any(ParticipantStatus::NON_FINISHERS))
+        .order_by((
+            participants::status,
+            participants::last_name,
+            participants::first_name,
+        ))
+        .select(NonFinisherEntry::as_select())
+        .load::<NonFinisherEntry>(conn)?;
+
+    let split_times = split_times::table
+        .inner_join(timing_points::table.inner_join(races::table))
+        .filter(races::competition_id.eq(competition_id))
+        .select((
+            split_times::participant_id,
+            split_times::timing_point_id,
+            split_times::time,
+        ))
+        .load::<(Id, Id, PrimitiveDateTime)>(conn)?
+        .into_iter()
+        .map(|(participant_id, timing_point_id, time)| ((participant_id, timing_point_id), time))
+        .collect::<SplitTimes>();
+
+    let teams = teams::table
+        .inner_join(team_categories::table)
+        .inner_join(races::table)
+        .filter(races::competition_id.eq(competition_id))
+        .select((
+            teams::race_id,
+            teams::id,
+            teams::name,
+            team_categories::id,
+            team_categories::label,
+        ))
+        .load::<(Id, Id, String, Id, String)>(conn)?;
+    let mut members = team_members::table
+        .inner_join(teams::table.inner_join(races::table))
+        .inner_join(
+            participants::table
+                .inner_join(categories::table.inner_join(starts::table))
+                .left_join(bib_numbers::table)
+                .left_join(start_reads::table)
+                .left_join(time_records::table),
+        )
+        .filter(races::competition_id.eq(competition_id))
+        .order_by(team_members::leg)
+        .select(TeamMemberEntry::as_select())
+        .load::<TeamMemberEntry>(conn)?;
+    for member in &mut members {
+        member.finish_time = member
+            .finish_time
+            .map(|finish_time| adjust(member.participant_id, finish_time));
+    }
+
+    let mut teams_per_race = HashMap::<Id, Vec<_>>::new();
+    for (race_id, id, name, team_category_id, team_category) in teams {
+        teams_per_race.entry(race_id).or_default().push((
+            id,
+            name,
+            team_category_id,
+            team_category,
+        ));
+    }
+    let mut members_per_team = HashMap::<Id, Vec<TeamMemberEntry>>::new();
+    for member in members {
+        members_per_team
+            .entry(member.team_id)
+            .or_default()
+            .push(member);
+    }
+
+    let mut entries_per_race = HashMap::<Id, Vec<ResultEntry>>::new();
+    for entry in entries {
+        entries_per_race
+            .entry(entry.race_id)
+            .or_default()
+            .push(entry);
+    }
+    let mut non_finishers_per_race = HashMap::<Id, Vec<NonFinisherEntry>>::new();
+    for entry in non_finishers {
+        non_finishers_per_race
+            .entry(entry.race_id)
+            .or_default()
+            .push(entry);
+    }
+
+    Ok(races
+        .into_iter()
+        .map(|race| {
+            let entries = entries_per_race.remove(&race.race.id).unwrap_or_default();
+            let non_finishers = non_finishers_per_race
+                .remove(&race.race.id)
+                .unwrap_or_default();
+            let teams = teams_per_race.remove(&race.race.id).unwrap_or_default();
+            let teams = rank_teams(race.race.ranking_mode, teams, &mut members_per_team);
+            rank_race(
+                race,
+                entries,
+                non_finishers,
+                &split_times,
+                &time_adjustments,
+                teams,
+            )
+        })
+        .collect())
+}
+
+pub(crate) fn json_error(e: serde_json::Error) -> diesel::result::Error {
+    diesel::result::Error::DeserializationError(Box::new(e))
+}
+
+/// Load the shown results of all races of a competition
+///
+/// Races with published results are represented by their latest published
+/// version, all other races by their live ranking
+pub(crate) fn load_shown_results(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<Vec<ShownRaceResults>> {
+    let races = load_results(conn, competition_id)?;
+    let mut versions = crate::database::result_versions::latest_versions(conn, competition_id)?;
+    races
+        .into_iter()
+        .map(|race| {
+            Ok(match versions.remove(&race.race.race.id) {
+                Some(version) => ShownRaceResults {
+                    results: serde_json::from_str(&version.results).map_err(json_error)?,
+                    version: Some(version),
+                    superseded: false,
+                },
+                None => ShownRaceResults {
+                    results: serde_json::to_value(race).map_err(json_error)?,
+                    version: None,
+                    superseded: false,
+                },
+            })
+        })
+        .collect()
+}
+
+/// The part of the shown results of a race needed by the club results,
+/// the series standings, the awards, the history of a person and the
+/// changelog of published versions
+#[derive(Debug)]
+pub(crate) struct ShownRanking {
+    pub(crate) race: ShownRace,
+    /// all finishers ordered by their overall place
+    pub(crate) participants: Vec<ShownEntry>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ShownRace {
+    pub(crate) id: Id,
+    pub(crate) name: String,
+}
+
+/// A ranked finisher of the shown results
+#[derive(Debug)]
+pub(crate) struct ShownEntry {
+    /// id of the participant
+    pub(crate) id: Id,
+    pub(crate) bib: Option<i32>,
+    pub(crate) first_name: String,
+    pub(crate) last_name: String,
+    pub(crate) club: Option<String>,
+    pub(crate) birth_year: i32,
+    /// id of the category of the participant
+    pub(crate) category_id: Option<Id>,
+    /// label of the category of the participant
+    pub(crate) category: String,
+    /// time used for the ranking in milliseconds
+    pub(crate) time: i64,
+    /// overall place in the race
+    pub(crate) place: usize,
+    /// place within the category
+    pub(crate) category_place: usize,
+}
+
+/// A field of a JSON object, `None` if it is missing or has another type
+fn json_field<T: serde::de::DeserializeOwned>(value: &serde_json::Value, key: &str) -> Option<T> {
+    value.get(key).and_then(|v| T::deserialize(v).ok())
+}
+
+impl ShownRanking {
+    /// Read the ranking from the serialized results of a race
+    ///
+    /// Published versions keep the format of [`RaceResults`] at the time
+    /// they were published, so they are read field by field instead of into
+    /// a struct that follows the current format. Missing optional fields
+    /// are left empty, finishers without id, time, places or birth year are
+    /// skipped. Returns `None` if the race has no id
+    pub(crate) fn from_results(results: &serde_json::Value) -> Option<Self> {
+        let race = results.get("race")?;
+        let race = ShownRace {
+            id: json_field(race, "id")?,
+            name: json_field(race, "name").unwrap_or_default(),
+        };
+        let participants = results
+            .get("participants")
+            .and_then(|p| p.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|p| {
+                Some(ShownEntry {
+                    id: json_field(p, "id")?,
+                    bib: json_field(p, "bib"),
+                    first_name: json_field(p, "first_name").unwrap_or_default(),
+                    last_name: json_field(p, "last_name").unwrap_or_default(),
+                    club: json_field(p, "club"),
+                    birth_year: json_field(p, "birth_year")?,
+                    category_id: json_field(p, "category_id"),
+                    category: json_field(p, "category").unwrap_or_default(),
+                    time: json_field(p, "time")?,
+                    place: json_field(p, "place")?,
+                    category_place: json_field(p, "category_place")?,
+                })
+            })
+            .collect();
+        Some(Self { race, participants })
+    }
+}
+
+/// Load the rankings of all races of a competition as shown on the result list
+///
+/// See [`load_shown_results`] and [`ShownRanking::from_results`]
+pub(crate) fn load_shown_rankings(
+    conn: &mut SqliteConnection,
+    competition_id: Id,
+) -> QueryResult<Vec<ShownRanking>> {
+    Ok(load_shown_results(conn, competition_id)?
+        .iter()
+        .filter_map(|race| ShownRanking::from_results(&race.results))
+        .collect())
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_results(state: AppState, Path(competition_id): Path<Id>) -> Result<Html<String>> {
+    let (competition_info, races) = state
+        .with_connection(move |conn| {
+            let competition = competitions::table
+                .find(competition_id)
+                .select(Competition::as_select())
+                .first(conn)
+                .optional()?;
+            let races = load_shown_results(conn, competition_id)?;
+            QueryResult::Ok((competition, races))
+        })
+        .await?;
+    let competition_info = competition_info
+        .ok_or_else(|| Error::NotFound(format!("No competition for id {competition_id} found")))?;
+
+    state.render_template(
+        "results.html",
+        ResultListData {
+            races,
+            competition_info,
+        },
+    )
+}
+
+/// Send a `results` event whenever the results of the competition change
+#[axum::debug_handler(state = app_state::State)]
+async fn stream_results(
+    state: AppState,
+    Path(competition_id): Path<Id>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    // subscribe before checking the competition to not miss any update
+    let updates = state.subscribe_results();
+    let competition_exists = state
+        .with_connection(move |conn| {
+            diesel::select(dsl::exists(competitions::table.find(competition_id)))
+                .get_result::<bool>(conn)
+        })
+        .await?;
+    if !competition_exists {
+        return Err(Error::NotFound(format!(
+            "No competition for id {competition_id} found"
+        )));
+    }
+
+    let events = BroadcastStream::new(updates).filter_map(move |update| match update {
+        Ok(id) if id != competition_id => None,
+        // a lagging receiver missed some updates, so the client should reload as well
+        Ok(_) | Err(BroadcastStreamRecvError::Lagged(_)) => Some(Ok(Event::default()
+            .event("results")
+            .data(competition_id.to_string()))),
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,200 @@
+//! Render the standings of a series of competitions
+//!
+//! Participants of all competitions of a series are matched to persons
+//! (see `database::persons`). Each person gets points for their category
+//! place in every competition, only the best results count for the total.
+//! Races with published results count with their latest published version.
+use crate::app_state::{self, AppState};
+use crate::database::schema::{competitions, participants, series, series_competitions};
+use crate::database::shared_models::{Competition, Series};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use crate::results::places;
+use axum::extract::Path;
+use axum::response::Html;
+use axum::Router;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new().route(
+        "/series/{series_id}/standings.html",
+        axum::routing::get(render_standings),
+    )
+}
+
+/// Points of a person in a single competition of the series
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+struct CompetitionPoints {
+    /// points for the category place, if the person finished
+    points: Option<i32>,
+    /// whether the points count for the total
+    counted: bool,
+}
+
+/// A person in the series standings
+#[derive(Debug, Serialize)]
+struct SeriesStanding {
+    first_name: String,
+    last_name: String,
+    birth_year: i32,
+    club: Option<String>,
+    /// one entry per competition of the series
+    results: Vec<CompetitionPoints>,
+    /// sum of the best results
+    total: i32,
+    place: usize,
+    /// category in the latest competition of the person
+    #[serde(skip)]
+    category: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CategoryStandings {
+    category: String,
+    /// ordered by place
+    standings: Vec<SeriesStanding>,
+}
+
+/// Data used to render the series standings
+///
+/// See `templates/series_standings.html` for the relevant template
+#[derive(Serialize)]
+struct SeriesStandingsData {
+    series: Series,
+    /// competitions of the series ordered by date
+    competitions: Vec<Competition>,
+    categories: Vec<CategoryStandings>,
+}
+
+/// Mark the best results of a person as counted and compute the total
+fn count_best_results(standing: &mut SeriesStanding, counting_results: usize) {
+    let mut best = standing
+        .results
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, r)| r.points.map(|points| (idx, points)))
+        .collect::<Vec<_>>();
+    // earlier competitions win on equal points
+    best.sort_by(|(a_idx, a), (b_idx, b)| b.cmp(a).then_with(|| a_idx.cmp(b_idx)));
+    standing.total = 0;
+    for (idx, points) in best.into_iter().take(counting_results) {
+        standing.results[idx].counted = true;
+        standing.total += points;
+    }
+}
+
+/// Compute the standings of a series grouped by category
+fn load_standings(
+    conn: &mut SqliteConnection,
+    series: &Series,
+    competitions: &[Competition],
+) -> QueryResult<Vec<CategoryStandings>> {
+    let points = series.points();
+
+    let mut by_person = HashMap::<Id, SeriesStanding>::new();
+    for (idx, competition) in competitions.iter().enumerate() {
+        let results = crate::results::load_shown_rankings(conn, competition.id)?;
+        let participant_ids = results
+            .iter()
+            .flat_map(|race| race.participants.iter().map(|p| p.id))
+            .collect::<Vec<_>>();
+        let persons = participants::table
+            .filter(participants::id.eq_any(participant_ids))
+            .filter(participants::person_id.is_not_null())
+            .select((participants::id, participants::person_id.assume_not_null()))
+            .load::<(Id, Id)>(conn)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        for participant in results.into_iter().flat_map(|race| race.participants) {
+            let Some(person_id) = persons.get(&participant.id) else {
+                continue;
+            };
+            let place_points = points
+                .get(participant.category_place - 1)
+                .copied()
+                .unwrap_or_default();
+            let standing = by_person
+                .entry(*person_id)
+                .or_insert_with(|| SeriesStanding {
+                    first_name: String::new(),
+                    last_name: String::new(),
+                    birth_year: participant.birth_year,
+                    club: None,
+                    results: vec![CompetitionPoints::default(); competitions.len()],
+                    total: 0,
+                    place: 0,
+                    category: String::new(),
+                });
+            // competitions are ordered by date, so the latest data wins
+            standing.first_name = participant.first_name;
+            standing.last_name = participant.last_name;
+            standing.club = participant.club;
+            standing.category = participant.category;
+            let result = &mut standing.results[idx];
+            result.points = result.points.max(Some(place_points));
+        }
+    }
+
+    let counting_results = usize::try_from(series.counting_results).unwrap_or_default();
+    let mut by_category = BTreeMap::<String, Vec<SeriesStanding>>::new();
+    for mut standing in by_person.into_values() {
+        count_best_results(&mut standing, counting_results);
+        by_category
+            .entry(standing.category.clone())
+            .or_default()
+            .push(standing);
+    }
+    Ok(by_category
+        .into_iter()
+        .map(|(category, mut standings)| {
+            standings.sort_by(|a, b| {
+                b.total
+                    .cmp(&a.total)
+                    .then_with(|| a.last_name.cmp(&b.last_name))
+                    .then_with(|| a.first_name.cmp(&b.first_name))
+            });
+            let category_places = places(standings.iter().map(|s| -i64::from(s.total)));
+            for (standing, place) in standings.iter_mut().zip(category_places) {
+                standing.place = place;
+            }
+            CategoryStandings {
+                category,
+                standings,
+            }
+        })
+        .collect())
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_standings(state: AppState, Path(series_id): Path<Id>) -> Result<Html<String>> {
+    let data = state
+        .with_connection(move |conn| {
+            let Some(series) = series::table
+                .find(series_id)
+                .select(Series::as_select())
+                .first(conn)
+                .optional()?
+            else {
+                return QueryResult::Ok(None);
+            };
+            let competitions = competitions::table
+                .inner_join(series_competitions::table)
+                .filter(series_competitions::series_id.eq(series_id))
+                .order_by((competitions::date, competitions::id))
+                .select(Competition::as_select())
+                .load(conn)?;
+            let categories = load_standings(conn, &series, &competitions)?;
+            Ok(Some(SeriesStandingsData {
+                series,
+                competitions,
+                categories,
+            }))
+        })
+        .await?;
+    let data =
+        data.ok_or_else(|| Error::NotFound(format!("No series for id {series_id} found")))?;
+    state.render_template("series_standings.html", data)
+}
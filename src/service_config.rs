@@ -24,9 +24,65 @@ pub struct Config {
     /// Base url the application is hosted at
     #[clap(long = "base_url", default_value = "")]
     pub base_url: String,
+    /// Public address of the application including the scheme, used for
+    /// links in mails
+    ///
+    /// Defaults to the address and port the application listens on, but is
+    /// required if mails are sent via SMTP
+    #[clap(long = "public-url")]
+    pub public_url: Option<String>,
     /// Path to the template directory
     #[clap(default_value = "templates")]
     pub template_dir: PathBuf,
+    /// Whether or not to listen for reads of timing readers
+    #[clap(long = "timing-listener")]
+    pub timing_listener: bool,
+    /// Address the timing reader listener is listening on
+    ///
+    /// Defaults to the local host, set this to the address of the network
+    /// the timing readers are connected to
+    #[clap(long = "timing-address", default_value = "127.0.0.1")]
+    pub timing_address: IpAddr,
+    /// Port the timing reader listener is running on
+    #[clap(long = "timing-port", default_value = "8001")]
+    pub timing_port: u16,
+    /// Shared secret timing readers need to send before their reads are stored
+    ///
+    /// Required if the timing listener is enabled
+    #[clap(long = "timing-token", env = "TIMING_TOKEN", hide_env_values = true)]
+    pub timing_token: Option<String>,
+    /// SMTP server used to send mails, mails are only logged if this is not set
+    ///
+    /// Mails are sent via STARTTLS unless `--smtp-insecure` is set
+    #[clap(long = "smtp-server")]
+    pub smtp_server: Option<String>,
+    /// Port of the SMTP server
+    ///
+    /// Defaults to 587, or to 25 with `--smtp-insecure`
+    #[clap(long = "smtp-port")]
+    pub smtp_port: Option<u16>,
+    /// Send mails without encryption, only meant for a relay on the same host
+    #[clap(long = "smtp-insecure")]
+    pub smtp_insecure: bool,
+    /// User name to authenticate at the SMTP server
+    #[clap(long = "smtp-user")]
+    pub smtp_user: Option<String>,
+    /// File containing the password to authenticate at the SMTP server
+    ///
+    /// Alternatively the password is read from the SMTP_PASSWORD environment variable
+    #[clap(long = "smtp-password-file")]
+    pub smtp_password_file: Option<PathBuf>,
+    /// Password to authenticate at the SMTP server
+    ///
+    /// This is never read from the command line, where other users could see it
+    #[clap(skip = std::env::var("SMTP_PASSWORD").ok())]
+    pub smtp_password: Option<String>,
+    /// Sender address of mails
+    #[clap(long = "mail-from", default_value = "registration@localhost")]
+    pub mail_from: String,
+    /// File logged mails are appended to if no SMTP server is set
+    #[clap(long = "mail-file")]
+    pub mail_file: Option<PathBuf>,
     /// Internal flag whether or on this config is a test run config
     ///
     /// This cannot be set from the command line
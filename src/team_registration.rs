@@ -0,0 +1,234 @@
+//! Routes for handling the registration of a relay team
+use crate::app_state::{self, AppState};
+use crate::database::schema::{competitions, races};
+use crate::database::shared_models::{Competition, Race};
+use crate::database::teams::{NewTeamMember, TeamRegistration};
+use crate::database::Id;
+use crate::errors::{Error, Result};
+use axum::extract::{Form, Path};
+use axum::response::{Html, Redirect};
+use axum::Router;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub fn routes() -> Router<app_state::State> {
+    Router::new()
+        .route(
+            "/{event_id}/team_registration.html",
+            axum::routing::get(render_team_registration_page),
+        )
+        .route("/{event_id}/team/", axum::routing::post(add_team))
+}
+
+/// Data used to render the team registration form
+///
+/// see `templates/team_registration.html` for the template
+#[derive(Serialize)]
+struct TeamRegistrationPageData {
+    /// Which competition is the form for
+    event: Competition,
+    /// Which relay races exist for the competition
+    races: Vec<Race>,
+    /// The largest team size of all relay races
+    ///
+    /// The form contains that many member fieldsets
+    max_team_size: i32,
+    /// Title displayed in the HTML head tag
+    head_title: String,
+    /// Title displayed as headline on the page
+    title: String,
+}
+
+#[axum::debug_handler(state = app_state::State)]
+async fn render_team_registration_page(
+    state: AppState,
+    event_id: Path<Id>,
+) -> Result<Html<String>> {
+    let event_id = event_id.0;
+    let data = state
+        .with_connection(move |conn| {
+            let Some(competition) = competitions::table
+                .find(event_id)
+                .first::<Competition>(conn)
+                .optional()?
+            else {
+                return QueryResult::Ok(None);
+            };
+            let races = races::table
+                .filter(races::competition_id.eq(event_id))
+                .filter(races::team_size.is_not_null())
+                .order_by(races::id)
+                .select(Race::as_select())
+                .load(conn)?;
+            Ok(Some((competition, races)))
+        })
+        .await?;
+    let (competition, races) =
+        data.ok_or_else(|| Error::NotFound(format!("No competition for id {event_id} found")))?;
+
+    let max_team_size = races.iter().filter_map(|r| r.team_size).max().unwrap_or(0);
+    let params = HashMap::from([("competition", &competition.name as &str)]);
+    state.render_template(
+        "team_registration.html",
+        TeamRegistrationPageData {
+            races,
+            max_team_size,
+            head_title: state.translation("short_team_registration"),
+            title: state.translation_with_params("team_registration", params),
+            event: competition,
+        },
+    )
+}
+
+/// Data returned from the team registration form
+///
+/// The member fields are suffixed with the leg of the member,
+/// e.g. `firstname_1`, so they are collected from the raw form fields
+#[derive(Debug)]
+struct TeamRegistrationForm {
+    race: Id,
+    team_name: String,
+    club: Option<String>,
+    consent: bool,
+    fields: HashMap<String, String>,
+}
+
+impl TeamRegistrationForm {
+    fn parse(fields: Vec<(String, String)>) -> Result<Self> {
+        let mut fields = fields.into_iter().collect::<HashMap<_, _>>();
+        let race = fields
+            .remove("race")
+            .and_then(|r| r.parse().ok())
+            .ok_or_else(|| Error::InvalidInput("No valid race selected".into()))?;
+        let team_name = fields
+            .remove("team_name")
+            .map(|n| n.trim().to_owned())
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| Error::InvalidInput("The team name must not be empty".into()))?;
+        let club = fields
+            .remove("club")
+            .map(|c| c.trim().to_owned())
+            .filter(|c| !c.is_empty());
+        let consent = fields.remove("consent").is_some_and(|c| c == "on");
+        Ok(Self {
+            race,
+            team_name,
+            club,
+            consent,
+            fields,
+        })
+    }
+
+    /// Collect the members for the legs `1..=team_size`
+    fn members(&self, team_size: i32) -> Result<Vec<NewTeamMember>> {
+        (1..=team_size)
+            .map(|leg| {
+                let field = |name: &str| {
+                    self.fields
+                        .get(&format!("{name}_{leg}"))
+                        .map(|v| v.trim())
+                        .filter(|v| !v.is_empty())
+                        .ok_or_else(|| {
+                            Error::InvalidInput(format!("Missing {name} for member {leg}"))
+                        })
+                };
+                Ok(NewTeamMember {
+                    first_name: field("firstname")?.to_owned(),
+                    last_name: field("lastname")?.to_owned(),
+                    birth_year: field("age")?.parse().map_err(|_| {
+                        Error::InvalidInput(format!("Invalid birth year for member {leg}"))
+                    })?,
+                    male: field("male")? == "true",
+                    email: field("email").ok().map(str::to_owned),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Handle adding a new team
+#[axum::debug_handler(state = app_state::State)]
+async fn add_team(
+    state: AppState,
+    event_id: Path<Id>,
+    form_data: Form<Vec<(String, String)>>,
+) -> Result<Redirect> {
+    let event_id = event_id.0;
+    crate::registration::ensure_registration_open(&state, event_id).await?;
+    let form = TeamRegistrationForm::parse(form_data.0)?;
+    if !form.consent {
+        return Err(Error::InvalidInput(String::from(
+            "Expect that you consent to the participant conditions",
+        )));
+    }
+    let race_id = form.race;
+    let team_size = state
+        .with_connection(move |conn| {
+            races::table
+                .find(race_id)
+                .filter(races::competition_id.eq(event_id))
+                .select(races::team_size)
+                .first::<Option<i32>>(conn)
+                .optional()
+        })
+        .await?
+        .flatten()
+        .ok_or_else(|| Error::InvalidInput(format!("Race {race_id} is not a relay race")))?;
+    let members = form.members(team_size)?;
+
+    let outcome = state
+        .with_connection(move |conn| {
+            crate::database::teams::register_team(
+                conn,
+                event_id,
+                race_id,
+                &form.team_name,
+                form.club.as_deref(),
+                &members,
+            )
+        })
+        .await?;
+    let member_ids = match outcome {
+        TeamRegistration::Registered(member_ids) => member_ids,
+        TeamRegistration::InvalidRace => {
+            return Err(Error::InvalidInput(format!(
+                "Race {race_id} is not a relay race"
+            )));
+        }
+        TeamRegistration::WrongTeamSize(size) => {
+            return Err(Error::InvalidInput(format!(
+                "A team needs exactly {size} members"
+            )));
+        }
+        TeamRegistration::NoCategory(member) => {
+            return Err(Error::InvalidInput(format!(
+                "No category in this race matches {member}"
+            )));
+        }
+        TeamRegistration::NoTeamCategory => {
+            return Err(Error::InvalidInput(String::from(
+                "No team category matches the members of this team",
+            )));
+        }
+        TeamRegistration::Duplicate(member) => {
+            let birth_year = member.birth_year.to_string();
+            return Err(Error::InvalidInput(state.translation_with_params(
+                "duplicate_registration",
+                HashMap::from([
+                    ("first_name", member.first_name.as_str()),
+                    ("last_name", member.last_name.as_str()),
+                    ("birth_year", birth_year.as_str()),
+                ]),
+            )));
+        }
+    };
+    state.notify_results_changed(event_id);
+    for participant_id in member_ids {
+        crate::registration::send_confirmation(&state, event_id, participant_id).await?;
+    }
+    let base_url = state.base_url();
+    Ok(Redirect::to(&format!(
+        "{base_url}/{event_id}/registration_list.html"
+    )))
+}
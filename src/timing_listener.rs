@@ -0,0 +1,134 @@
+//! Live input of timing readers via TCP
+//!
+//! Readers connect to the configured port and first authenticate with a
+//! `token <secret>` line containing the configured shared secret. Afterwards
+//! they send one read per line using the same `chip;timestamp;point` format as
+//! the file import. Each line is answered with `ok`, `duplicate` or
+//! `error: <reason>`. Reads are stored right away, so the result list updates
+//! while the race is running.
+//!
+//! Connections sending an invalid token or lines longer than
+//! [`MAX_LINE_LENGTH`] are closed.
+use crate::app_state;
+use crate::chip_timing::{self, ChipRead};
+use crate::database::chip_reads::{self, DEFAULT_DEDUP_WINDOW};
+use crate::errors::{Error, Result};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_stream::StreamExt;
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+/// Maximal length of a single line in bytes, reads are much shorter
+const MAX_LINE_LENGTH: usize = 256;
+
+/// Start listening on `address` in a background task
+///
+/// Only clients sending `token` are allowed to store reads. Returns the
+/// address the listener is bound to
+pub(crate) async fn spawn(
+    state: app_state::State,
+    address: SocketAddr,
+    token: String,
+) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(address).await?;
+    let local_address = listener.local_addr()?;
+    let token = Arc::new(token);
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    tracing::info!("Timing reader connected from {peer}");
+                    tokio::spawn(handle_connection(state.clone(), stream, token.clone()));
+                }
+                Err(e) => tracing::error!("Failed to accept timing reader connection: {e}"),
+            }
+        }
+    });
+    Ok(local_address)
+}
+
+/// Compare the digests instead of the tokens, so the time the comparison takes
+/// does not reveal how much of the token was correct
+fn is_valid_token(token: &str, expected: &str) -> bool {
+    Sha256::digest(token.as_bytes()) == Sha256::digest(expected.as_bytes())
+}
+
+async fn handle_connection(state: app_state::State, stream: TcpStream, token: Arc<String>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = FramedRead::new(reader, LinesCodec::new_with_max_length(MAX_LINE_LENGTH));
+    let mut authenticated = false;
+    loop {
+        let line = match lines.next().await {
+            Some(Ok(line)) => line,
+            None => break,
+            Some(Err(e)) => {
+                tracing::error!("Failed to read from timing reader, closing the connection: {e}");
+                break;
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !authenticated {
+            let valid = line
+                .strip_prefix("token ")
+                .is_some_and(|sent| is_valid_token(sent.trim(), &token));
+            let response = if valid {
+                "ok\n"
+            } else {
+                "error: invalid token\n"
+            };
+            if let Err(e) = writer.write_all(response.as_bytes()).await {
+                tracing::error!("Failed to answer timing reader: {e}");
+                break;
+            }
+            if !valid {
+                tracing::warn!("Timing reader sent an invalid token, closing the connection");
+                break;
+            }
+            authenticated = true;
+            continue;
+        }
+        let response = match chip_timing::parse_line(line) {
+            Ok(read) => match store_read(&state, read).await {
+                Ok(response) => response.to_owned(),
+                Err(e) => format!("error: {e}"),
+            },
+            Err(e) => format!("error: {e}"),
+        };
+        if let Err(e) = writer.write_all(format!("{response}\n").as_bytes()).await {
+            tracing::error!("Failed to answer timing reader: {e}");
+            break;
+        }
+    }
+}
+
+/// Store a single read in the competition it belongs to
+async fn store_read(state: &app_state::State, read: ChipRead) -> Result<&'static str> {
+    let summary = state
+        .with_connection(move |conn| {
+            let Some(competition_id) = chip_reads::competition_for_read(conn, &read)? else {
+                return Ok(None);
+            };
+            chip_reads::store_reads(
+                conn,
+                competition_id,
+                std::slice::from_ref(&read),
+                DEFAULT_DEDUP_WINDOW,
+            )
+            .map(|summary| Some((competition_id, summary)))
+        })
+        .await?;
+    match summary {
+        None => Err(Error::NotFound("No competition found for this read".into())),
+        Some((_, summary)) if summary.duplicates > 0 => Ok("duplicate"),
+        Some((competition_id, _)) => {
+            state.notify_results_changed(competition_id);
+            Ok("ok")
+        }
+    }
+}
@@ -1,6 +1,14 @@
+// unwrapping is fine in tests
+#![allow(clippy::unwrap_used)]
 use axum::body::Body;
-use axum::http::{Request, StatusCode};
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use diesel::prelude::*;
 use http_body_util::BodyExt;
+use race_timing::database::schema::{
+    categories, clubs, competitions, correction_requests, participants, payments, persons, races,
+    secrets, series, starts, time_adjustments,
+};
 use race_timing::service_config::Config;
 use std::path::PathBuf;
 use tower::ServiceExt;
@@ -18,11 +26,103 @@ fn test_config(test_data: bool) -> Config {
         database_url: ":memory:".into(),
         insert_test_data: test_data,
         base_url: "".into(),
+        public_url: None,
         template_dir,
+        timing_listener: false,
+        timing_address: "127.0.0.1".parse().unwrap(),
+        timing_port: 8001,
+        timing_token: None,
+        smtp_server: None,
+        smtp_port: None,
+        smtp_insecure: false,
+        smtp_user: None,
+        smtp_password_file: None,
+        smtp_password: None,
+        mail_from: "registration@localhost".into(),
+        mail_file: None,
         is_test: true,
     }
 }
 
+// insert an additional participant into the category with the given label
+// of the 11km race of the test data
+fn insert_participant(
+    conn: &mut SqliteConnection,
+    first_name: &str,
+    last_name: &str,
+    category: &str,
+) -> QueryResult<i32> {
+    let category_id = categories::table
+        .inner_join(starts::table)
+        .filter(starts::name.eq("11km"))
+        .filter(categories::label.eq(category))
+        .select(categories::id)
+        .first::<i32>(conn)?;
+    diesel::insert_into(participants::table)
+        .values((
+            participants::first_name.eq(first_name),
+            participants::last_name.eq(last_name),
+            participants::category_id.eq(category_id),
+            participants::consent_agb.eq(true),
+            participants::birth_year.eq(1990),
+            participants::registered_at.eq(time::macros::datetime!(2026-02-01 09:00:00)),
+        ))
+        .returning(participants::id)
+        .get_result(conn)
+}
+
+// log in as the `admin` user created by the test data
+//
+// returns the session cookie that needs to be passed to admin requests
+async fn login(router: &Router) -> String {
+    let resp = router
+        .clone()
+        .oneshot(
+            Request::post("/admin/login")
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from("name=admin&password=admin"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    let cookie = resp.headers()[header::SET_COOKIE].to_str().unwrap();
+    cookie.split(';').next().unwrap().to_owned()
+}
+
+// send a form to the given uri with the given session cookie
+async fn post_form(router: &Router, cookie: &str, uri: &str, form: &[(&str, &str)]) -> StatusCode {
+    let resp = router
+        .clone()
+        .oneshot(
+            Request::post(uri)
+                .header(header::COOKIE, cookie)
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(serde_urlencoded::to_string(form).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    resp.status()
+}
+
+// request the given uri with the given session cookie and return the response body
+async fn get_page(router: &Router, cookie: &str, uri: &str) -> (StatusCode, String) {
+    let resp = router
+        .clone()
+        .oneshot(
+            Request::get(uri)
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = resp.status();
+    let data = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(data.to_vec()).unwrap())
+}
+
 #[tokio::test]
 async fn translations_work() {
     let (router, _state) = race_timing::setup(test_config(false)).await;
@@ -104,3 +204,2684 @@ async fn translations_work() {
     assert!(string.contains("Wettkämpfe"), "{string}");
     insta::assert_snapshot!("german", string);
 }
+
+#[tokio::test]
+async fn record_finish_times() {
+    let (router, _state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+
+    // John Doe (id 1) starts at 10:50 in the 11km race
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", "1"),
+            ("finish_time", "2026-02-18T11:35:12"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (status, page) =
+        get_page(&router, &cookie, "/admin/competitions/1/time_records.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("John"), "{page}");
+    assert!(page.contains("45:12"), "{page}");
+    // the test data assign bib numbers from the range of the 11km start
+    assert!(page.contains("<td>600</td>"), "{page}");
+
+    // a recorded time is never replaced, corrections need a time adjustment
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", "1"),
+            ("finish_time", "2026-02-18T11:36:00.5"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (_, page) = get_page(&router, &cookie, "/admin/competitions/1/time_records.html").await;
+    assert!(page.contains("45:12"), "{page}");
+    assert!(!page.contains("46:00.5"), "{page}");
+    // recorded times cannot be deleted either
+    let (status, _) = get_page(&router, &cookie, "/admin/time_records/1/delete.html").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // unknown participants are rejected
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", "42"),
+            ("finish_time", "2026-02-18T11:35:12"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn results_are_ranked() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (anna, max) = state
+        .with_connection(|conn| {
+            let anna = insert_participant(conn, "Anna", "Smith", "W 21")?;
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            Ok((anna, max))
+        })
+        .await
+        .unwrap();
+
+    for (participant_id, finish_time) in [
+        (1.to_string(), "2026-02-18T11:35:12"),
+        (max.to_string(), "2026-02-18T11:35:12"),
+        (anna.to_string(), "2026-02-18T11:34:00"),
+    ] {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_records",
+            &[
+                ("participant_id", &participant_id),
+                ("finish_time", finish_time),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) = get_page(&router, "", "/1/results.html").await;
+    assert_eq!(status, StatusCode::OK);
+    let anna_pos = page.find("Anna").unwrap();
+    let john_pos = page.find("John").unwrap();
+    let max_pos = page.find("Max").unwrap();
+    assert!(anna_pos < john_pos && anna_pos < max_pos, "{page}");
+    // John and Max share the second place, there is no third place
+    assert_eq!(page.matches("<td>2.</td>").count(), 2, "{page}");
+    assert!(!page.contains("<td>3.</td>"), "{page}");
+    assert!(page.contains("+01:12"), "{page}");
+    assert!(page.contains("44:00"), "{page}");
+
+    // a disqualification requires a reason
+    let status_uri =
+        format!("/admin/participants/{max}/status?redirect_to=competitions/1/participants.html");
+    let status = post_form(&router, &cookie, &status_uri, &[("status", "dsq")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = post_form(
+        &router,
+        &cookie,
+        &status_uri,
+        &[("status", "dsq"), ("reason", "Shortcut")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // disqualified participants are listed below the ranked participants
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    let non_finishers_pos = page.find("Not classified").unwrap();
+    let max_pos = page.find("Max").unwrap();
+    assert!(non_finishers_pos < max_pos, "{page}");
+    assert!(page.contains("Shortcut"), "{page}");
+    assert_eq!(page.matches("<td>2.</td>").count(), 1, "{page}");
+}
+
+#[tokio::test]
+async fn split_times_are_ranked_per_segment() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (race_id, max) = state
+        .with_connection(|conn| {
+            let race_id = races::table
+                .filter(races::name.eq("11km"))
+                .select(races::id)
+                .first::<i32>(conn)?;
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            Ok((race_id, max))
+        })
+        .await
+        .unwrap();
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/timing_points"),
+        &[("name", "5km"), ("position", "1")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (status, page) = get_page(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/timing_points.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("5km"), "{page}");
+
+    // the 11km race starts at 10:50
+    for (participant_id, split, finish) in [
+        (1.to_string(), "2026-02-18T11:10:00", "2026-02-18T11:35:00"),
+        (
+            max.to_string(),
+            "2026-02-18T11:12:00",
+            "2026-02-18T11:34:00",
+        ),
+    ] {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/split_times",
+            &[
+                ("participant_id", &participant_id),
+                ("timing_point_id", "1"),
+                ("time", split),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_records",
+            &[("participant_id", &participant_id), ("finish_time", finish)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    // Jane Doe runs the 5,5km race, which has no such timing point
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/split_times",
+        &[
+            ("participant_id", "2"),
+            ("timing_point_id", "1"),
+            ("time", "2026-02-18T11:10:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, page) = get_page(&router, "", "/1/results.html").await;
+    assert_eq!(status, StatusCode::OK);
+    // John is faster to the timing point, Max is faster from there to the finish
+    assert!(page.contains("(20:00, 1.)"), "{page}");
+    assert!(page.contains("(22:00, 2.)"), "{page}");
+    assert!(page.contains("(25:00, 2.)"), "{page}");
+    assert!(page.contains("(22:00, 1.)"), "{page}");
+}
+
+#[tokio::test]
+async fn finish_order_is_merged_with_stopwatch_times() {
+    use race_timing::database::schema::{finish_order_bibs, stopwatch_times, time_records};
+
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+
+    for time in [
+        "2026-02-18T11:30:00",
+        "2026-02-18T11:32:00",
+        "2026-02-18T11:33:00",
+    ] {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/stopwatch",
+            &[("time", time)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+    // John Doe has bib 600, Jane Doe has bib 700
+    for (bib, position) in [("700", ""), ("600", "1"), ("999", "")] {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/finish_order",
+            &[("bib", bib), ("position", position)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) =
+        get_page(&router, &cookie, "/admin/competitions/1/finish_merge.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Unknown bib"), "{page}");
+    let john_pos = page.find("John").unwrap();
+    let jane_pos = page.find("Jane").unwrap();
+    assert!(john_pos < jane_pos, "{page}");
+
+    // drop the wrong bib and the superfluous time
+    let (time_id, bib_id) = state
+        .with_connection(|conn| {
+            let time_id = stopwatch_times::table
+                .filter(stopwatch_times::position.eq(3))
+                .select(stopwatch_times::id)
+                .first::<i32>(conn)?;
+            let bib_id = finish_order_bibs::table
+                .filter(finish_order_bibs::bib.eq(999))
+                .select(finish_order_bibs::id)
+                .first::<i32>(conn)?;
+            Ok((time_id, bib_id))
+        })
+        .await
+        .unwrap();
+    for uri in [
+        format!("/admin/stopwatch_times/{time_id}/delete.html"),
+        format!("/admin/finish_order/{bib_id}/delete.html"),
+    ] {
+        let (status, _) = get_page(&router, &cookie, &uri).await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let status = post_form(&router, &cookie, "/admin/competitions/1/finish_merge", &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // merging again after inserting a time does not replace recorded times
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/stopwatch",
+        &[("time", "2026-02-18T11:29:00"), ("position", "1")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let status = post_form(&router, &cookie, "/admin/competitions/1/finish_merge", &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let finish_times = state
+        .with_connection(|conn| {
+            time_records::table
+                .order_by(time_records::participant_id)
+                .select((time_records::participant_id, time_records::finish_time))
+                .load::<(i32, time::PrimitiveDateTime)>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        finish_times,
+        [
+            (1, time::macros::datetime!(2026-02-18 11:30:00)),
+            (2, time::macros::datetime!(2026-02-18 11:32:00)),
+        ]
+    );
+}
+
+// upload `content` as `file` field of a multipart form
+async fn post_file(
+    router: &Router,
+    cookie: &str,
+    uri: &str,
+    content: &str,
+    fields: &[(&str, &str)],
+) -> StatusCode {
+    post_file_redirect(router, cookie, uri, content, fields)
+        .await
+        .0
+}
+
+// like `post_file`, also returns the redirect target relative to the router
+async fn post_file_redirect(
+    router: &Router,
+    cookie: &str,
+    uri: &str,
+    content: &str,
+    fields: &[(&str, &str)],
+) -> (StatusCode, String) {
+    let boundary = "race-timing-test-boundary";
+    let mut body = String::new();
+    for (name, value) in fields {
+        body.push_str(&format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+        ));
+    }
+    body.push_str(&format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"reads.csv\"\r\nContent-Type: text/csv\r\n\r\n{content}\r\n--{boundary}--\r\n"
+    ));
+    let resp = router
+        .clone()
+        .oneshot(
+            Request::post(uri)
+                .header(header::COOKIE, cookie)
+                .header(
+                    header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let location = resp
+        .headers()
+        .get(header::LOCATION)
+        .map(|l| l.to_str().unwrap().to_owned())
+        .unwrap_or_default();
+    (resp.status(), location)
+}
+
+#[tokio::test]
+async fn chip_reads_are_imported() {
+    use race_timing::database::schema::{chip_reads, time_records};
+
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chips",
+        &[("chip", "A1"), ("participant_id", "1")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // B2 passes the finish mat right after the start at 11:00, that read is
+    // rejected as a finish
+    let export = "chip,time,reader\n\
+                  \"A1\",2026-02-18T11:40:00,finish\n\
+                  A1,2026-02-18 11:40:02.5,finish\n\
+                  B2;2026-02-18 11:00:10;finish\n\
+                  B2;2026-02-18 11:41:00;finish\n";
+    for duplicates in [1, 4] {
+        // importing the same file twice does not duplicate reads
+        let (status, location) = post_file_redirect(
+            &router,
+            &cookie,
+            "/admin/competitions/1/chip_reads",
+            export,
+            &[("dedup_window", "5")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+        // the summary of the import is shown to the admin
+        let (status, page) = get_page(&router, &cookie, &location).await;
+        assert_eq!(status, StatusCode::OK, "{page}");
+        assert!(page.contains("Last Import"), "{page}");
+        assert!(
+            page.contains(&format!("Ignored repeated reads: {duplicates}")),
+            "{page}"
+        );
+    }
+    let status = post_file(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chip_reads",
+        "A1;2026-02-18 11:40:00;finish\nA1;yesterday;finish\n",
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, page) = get_page(&router, &cookie, "/admin/competitions/1/chips.html").await;
+    assert_eq!(status, StatusCode::OK, "{page}");
+    assert!(page.contains("Unassigned Chips"), "{page}");
+    assert!(page.contains("B2"), "{page}");
+
+    // reads imported before the assignment are applied afterwards
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chips",
+        &[("chip", "B2"), ("participant_id", "2")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // later finish reads, e.g. of a runner crossing the finish mat again,
+    // do not change the finish time
+    let status = post_file(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chip_reads",
+        "A1;2026-02-18 11:42:00;finish\nA1;2026-02-18 11:44:00;finish\n",
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (read_count, finish_times) = state
+        .with_connection(|conn| {
+            let read_count = chip_reads::table.count().get_result::<i64>(conn)?;
+            let finish_times = time_records::table
+                .order_by(time_records::participant_id)
+                .select((time_records::participant_id, time_records::finish_time))
+                .load::<(i32, time::PrimitiveDateTime)>(conn)?;
+            Ok((read_count, finish_times))
+        })
+        .await
+        .unwrap();
+    assert_eq!(read_count, 5);
+    assert_eq!(
+        finish_times,
+        [
+            (1, time::macros::datetime!(2026-02-18 11:40:00)),
+            (2, time::macros::datetime!(2026-02-18 11:41:00)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn timing_listener_stores_reads() {
+    use race_timing::database::schema::{chip_reads, time_records};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    // find a free port for the listener
+    let timing_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let config = Config {
+        timing_listener: true,
+        timing_port,
+        timing_token: Some("reader secret".into()),
+        ..test_config(true)
+    };
+    let (router, state) = race_timing::setup(config).await;
+    let cookie = login(&router).await;
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chips",
+        &[("chip", "A1"), ("participant_id", "1")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // connections without the right token are closed before storing reads
+    for first_line in ["token wrong", "A1;2026-02-18 11:39:00;finish"] {
+        let stream = tokio::net::TcpStream::connect(("127.0.0.1", timing_port))
+            .await
+            .unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut responses = BufReader::new(reader).lines();
+        writer
+            .write_all(format!("{first_line}\nA1;2026-02-18 11:39:00;finish\n").as_bytes())
+            .await
+            .unwrap();
+        let response = responses.next_line().await.unwrap().unwrap();
+        assert_eq!(response, "error: invalid token");
+        assert!(!matches!(responses.next_line().await, Ok(Some(_))));
+    }
+
+    let stream = tokio::net::TcpStream::connect(("127.0.0.1", timing_port))
+        .await
+        .unwrap();
+    let (reader, mut writer) = stream.into_split();
+    let mut responses = BufReader::new(reader).lines();
+    for (line, expected) in [
+        ("token reader secret", "ok"),
+        ("A1;2026-02-18 11:40:00;finish", "ok"),
+        ("A1;2026-02-18 11:40:01;finish", "duplicate"),
+        // a further read is stored, but never replaces the recorded finish time
+        ("A1;2026-02-18 11:38:00;finish", "ok"),
+        // unassigned chips are stored in the competition of that day
+        ("Z9;2026-02-18 11:41:00;finish", "ok"),
+        ("Z9;2025-01-01 11:41:00;finish", "error"),
+        ("garbage", "error"),
+    ] {
+        writer
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .unwrap();
+        let response = responses.next_line().await.unwrap().unwrap();
+        assert!(response.starts_with(expected), "{line}: {response}");
+    }
+    // overlong lines close the connection
+    writer.write_all(&[b'A'; 1024]).await.unwrap();
+    writer.write_all(b"\n").await.unwrap();
+    assert!(!matches!(responses.next_line().await, Ok(Some(_))));
+
+    let (read_count, finish_time) = state
+        .with_connection(|conn| {
+            let read_count = chip_reads::table.count().get_result::<i64>(conn)?;
+            let finish_time = time_records::table
+                .filter(time_records::participant_id.eq(1))
+                .select(time_records::finish_time)
+                .first::<time::PrimitiveDateTime>(conn)?;
+            Ok((read_count, finish_time))
+        })
+        .await
+        .unwrap();
+    assert_eq!(read_count, 3);
+    assert_eq!(finish_time, time::macros::datetime!(2026-02-18 11:40:00));
+}
+
+#[tokio::test]
+async fn ranking_by_gun_or_net_time() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (start_id, race_id, max) = state
+        .with_connection(|conn| {
+            let (start_id, race_id) = starts::table
+                .filter(starts::name.eq("11km"))
+                .select((starts::id, starts::race_id))
+                .first::<(i32, i32)>(conn)?;
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            Ok((start_id, race_id, max))
+        })
+        .await
+        .unwrap();
+
+    // the start signal was given a minute late
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/starts/{start_id}/gun_time"),
+        &[("gun_time", "2026-02-18T10:51:00")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // John crossed the start mat another minute later
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chips",
+        &[("chip", "A1"), ("participant_id", "1")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let status = post_file(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chip_reads",
+        "A1;2026-02-18 10:52:00;start\nA1;2026-02-18 11:35:00;finish\n",
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &max.to_string()),
+            ("finish_time", "2026-02-18T11:34:30"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // races are ranked by net time by default
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(
+        page.find("John").unwrap() < page.find("Max").unwrap(),
+        "{page}"
+    );
+    assert!(page.contains("43:00"), "{page}");
+    assert!(page.contains("44:00"), "{page}");
+
+    state
+        .with_connection(move |conn| {
+            diesel::update(races::table.find(race_id))
+                .set(races::ranking_mode.eq("gun"))
+                .execute(conn)
+        })
+        .await
+        .unwrap();
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(
+        page.find("Max").unwrap() < page.find("John").unwrap(),
+        "{page}"
+    );
+    assert!(page.contains("+00:30"), "{page}");
+
+    // "start now" uses the server clock in the time zone of the device
+    let uri = format!("/admin/starts/{start_id}/start_now");
+    let status = post_form(&router, &cookie, &uri, &[("utc_offset_minutes", "5000")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = post_form(&router, &cookie, &uri, &[("utc_offset_minutes", "60")]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let gun_time = state
+        .with_connection(move |conn| {
+            starts::table
+                .find(start_id)
+                .select(starts::gun_time.assume_not_null())
+                .first::<time::PrimitiveDateTime>(conn)
+        })
+        .await
+        .unwrap();
+    let now = time::OffsetDateTime::now_utc().to_offset(time::macros::offset!(+1));
+    let now = time::PrimitiveDateTime::new(now.date(), now.time());
+    assert!(
+        (now - gun_time).abs() < time::Duration::minutes(1),
+        "{gun_time}"
+    );
+}
+
+#[tokio::test]
+async fn results_stream_reports_changes() {
+    let (router, _state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+
+    let resp = router
+        .clone()
+        .oneshot(
+            Request::get("/1/results/stream")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "text/event-stream"
+    );
+    let mut body = resp.into_body();
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", "1"),
+            ("finish_time", "2026-02-18T11:35:12"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let event = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+    assert_eq!(event, "event: results\ndata: 1\n\n");
+
+    let (status, _) = get_page(&router, "", "/42/results/stream").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn relay_teams_are_ranked_by_summed_leg_times() {
+    let mail_file = std::env::temp_dir().join(format!(
+        "race_timing_relay_mails_{}.txt",
+        std::process::id()
+    ));
+    let config = Config {
+        mail_file: Some(mail_file.clone()),
+        ..test_config(true)
+    };
+    let (router, state) = race_timing::setup(config).await;
+    let cookie = login(&router).await;
+    let race_id = state
+        .with_connection(|conn| {
+            let race_id = diesel::insert_into(races::table)
+                .values((
+                    races::name.eq("Relay"),
+                    races::competition_id.eq(1),
+                    races::team_size.eq(2),
+                ))
+                .returning(races::id)
+                .get_result::<i32>(conn)?;
+            let start_id = diesel::insert_into(starts::table)
+                .values((
+                    starts::name.eq("Relay"),
+                    starts::time.eq(time::macros::datetime!(2026-02-18 10:00:00)),
+                    starts::race_id.eq(race_id),
+                ))
+                .returning(starts::id)
+                .get_result::<i32>(conn)?;
+            diesel::insert_into(categories::table)
+                .values(vec![
+                    (
+                        categories::label.eq("Relay m"),
+                        categories::from_age.eq(0),
+                        categories::to_age.eq(99),
+                        categories::male.eq(true),
+                        categories::start_id.eq(start_id),
+                    ),
+                    (
+                        categories::label.eq("Relay w"),
+                        categories::from_age.eq(0),
+                        categories::to_age.eq(99),
+                        categories::male.eq(false),
+                        categories::start_id.eq(start_id),
+                    ),
+                ])
+                .execute(conn)?;
+            QueryResult::Ok(race_id)
+        })
+        .await
+        .unwrap();
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/team_categories"),
+        &[
+            ("label", "Mixed"),
+            ("composition", "mixed"),
+            ("from_combined_age", "0"),
+            ("to_combined_age", "200"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/fees"),
+        &[("base_fee", "20")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (status, page) = get_page(&router, "", "/1/team_registration.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("firstname_2"), "{page}");
+
+    let race = race_id.to_string();
+    let team = |name: &'static str, male_2: &'static str| {
+        vec![
+            ("race", race.clone()),
+            ("team_name", name.to_owned()),
+            ("club", "Relay Club".to_owned()),
+            ("consent", "on".to_owned()),
+            ("firstname_1", format!("{name}One")),
+            ("lastname_1", "Runner".to_owned()),
+            ("age_1", "1990".to_owned()),
+            ("male_1", "true".to_owned()),
+            ("email_1", format!("{name}@example.com")),
+            ("firstname_2", format!("{name}Two")),
+            ("lastname_2", "Runner".to_owned()),
+            ("age_2", "1992".to_owned()),
+            ("male_2", male_2.to_owned()),
+        ]
+    };
+    for (name, male_2, expected) in [
+        ("Fast", "false", StatusCode::SEE_OTHER),
+        ("Slow", "false", StatusCode::SEE_OTHER),
+        // there is no team category for male only teams
+        ("Men", "true", StatusCode::BAD_REQUEST),
+        // the members of a team cannot register twice
+        ("Fast", "false", StatusCode::BAD_REQUEST),
+    ] {
+        let fields = team(name, male_2);
+        let fields = fields
+            .iter()
+            .map(|(k, v)| (*k, v.as_str()))
+            .collect::<Vec<_>>();
+        let status = post_form(&router, "", "/1/team/", &fields).await;
+        assert_eq!(status, expected, "{name}");
+    }
+
+    let members = state
+        .with_connection(|conn| {
+            participants::table
+                .filter(participants::last_name.eq("Runner"))
+                .select((participants::first_name, participants::id))
+                .load::<(String, i32)>(conn)
+        })
+        .await
+        .unwrap()
+        .into_iter()
+        .collect::<std::collections::HashMap<_, _>>();
+    assert_eq!(members.len(), 4);
+    // every member owes the entry fee
+    let amounts = state
+        .with_connection(|conn| {
+            payments::table
+                .inner_join(participants::table)
+                .filter(participants::last_name.eq("Runner"))
+                .select(payments::amount_due)
+                .load::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(amounts, vec![2000; 4]);
+    // members with an email address get a confirmation
+    let mails = std::fs::read_to_string(&mail_file).unwrap();
+    std::fs::remove_file(&mail_file).unwrap();
+    assert!(mails.contains("To: Fast@example.com"), "{mails}");
+    assert!(mails.contains("FastOne Runner"), "{mails}");
+    assert!(!mails.contains("FastTwo"), "{mails}");
+    let (_, page) = get_page(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/teams.html"),
+    )
+    .await;
+    assert!(page.contains("2. FastTwo Runner"), "{page}");
+
+    // later legs have no bib of their own, they are timed by a chip
+    // assigned to the member or by the admin
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chips",
+        &[
+            ("chip", "R2"),
+            ("participant_id", &members["FastTwo"].to_string()),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let status = post_file(
+        &router,
+        &cookie,
+        "/admin/competitions/1/chip_reads",
+        "R2;2026-02-18 10:45:00;finish\n",
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    for (member, finish_time) in [
+        ("FastOne", "2026-02-18T10:20:00"),
+        ("SlowOne", "2026-02-18T10:19:00"),
+        ("SlowTwo", "2026-02-18T10:50:00"),
+    ] {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_records",
+            &[
+                ("participant_id", &members[member].to_string()),
+                ("finish_time", finish_time),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) = get_page(&router, "", "/1/results.html").await;
+    assert_eq!(status, StatusCode::OK);
+    let teams = &page[page.find("Relay: ").unwrap()..];
+    assert!(
+        teams.find("Fast").unwrap() < teams.find("Slow").unwrap(),
+        "{page}"
+    );
+    // leg times are measured from the finish of the previous leg
+    assert!(teams.contains("25:00"), "{page}");
+    assert!(teams.contains("31:00"), "{page}");
+    assert!(teams.contains("45:00"), "{page}");
+    assert!(teams.contains("+05:00"), "{page}");
+
+    // a team is waitlisted as a whole, even if some members would fit
+    state
+        .with_connection(move |conn| {
+            diesel::update(races::table.find(race_id))
+                .set(races::capacity.eq(5))
+                .execute(conn)
+        })
+        .await
+        .unwrap();
+    let fields = team("Late", "false");
+    let fields = fields
+        .iter()
+        .map(|(k, v)| (*k, v.as_str()))
+        .collect::<Vec<_>>();
+    let status = post_form(&router, "", "/1/team/", &fields).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let waitlisted = state
+        .with_connection(|conn| {
+            participants::table
+                .filter(participants::first_name.like("Late%"))
+                .select(participants::waitlisted_at.is_not_null())
+                .load::<bool>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(waitlisted, vec![true, true]);
+
+    // teams are only registered while the registration is open
+    state
+        .with_connection(|conn| {
+            diesel::update(competitions::table.find(1))
+                .set(
+                    competitions::registration_closes
+                        .eq(time::macros::datetime!(2020-01-01 00:00:00)),
+                )
+                .execute(conn)
+        })
+        .await
+        .unwrap();
+    let fields = team("Closed", "false");
+    let fields = fields
+        .iter()
+        .map(|(k, v)| (*k, v.as_str()))
+        .collect::<Vec<_>>();
+    let status = post_form(&router, "", "/1/team/", &fields).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn clubs_are_scored_with_normalized_names() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (race_id, participants) = state
+        .with_connection(|conn| {
+            let race_id = races::table
+                .filter(races::name.eq("11km"))
+                .select(races::id)
+                .first::<i32>(conn)?;
+            let mut ids = Vec::new();
+            for (first_name, club) in [
+                ("Anna", "SV Musterstadt"),
+                ("Berta", "sv  musterstadt e.V."),
+                ("Carl", "LG Test"),
+                ("Dora", "LG Test"),
+            ] {
+                let id = insert_participant(conn, first_name, "Clubber", "M 21")?;
+                diesel::update(participants::table.find(id))
+                    .set(participants::club.eq(club))
+                    .execute(conn)?;
+                ids.push(id);
+            }
+            QueryResult::Ok((race_id, ids))
+        })
+        .await
+        .unwrap();
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/clubs",
+        &[("name", "SV Musterstadt")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, &cookie, "/admin/clubs.html").await;
+    assert!(page.contains("sv musterstadt e v"), "{page}");
+    assert!(page.contains("LG Test"), "{page}");
+
+    let club_id = state
+        .with_connection(|conn| clubs::table.select(clubs::id).first::<i32>(conn))
+        .await
+        .unwrap();
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/club_aliases",
+        &[
+            ("club_id", &club_id.to_string()),
+            ("alias", "SV Musterstadt e.V."),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, &cookie, "/admin/clubs.html").await;
+    assert!(!page.contains("sv  musterstadt e.V."), "{page}");
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/club_scoring"),
+        &[("mode", "time_sum"), ("counting", "2")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    for (participant, finish_time) in participants.iter().zip([
+        "2026-02-18T11:30:00",
+        "2026-02-18T11:40:00",
+        "2026-02-18T11:35:00",
+        "2026-02-18T11:50:00",
+    ]) {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_records",
+            &[
+                ("participant_id", &participant.to_string()),
+                ("finish_time", finish_time),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) = get_page(&router, "", "/1/club_results.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        page.find("SV Musterstadt").unwrap() < page.find("LG Test").unwrap(),
+        "{page}"
+    );
+    // 40 + 50 minutes for both spellings of the same club
+    assert!(page.contains("1:30:00"), "{page}");
+    assert!(page.contains("1:45:00"), "{page}");
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/club_scoring"),
+        &[("mode", "place_sum"), ("counting", "3")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, "", "/1/club_results.html").await;
+    assert!(page.contains("Not classified"), "{page}");
+}
+
+#[tokio::test]
+async fn series_standings_count_best_results() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (max, tom, second_competition, second_max) = state
+        .with_connection(|conn| {
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            let tom = insert_participant(conn, "Tom", "Tester", "M 21")?;
+
+            // a second competition of the series with the same category
+            let competition_id = diesel::insert_into(competitions::table)
+                .values((
+                    competitions::name.eq("Autumn Run"),
+                    competitions::description.eq(""),
+                    competitions::date.eq(time::macros::date!(2026 - 09 - 01)),
+                    competitions::location.eq("London"),
+                    competitions::announcement.eq(""),
+                ))
+                .returning(competitions::id)
+                .get_result::<i32>(conn)?;
+            let race_id = diesel::insert_into(races::table)
+                .values((
+                    races::name.eq("10km"),
+                    races::competition_id.eq(competition_id),
+                ))
+                .returning(races::id)
+                .get_result::<i32>(conn)?;
+            let start_id = diesel::insert_into(starts::table)
+                .values((
+                    starts::name.eq("10km"),
+                    starts::time.eq(time::macros::datetime!(2026-09-01 10:00:00)),
+                    starts::race_id.eq(race_id),
+                ))
+                .returning(starts::id)
+                .get_result::<i32>(conn)?;
+            let category_id = diesel::insert_into(categories::table)
+                .values((
+                    categories::label.eq("M 21"),
+                    categories::from_age.eq(0),
+                    categories::to_age.eq(99),
+                    categories::male.eq(true),
+                    categories::start_id.eq(start_id),
+                ))
+                .returning(categories::id)
+                .get_result::<i32>(conn)?;
+            // the same person, registered with a slightly different spelling
+            let second_max = diesel::insert_into(participants::table)
+                .values((
+                    participants::first_name.eq("max"),
+                    participants::last_name.eq("Miller "),
+                    participants::category_id.eq(category_id),
+                    participants::consent_agb.eq(true),
+                    participants::birth_year.eq(1990),
+                ))
+                .returning(participants::id)
+                .get_result::<i32>(conn)?;
+            QueryResult::Ok((max, tom, competition_id, second_max))
+        })
+        .await
+        .unwrap();
+
+    // participants inserted directly are not linked to persons yet
+    let status = post_form(&router, &cookie, "/admin/persons/link", &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    for (competition, participant, finish_time) in [
+        (1, tom, "2026-02-18T11:30:00"),
+        (1, max, "2026-02-18T11:35:00"),
+        (second_competition, second_max, "2026-09-01T10:40:00"),
+    ] {
+        let status = post_form(
+            &router,
+            &cookie,
+            &format!("/admin/competitions/{competition}/time_records"),
+            &[
+                ("participant_id", &participant.to_string()),
+                ("finish_time", finish_time),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/series",
+        &[
+            ("name", "Cup"),
+            ("points_table", "100, 90, 80"),
+            ("counting_results", "1"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let series_id = state
+        .with_connection(|conn| series::table.select(series::id).first::<i32>(conn))
+        .await
+        .unwrap();
+    for competition in [1, second_competition] {
+        let status = post_form(
+            &router,
+            &cookie,
+            &format!("/admin/series/{series_id}/competitions"),
+            &[("competition_id", &competition.to_string())],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) =
+        get_page(&router, "", &format!("/series/{series_id}/standings.html")).await;
+    assert_eq!(status, StatusCode::OK);
+    let category = &page[page.find("M 21").unwrap()..];
+    // both spellings belong to the same person, only the best result counts
+    assert_eq!(category.matches("Miller").count(), 1, "{page}");
+    assert!(category.contains("(90)"), "{page}");
+    assert!(
+        category.find("Miller").unwrap() < category.find("Tester").unwrap(),
+        "{page}"
+    );
+}
+
+#[tokio::test]
+async fn persons_can_be_merged_and_split() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let participants = state
+        .with_connection(|conn| {
+            let mut ids = Vec::new();
+            for (first_name, email) in [
+                ("Eva", Some("eva@example.com")),
+                ("eva", Some("other@example.com")),
+                ("EVA", None),
+            ] {
+                let id = insert_participant(conn, first_name, "Example", "M 21")?;
+                diesel::update(participants::table.find(id))
+                    .set(participants::email.eq(email))
+                    .execute(conn)?;
+                ids.push(id);
+            }
+            QueryResult::Ok(ids)
+        })
+        .await
+        .unwrap();
+
+    // listing the persons does not change them, unlinked participants are
+    // linked on request
+    let (status, page) = get_page(&router, &cookie, "/admin/persons.html?q=eva").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Registrations without a person"), "{page}");
+    assert!(!page.contains("other@example.com"), "{page}");
+    let status = post_form(&router, &cookie, "/admin/persons/link", &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, &cookie, "/admin/persons.html?q=eva").await;
+    assert!(!page.contains("Registrations without a person"), "{page}");
+    assert!(page.contains("other@example.com"), "{page}");
+
+    let person_of = |participant: i32| {
+        let state = state.clone();
+        async move {
+            state
+                .with_connection(move |conn| {
+                    participants::table
+                        .find(participant)
+                        .select(participants::person_id.assume_not_null())
+                        .first::<i32>(conn)
+                })
+                .await
+                .unwrap()
+        }
+    };
+    let eva = person_of(participants[0]).await;
+    let other = person_of(participants[1]).await;
+    // different email addresses are different persons, no email matches the first one
+    assert_ne!(eva, other);
+    assert_eq!(person_of(participants[2]).await, eva);
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/persons/{eva}/merge"),
+        &[("other_person_id", &other.to_string())],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert_eq!(person_of(participants[1]).await, eva);
+    let remaining = state
+        .with_connection(move |conn| persons::table.find(other).count().get_result::<i64>(conn))
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+    let (status, page) =
+        get_page(&router, &cookie, &format!("/admin/persons/{eva}/edit.html")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("other@example.com"), "{page}");
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/persons/{eva}/split"),
+        &[("participant_id", &participants[2].to_string())],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    assert_ne!(person_of(participants[2]).await, eva);
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &participants[0].to_string()),
+            ("finish_time", "2026-02-18T11:35:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(
+        page.contains(&format!("/persons/{eva}/history.html")),
+        "{page}"
+    );
+    let (status, page) = get_page(&router, "", &format!("/persons/{eva}/history.html")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("London City Marathon"), "{page}");
+    assert!(page.contains("45:00"), "{page}");
+}
+
+#[tokio::test]
+async fn results_are_age_graded() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (young, old) = state
+        .with_connection(|conn| {
+            diesel::update(races::table.filter(races::name.eq("11km")))
+                .set(races::distance_meters.eq(11000))
+                .execute(conn)?;
+            let young = insert_participant(conn, "Young", "Runner", "M 21")?;
+            let old = insert_participant(conn, "Old", "Runner", "M 21")?;
+            diesel::update(participants::table.find(old))
+                .set(participants::birth_year.eq(1961))
+                .execute(conn)?;
+            QueryResult::Ok((young, old))
+        })
+        .await
+        .unwrap();
+    for (participant, finish_time) in [(young, "2026-02-18T11:35:00"), (old, "2026-02-18T11:40:00")]
+    {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_records",
+            &[
+                ("participant_id", &participant.to_string()),
+                ("finish_time", finish_time),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) = get_page(&router, "", "/1/results.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        page.find("Young").unwrap() < page.find("Old").unwrap(),
+        "{page}"
+    );
+    assert!(page.contains("NOT the official WMA"), "{page}");
+    // the older runner wins the age graded ranking
+    let graded = &page[page.find("Age graded ranking").unwrap()..];
+    assert!(
+        graded.find("Old").unwrap() < graded.find("Young").unwrap(),
+        "{page}"
+    );
+    assert!(graded.contains(" %"), "{page}");
+
+    // distances far from the tables are not graded
+    state
+        .with_connection(|conn| {
+            diesel::update(races::table.filter(races::name.eq("11km")))
+                .set(races::distance_meters.eq(400))
+                .execute(conn)
+        })
+        .await
+        .unwrap();
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("Old"), "{page}");
+    assert!(!page.contains("Age graded ranking"), "{page}");
+}
+
+#[tokio::test]
+async fn results_show_pace_and_speed() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let max = state
+        .with_connection(|conn| {
+            diesel::update(races::table.filter(races::name.eq("11km")))
+                .set((races::distance_meters.eq(10000), races::surface.eq("road")))
+                .execute(conn)?;
+            insert_participant(conn, "Max", "Miller", "M 21")
+        })
+        .await
+        .unwrap();
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &max.to_string()),
+            ("finish_time", "2026-02-18T11:35:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("04:30 min/km"), "{page}");
+    assert!(page.contains("13.3 km/h"), "{page}");
+}
+
+#[tokio::test]
+async fn time_adjustments_are_applied_to_results() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let max = state
+        .with_connection(|conn| insert_participant(conn, "Max", "Miller", "M 21"))
+        .await
+        .unwrap();
+    let max_id = max.to_string();
+    // the 11km race starts at 10:50
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &max_id),
+            ("finish_time", "2026-02-18T11:35:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // adjustments need a reason
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_adjustments",
+        &[
+            ("participant_id", &max_id),
+            ("delta_seconds", "30"),
+            ("reason", " "),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for (delta, reason) in [("60", "Cut the course"), ("-30.5", "Stopwatch delay")] {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_adjustments",
+            &[
+                ("participant_id", &max_id),
+                ("delta_seconds", delta),
+                ("reason", reason),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("45:29.5"), "{page}");
+    assert!(page.contains("adjusted"), "{page}");
+
+    // the recorded time stays untouched and the adjustments keep who changed it
+    let (_, page) = get_page(&router, &cookie, "/admin/competitions/1/time_records.html").await;
+    assert!(page.contains("45:00"), "{page}");
+    assert!(page.contains("+00:29.5"), "{page}");
+    let adjustments = state
+        .with_connection(move |conn| {
+            time_adjustments::table
+                .filter(time_adjustments::participant_id.eq(max))
+                .order_by(time_adjustments::id)
+                .select((
+                    time_adjustments::delta_ms,
+                    time_adjustments::reason,
+                    time_adjustments::created_by,
+                ))
+                .load::<(i64, String, String)>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        adjustments,
+        [
+            (
+                60_000,
+                String::from("Cut the course"),
+                String::from("admin")
+            ),
+            (
+                -30_500,
+                String::from("Stopwatch delay"),
+                String::from("admin")
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn published_results_are_versioned() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (race_id, max) = state
+        .with_connection(|conn| {
+            let race_id = races::table
+                .filter(races::name.eq("11km"))
+                .select(races::id)
+                .first::<i32>(conn)?;
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            QueryResult::Ok((race_id, max))
+        })
+        .await
+        .unwrap();
+    let max_id = max.to_string();
+    // the 11km race starts at 10:50
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &max_id),
+            ("finish_time", "2026-02-18T11:35:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("Provisional results"), "{page}");
+
+    let publish = format!("/admin/races/{race_id}/result_versions");
+    let status = post_form(&router, &cookie, &publish, &[("note", "")]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    // unchanged results are not published again
+    let status = post_form(&router, &cookie, &publish, &[("note", "again")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // later changes only show up after publishing a new version
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_adjustments",
+        &[
+            ("participant_id", &max_id),
+            ("delta_seconds", "60"),
+            ("reason", "Cut the course"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("Official results"), "{page}");
+    assert!(page.contains("45:00"), "{page}");
+    assert!(!page.contains("46:00"), "{page}");
+    // the history of the person shows the published version as well
+    let status = post_form(&router, &cookie, "/admin/persons/link", &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let person = state
+        .with_connection(move |conn| {
+            participants::table
+                .find(max)
+                .select(participants::person_id.assume_not_null())
+                .first::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    let history = format!("/persons/{person}/history.html");
+    let (_, page) = get_page(&router, "", &history).await;
+    assert!(page.contains("45:00"), "{page}");
+    assert!(!page.contains("46:00"), "{page}");
+
+    let (status, page) = get_page(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/result_versions.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Unpublished changes"), "{page}");
+
+    // a new version needs a note
+    let status = post_form(&router, &cookie, &publish, &[("note", " ")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = post_form(&router, &cookie, &publish, &[("note", "Time penalty")]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("46:00"), "{page}");
+    assert!(page.contains("Version 2"), "{page}");
+    let (_, page) = get_page(&router, "", &history).await;
+    assert!(page.contains("46:00"), "{page}");
+
+    let (status, page) = get_page(
+        &router,
+        "",
+        &format!("/races/{race_id}/result_versions.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Time penalty"), "{page}");
+    assert!(page.contains("Miller"), "{page}");
+    assert!(page.contains("(46:00)"), "{page}");
+
+    // earlier versions stay available
+    let (status, page) = get_page(
+        &router,
+        "",
+        &format!("/races/{race_id}/result_versions/1/results.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Outdated results"), "{page}");
+    assert!(page.contains("45:00"), "{page}");
+    assert!(!page.contains("46:00"), "{page}");
+    let (status, _) = get_page(
+        &router,
+        "",
+        &format!("/races/{race_id}/result_versions/3/results.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // versions published in an older format, here without bib and category
+    // ids, stay readable for all pages based on the shown results
+    state
+        .with_connection(move |conn| {
+            use race_timing::database::schema::result_versions;
+            let stored = result_versions::table
+                .filter(result_versions::race_id.eq(race_id))
+                .filter(result_versions::version.eq(2))
+                .select(result_versions::results)
+                .first::<String>(conn)?;
+            let mut results = serde_json::from_str::<serde_json::Value>(&stored).unwrap();
+            for participant in results["participants"].as_array_mut().unwrap() {
+                let participant = participant.as_object_mut().unwrap();
+                participant.remove("bib");
+                participant.remove("category_id");
+            }
+            diesel::update(result_versions::table)
+                .filter(result_versions::race_id.eq(race_id))
+                .filter(result_versions::version.eq(2))
+                .set(result_versions::results.eq(results.to_string()))
+                .execute(conn)
+        })
+        .await
+        .unwrap();
+    for uri in [
+        "/1/results.html",
+        "/1/club_results.html",
+        "/admin/competitions/1/awards.html",
+    ] {
+        let (status, page) = get_page(&router, &cookie, uri).await;
+        assert_eq!(status, StatusCode::OK, "{uri}: {page}");
+    }
+}
+
+#[tokio::test]
+async fn correction_requests_are_accepted_or_rejected() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (max, other_category) = state
+        .with_connection(|conn| {
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            let other_category = categories::table
+                .inner_join(starts::table.inner_join(races::table))
+                .filter(races::name.eq("11km"))
+                .filter(categories::label.ne("M 21"))
+                .select(categories::id)
+                .first::<i32>(conn)?;
+            QueryResult::Ok((max, other_category))
+        })
+        .await
+        .unwrap();
+    let max_id = max.to_string();
+    // the 11km race starts at 10:50
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &max_id),
+            ("finish_time", "2026-02-18T11:35:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (status, page) = get_page(
+        &router,
+        "",
+        &format!("/participants/{max}/correction_request.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Miller"), "{page}");
+
+    // requests are filed without login, but need a reason, contact data and a valid time
+    let file = format!("/participants/{max}/correction_request");
+    for (kind, time, reason, contact) in [
+        ("time", "44:30", "", "max@example.com"),
+        ("time", "44:30", "Watch says 44:30", " "),
+        ("time", "44:75", "Watch says 44:30", "max@example.com"),
+        ("time", "", "Watch says 44:30", "max@example.com"),
+    ] {
+        let status = post_form(
+            &router,
+            "",
+            &file,
+            &[
+                ("kind", kind),
+                ("requested_time", time),
+                ("requested_category_id", ""),
+                ("reason", reason),
+                ("contact", contact),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{kind} {time} {reason}");
+    }
+    let category = other_category.to_string();
+    for (kind, time, category) in [("time", "44:30", ""), ("category", "", category.as_str())] {
+        let status = post_form(
+            &router,
+            "",
+            &file,
+            &[
+                ("kind", kind),
+                ("requested_time", time),
+                ("requested_category_id", category),
+                ("reason", "Something is wrong"),
+                ("contact", "max@example.com"),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    let (status, page) = get_page(&router, &cookie, "/admin/correction_requests.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("max@example.com"), "{page}");
+    assert!(page.contains("44:30"), "{page}");
+
+    let requests = state
+        .with_connection(|conn| {
+            correction_requests::table
+                .order_by(correction_requests::id)
+                .select(correction_requests::id)
+                .load::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    let [time_request, category_request] = requests[..] else {
+        panic!("Expected two requests, got {requests:?}");
+    };
+
+    // accepting a time correction adds a time adjustment
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/correction_requests/{time_request}/accept"),
+        &[("comment", "")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, "", "/1/results.html").await;
+    assert!(page.contains("44:30"), "{page}");
+    // a request is only handled once
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/correction_requests/{time_request}/reject"),
+        &[("comment", "Too late")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // rejections need a comment and keep the category
+    let reject = format!("/admin/correction_requests/{category_request}/reject");
+    let status = post_form(&router, &cookie, &reject, &[("comment", "")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = post_form(
+        &router,
+        &cookie,
+        &reject,
+        &[("comment", "Birth year is correct")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let (category_id, statuses) = state
+        .with_connection(move |conn| {
+            let category_id = participants::table
+                .find(max)
+                .select(participants::category_id)
+                .first::<i32>(conn)?;
+            let statuses = correction_requests::table
+                .order_by(correction_requests::id)
+                .select(correction_requests::status)
+                .load::<String>(conn)?;
+            QueryResult::Ok((category_id, statuses))
+        })
+        .await
+        .unwrap();
+    assert_ne!(category_id, other_category);
+    assert_eq!(statuses, ["accepted", "rejected"]);
+    let (_, page) = get_page(&router, &cookie, "/admin/correction_requests.html").await;
+    assert!(page.contains("Birth year is correct"), "{page}");
+
+    // accepting a category correction moves the participant
+    let status = post_form(
+        &router,
+        "",
+        &file,
+        &[
+            ("kind", "category"),
+            ("requested_time", ""),
+            ("requested_category_id", &category),
+            ("reason", "Wrong birth year"),
+            ("contact", "max@example.com"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/correction_requests/{}/accept", category_request + 1),
+        &[("comment", "")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let category_id = state
+        .with_connection(move |conn| {
+            participants::table
+                .find(max)
+                .select(participants::category_id)
+                .first::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(category_id, other_category);
+}
+
+#[tokio::test]
+async fn award_lists_merge_small_categories() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (finishers, m21, m31) = state
+        .with_connection(|conn| {
+            let finishers = [
+                insert_participant(conn, "Anton", "Fast", "M 21")?,
+                insert_participant(conn, "Bernd", "Steady", "M 21")?,
+                insert_participant(conn, "Carl", "Slow", "M 21")?,
+                insert_participant(conn, "Dieter", "Senior", "M 31")?,
+            ];
+            let category_id = |conn: &mut SqliteConnection, label: &str| {
+                categories::table
+                    .inner_join(starts::table)
+                    .filter(starts::name.eq("11km"))
+                    .filter(categories::label.eq(label))
+                    .select(categories::id)
+                    .first::<i32>(conn)
+            };
+            let m21 = category_id(conn, "M 21")?;
+            let m31 = category_id(conn, "M 31")?;
+            // spreadsheets must not evaluate the club as a formula
+            diesel::update(participants::table.find(finishers[3]))
+                .set(participants::club.eq("=HYPERLINK(\"x\",\"y\")"))
+                .execute(conn)?;
+            QueryResult::Ok((finishers, m21, m31))
+        })
+        .await
+        .unwrap();
+    // the 11km race starts at 10:50
+    for (participant, finish_time) in finishers.iter().zip([
+        "2026-02-18T11:30:00",
+        "2026-02-18T11:35:00",
+        "2026-02-18T11:40:00",
+        "2026-02-18T11:32:00",
+    ]) {
+        let status = post_form(
+            &router,
+            &cookie,
+            "/admin/competitions/1/time_records",
+            &[
+                ("participant_id", &participant.to_string()),
+                ("finish_time", finish_time),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER);
+    }
+
+    // by default the top 3 of each category are awarded
+    let (status, page) = get_page(&router, &cookie, "/admin/competitions/1/awards.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Slow"), "{page}");
+    assert!(!page.contains("M 21 &#x2f; M 31"), "{page}");
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/award_settings",
+        &[("places", "0"), ("min_finishers", "2")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (m21, m31) = (format!("category_{m21}"), format!("category_{m31}"));
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/award_settings",
+        &[
+            ("places", "2"),
+            ("min_finishers", "2"),
+            (&m21, "2"),
+            (&m31, ""),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (status, page) = get_page(
+        &router,
+        &cookie,
+        "/admin/competitions/1/award_settings.html",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        page.contains(&format!("name=\"{m21}\"  value=\"2\"")),
+        "{page}"
+    );
+
+    // M 31 has a single finisher and is merged with M 21, only 2 places are awarded
+    let (status, page) = get_page(&router, &cookie, "/admin/competitions/1/awards.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("M 21 &#x2f; M 31"), "{page}");
+    assert!(page.contains("Fast"), "{page}");
+    assert!(page.contains("Senior"), "{page}");
+    assert!(!page.contains("Steady"), "{page}");
+    assert!(!page.contains("Slow"), "{page}");
+
+    let (status, csv) = get_page(&router, &cookie, "/admin/competitions/1/awards.csv").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        csv.contains("11km,M 21 / M 31,1,,Anton,Fast,,40:00"),
+        "{csv}"
+    );
+    assert!(
+        csv.contains(
+            "11km,M 21 / M 31,2,,Dieter,Senior,\"'=HYPERLINK(\"\"x\"\",\"\"y\"\")\",42:00"
+        ),
+        "{csv}"
+    );
+
+    let resp = router
+        .clone()
+        .oneshot(
+            Request::get("/admin/competitions/1/awards.pdf")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/pdf");
+    let pdf = resp.into_body().collect().await.unwrap().to_bytes();
+    assert!(pdf.starts_with(b"%PDF-"));
+}
+
+// a minimal SMTP server accepting a single mail
+//
+// returns the port and the received mail data
+async fn fake_smtp_server() -> (u16, tokio::task::JoinHandle<String>) {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 Queued\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let response: &[u8] = match line.split(' ').next().unwrap_or_default() {
+                "EHLO" | "HELO" => b"250 localhost\r\n",
+                "DATA" => {
+                    in_data = true;
+                    b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 OK\r\n",
+            };
+            writer.write_all(response).await.unwrap();
+        }
+        data
+    });
+    (port, server)
+}
+
+#[tokio::test]
+async fn mails_are_sent_via_smtp_or_logged_to_a_file() {
+    use race_timing::mail::Mail;
+
+    let mail = Mail {
+        to: "max@example.com".into(),
+        subject: "Registration for the City Run".into(),
+        body: "Hello Max Miller".into(),
+    };
+
+    // mails are only sent unencrypted if that is configured explicitly
+    let (port, _server) = fake_smtp_server().await;
+    let config = Config {
+        smtp_server: Some("127.0.0.1".into()),
+        smtp_port: Some(port),
+        public_url: Some("https://timing.example.com".into()),
+        ..test_config(false)
+    };
+    let (_router, state) = race_timing::setup(config).await;
+    assert!(state.mailer.send(&mail).await.is_err());
+
+    let (port, server) = fake_smtp_server().await;
+    let config = Config {
+        smtp_server: Some("127.0.0.1".into()),
+        smtp_port: Some(port),
+        smtp_insecure: true,
+        public_url: Some("https://timing.example.com".into()),
+        mail_from: "registration@example.com".into(),
+        ..test_config(false)
+    };
+    let (_router, state) = race_timing::setup(config).await;
+    state.mailer.send(&mail).await.unwrap();
+    drop(state);
+    let data = tokio::time::timeout(std::time::Duration::from_secs(10), server)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(data.contains("From: registration@example.com"), "{data}");
+    assert!(data.contains("To: max@example.com"), "{data}");
+    assert!(
+        data.contains("Subject: Registration for the City Run"),
+        "{data}"
+    );
+    assert!(data.contains("Hello Max Miller"), "{data}");
+
+    // without SMTP server the mails end up in the mail file
+    let mail_file =
+        std::env::temp_dir().join(format!("race_timing_mails_{}.txt", std::process::id()));
+    let config = Config {
+        mail_file: Some(mail_file.clone()),
+        ..test_config(false)
+    };
+    let (_router, state) = race_timing::setup(config).await;
+    state.mailer.send(&mail).await.unwrap();
+    let logged = std::fs::read_to_string(&mail_file).unwrap();
+    std::fs::remove_file(&mail_file).unwrap();
+    assert!(logged.contains("To: max@example.com"), "{logged}");
+    assert!(logged.contains("Hello Max Miller"), "{logged}");
+}
+
+// the self-service token for a participant signed with the given secret
+fn registration_token(secret: &[u8], participant_id: i32) -> String {
+    use hmac::{Hmac, Mac};
+
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret).unwrap();
+    mac.update(format!("registration:{participant_id}").as_bytes());
+    let signature = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    format!("{participant_id}-{signature}")
+}
+
+#[tokio::test]
+async fn registrations_are_cancelled_via_signed_links() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let secret = b"test secret".to_vec();
+    let (max, erika) = state
+        .with_connection({
+            let secret = secret.clone();
+            move |conn| {
+                diesel::insert_into(secrets::table)
+                    .values((
+                        secrets::name.eq("registration_token"),
+                        secrets::value.eq(secret),
+                    ))
+                    .execute(conn)?;
+                let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+                let erika = insert_participant(conn, "Erika", "Runner", "W 21")?;
+                QueryResult::Ok((max, erika))
+            }
+        })
+        .await
+        .unwrap();
+    let token = registration_token(&secret, max);
+
+    let (status, page) = get_page(
+        &router,
+        "",
+        &format!("/1/my_registration/{token}/cancel.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("Miller"), "{page}");
+
+    // tokens cannot be guessed or used for other participants and competitions
+    let forged = registration_token(b"other secret", max);
+    let swapped = token.replacen(&max.to_string(), &erika.to_string(), 1);
+    for uri in [
+        format!("/1/my_registration/{forged}/cancel.html"),
+        format!("/1/my_registration/{swapped}/cancel.html"),
+        format!("/1/my_registration/{max}/cancel.html"),
+        format!("/2/my_registration/{token}/cancel.html"),
+    ] {
+        let (status, _) = get_page(&router, "", &uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+    }
+
+    // finishers cannot cancel their registration
+    let erika_token = registration_token(&secret, erika);
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/time_records",
+        &[
+            ("participant_id", &erika.to_string()),
+            ("finish_time", "2026-02-18T11:35:00"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let status = post_form(
+        &router,
+        "",
+        &format!("/1/my_registration/{erika_token}/cancel"),
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let cancel = format!("/1/my_registration/{token}/cancel");
+    let status = post_form(&router, "", &cancel, &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let remaining = state
+        .with_connection(move |conn| {
+            participants::table
+                .filter(participants::id.eq_any([max, erika]))
+                .select(participants::id)
+                .load::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(remaining, vec![erika]);
+    let status = post_form(&router, "", &cancel, &[]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn waitlist_is_promoted_on_cancellation() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let secret = b"test secret".to_vec();
+    let (max, anna, ben) = state
+        .with_connection({
+            let secret = secret.clone();
+            move |conn| {
+                diesel::insert_into(secrets::table)
+                    .values((
+                        secrets::name.eq("registration_token"),
+                        secrets::value.eq(secret),
+                    ))
+                    .execute(conn)?;
+                let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+                let anna = insert_participant(conn, "Anna", "Waiting", "W 21")?;
+                let ben = insert_participant(conn, "Ben", "Waiting", "M 31")?;
+                for (id, waitlisted_at) in [
+                    (anna, time::macros::datetime!(2026-02-01 10:00:00)),
+                    (ben, time::macros::datetime!(2026-02-01 11:00:00)),
+                ] {
+                    diesel::update(participants::table.find(id))
+                        .set(participants::waitlisted_at.eq(waitlisted_at))
+                        .execute(conn)?;
+                }
+                // the race is full with the confirmed participants
+                let race_id = starts::table
+                    .filter(starts::name.eq("11km"))
+                    .select(starts::race_id)
+                    .first::<i32>(conn)?;
+                let confirmed = participants::table
+                    .inner_join(categories::table.inner_join(starts::table))
+                    .filter(starts::race_id.eq(race_id))
+                    .filter(participants::waitlisted_at.is_null())
+                    .count()
+                    .get_result::<i64>(conn)?;
+                diesel::update(races::table.find(race_id))
+                    .set(races::capacity.eq(i32::try_from(confirmed).unwrap()))
+                    .execute(conn)?;
+                QueryResult::Ok((max, anna, ben))
+            }
+        })
+        .await
+        .unwrap();
+
+    let token = registration_token(&secret, max);
+    let status = post_form(
+        &router,
+        "",
+        &format!("/1/my_registration/{token}/cancel"),
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let waitlisted = state
+        .with_connection(move |conn| {
+            participants::table
+                .filter(participants::id.eq_any([anna, ben]))
+                .order_by(participants::id)
+                .select((participants::id, participants::waitlisted_at.is_not_null()))
+                .load::<(i32, bool)>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(waitlisted, vec![(anna, false), (ben, true)]);
+
+    // registrations are only accepted while the registration is open
+    let form = [
+        ("lastname", "Late"),
+        ("firstname", "Lisa"),
+        ("club", ""),
+        ("consent", "on"),
+        ("age", "1990"),
+        ("male", "false"),
+        ("race", "1"),
+        ("email", "lisa@example.com"),
+    ];
+    for (opens, closes) in [
+        (Some(time::macros::datetime!(2099-01-01 00:00:00)), None),
+        (None, Some(time::macros::datetime!(2020-01-01 00:00:00))),
+    ] {
+        state
+            .with_connection(move |conn| {
+                diesel::update(competitions::table.find(1))
+                    .set((
+                        competitions::registration_opens.eq(opens),
+                        competitions::registration_closes.eq(closes),
+                    ))
+                    .execute(conn)
+            })
+            .await
+            .unwrap();
+        let status = post_form(&router, "", "/1/participant/", &form).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        // registrations cannot be changed either
+        let token = registration_token(&secret, anna);
+        let status = post_form(&router, "", &format!("/1/my_registration/{token}"), &form).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    // a race cannot have a negative capacity
+    let cookie = login(&router).await;
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/new_race",
+        &[("name", "Negative"), ("capacity", "-1")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn duplicate_registrations_are_detected_and_merged() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let (max, duplicate) = state
+        .with_connection(move |conn| {
+            let max = insert_participant(conn, "Max", "Miller", "M 21")?;
+            let duplicate = insert_participant(conn, "max", "MILLER", "M 21")?;
+            for (id, club) in [(max, "LG Test"), (duplicate, "lg-test")] {
+                diesel::update(participants::table.find(id))
+                    .set(participants::club.eq(club))
+                    .execute(conn)?;
+            }
+            QueryResult::Ok((max, duplicate))
+        })
+        .await
+        .unwrap();
+
+    // the same athlete cannot register twice
+    let status = post_form(
+        &router,
+        "",
+        "/1/participant/",
+        &[
+            ("lastname", "Miller "),
+            ("firstname", "Max"),
+            ("club", "LG  Test"),
+            ("consent", "on"),
+            ("age", "1990"),
+            ("male", "true"),
+            ("race", "1"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, page) = get_page(&router, &cookie, "/admin/competitions/1/duplicates.html").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        page.contains(&format!(r#"name="from" value="{duplicate}""#)),
+        "{page}"
+    );
+
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/duplicates/merge",
+        &[("into", &max.to_string()), ("from", "9999")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/duplicates/merge",
+        &[("into", &max.to_string()), ("from", &duplicate.to_string())],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let remaining = state
+        .with_connection(move |conn| {
+            participants::table
+                .filter(participants::id.eq_any([max, duplicate]))
+                .select(participants::id)
+                .load::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(remaining, vec![max]);
+
+    let (_, page) = get_page(&router, &cookie, "/admin/competitions/1/duplicates.html").await;
+    assert!(!page.contains(r#"name="from""#), "{page}");
+
+    // merging a duplicate of another race frees a spot in that race
+    let (paul, walker, nora) = state
+        .with_connection(move |conn| {
+            let paul = insert_participant(conn, "Paul", "Walker", "M 21")?;
+            let nordic = |conn: &mut SqliteConnection, label: &str| {
+                categories::table
+                    .inner_join(starts::table)
+                    .filter(starts::name.eq("5,5km Nordic Walking"))
+                    .filter(categories::label.eq(label))
+                    .select((categories::id, starts::race_id))
+                    .first::<(i32, i32)>(conn)
+            };
+            let (men, race_id) = nordic(conn, "Men")?;
+            let (women, _) = nordic(conn, "Woman")?;
+            let mut ids = Vec::new();
+            for (first_name, category_id, waitlisted_at) in [
+                ("Paul", men, None),
+                (
+                    "Nora",
+                    women,
+                    Some(time::macros::datetime!(2026-02-01 10:00:00)),
+                ),
+            ] {
+                ids.push(
+                    diesel::insert_into(participants::table)
+                        .values((
+                            participants::first_name.eq(first_name),
+                            participants::last_name.eq("Walker"),
+                            participants::category_id.eq(category_id),
+                            participants::consent_agb.eq(true),
+                            participants::birth_year.eq(1990),
+                            participants::waitlisted_at.eq(waitlisted_at),
+                        ))
+                        .returning(participants::id)
+                        .get_result::<i32>(conn)?,
+                );
+            }
+            diesel::update(races::table.find(race_id))
+                .set(races::capacity.eq(1))
+                .execute(conn)?;
+            QueryResult::Ok((paul, ids[0], ids[1]))
+        })
+        .await
+        .unwrap();
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/duplicates/merge",
+        &[("into", &paul.to_string()), ("from", &walker.to_string())],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let nora_waitlisted = state
+        .with_connection(move |conn| {
+            participants::table
+                .find(nora)
+                .select(participants::waitlisted_at.is_not_null())
+                .first::<bool>(conn)
+        })
+        .await
+        .unwrap();
+    assert!(!nora_waitlisted);
+}
+
+#[tokio::test]
+async fn entry_fees_are_computed_and_payments_tracked() {
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    let secret = b"test secret".to_vec();
+    let (race_id, anna, youth, member) = state
+        .with_connection({
+            let secret = secret.clone();
+            move |conn| {
+                diesel::insert_into(secrets::table)
+                    .values((
+                        secrets::name.eq("registration_token"),
+                        secrets::value.eq(secret),
+                    ))
+                    .execute(conn)?;
+                diesel::insert_into(clubs::table)
+                    .values(clubs::name.eq("LG Fee"))
+                    .execute(conn)?;
+                let anna = insert_participant(conn, "Anna", "Payer", "W 21")?;
+                let youth = insert_participant(conn, "Jonas", "Junior", "M 21")?;
+                let member = insert_participant(conn, "Clara", "Member", "W 21")?;
+                // registered before the late surcharge applies
+                diesel::update(participants::table.find(youth))
+                    .set((
+                        participants::birth_year.eq(2012),
+                        participants::registered_at
+                            .eq(time::macros::datetime!(2019-06-01 12:00:00)),
+                    ))
+                    .execute(conn)?;
+                diesel::update(participants::table.find(member))
+                    .set(participants::club.eq("lg-fee"))
+                    .execute(conn)?;
+                let race_id = starts::table
+                    .filter(starts::name.eq("11km"))
+                    .select(starts::race_id)
+                    .first::<i32>(conn)?;
+                QueryResult::Ok((race_id, anna, youth, member))
+            }
+        })
+        .await
+        .unwrap();
+
+    let fees = format!("/admin/races/{race_id}/fees");
+    let status = post_form(&router, &cookie, &fees, &[("base_fee", "25.x")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = post_form(
+        &router,
+        &cookie,
+        &fees,
+        &[
+            ("base_fee", "25"),
+            ("late_surcharge", "5,00"),
+            ("late_from", "2020-01-01T00:00"),
+            ("youth_discount", "10"),
+            ("youth_max_age", "17"),
+            ("club_discount", "2.5"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    // the amounts are computed on request for participants registered before
+    // the fee schedule was set up, viewing the payments does not change them
+    let payments_page = "/admin/competitions/1/payments.html?status=open";
+    let (status, page) = get_page(&router, &cookie, payments_page).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(
+        page.contains("Registrations without an entry fee"),
+        "{page}"
+    );
+    assert!(!page.contains("Payer"), "{page}");
+    let status = post_form(&router, &cookie, "/admin/competitions/1/missing_fees", &[]).await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (_, page) = get_page(&router, &cookie, payments_page).await;
+    assert!(
+        !page.contains("Registrations without an entry fee"),
+        "{page}"
+    );
+    let amounts = state
+        .with_connection(move |conn| {
+            payments::table
+                .filter(payments::participant_id.eq_any([anna, youth, member]))
+                .order_by(payments::participant_id)
+                .select(payments::amount_due)
+                .load::<i32>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(amounts, vec![3000, 1500, 2750]);
+    assert!(page.contains("Payer"), "{page}");
+
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/competitions/1/payments/{anna}"),
+        &[
+            ("status", "paid"),
+            ("method", "cash"),
+            ("reference", ""),
+            ("filter", "open"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let (method, paid_at) = state
+        .with_connection(move |conn| {
+            payments::table
+                .find(anna)
+                .select((payments::method, payments::paid_at.is_not_null()))
+                .first::<(Option<String>, bool)>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(method.as_deref(), Some("cash"));
+    assert!(paid_at);
+    let (_, page) = get_page(&router, &cookie, payments_page).await;
+    assert!(!page.contains("Payer"), "{page}");
+    assert!(page.contains("Junior"), "{page}");
+
+    // payments of other competitions cannot be changed via this competition
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/competitions/2/payments/{anna}"),
+        &[("status", "refunded")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let token = registration_token(&secret, youth);
+    let (status, page) = get_page(
+        &router,
+        "",
+        &format!("/1/my_registration/{token}/confirmation.html"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(page.contains("15.00"), "{page}");
+
+    // paid registrations are kept for the records of the organizer
+    let token = registration_token(&secret, anna);
+    let status = post_form(
+        &router,
+        "",
+        &format!("/1/my_registration/{token}/cancel"),
+        &[],
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // a merge keeps the paid payment instead of the open one of `into`
+    let status = post_form(
+        &router,
+        &cookie,
+        "/admin/competitions/1/duplicates/merge",
+        &[("into", &member.to_string()), ("from", &anna.to_string())],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let kept = state
+        .with_connection(move |conn| {
+            payments::table
+                .filter(payments::participant_id.eq_any([anna, member]))
+                .select((payments::participant_id, payments::method))
+                .load::<(i32, Option<String>)>(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(kept, vec![(member, Some(String::from("cash")))]);
+}
+
+#[tokio::test]
+async fn bib_numbers_are_assigned_from_the_range_of_the_start() {
+    use race_timing::database::schema::bib_numbers;
+
+    let (router, state) = race_timing::setup(test_config(true)).await;
+    let cookie = login(&router).await;
+    // men and women start separately, each start with its own range, the
+    // men's range overlaps with a bib that is already taken
+    let race_id = state
+        .with_connection(|conn| {
+            let race_id = diesel::insert_into(races::table)
+                .values((
+                    races::name.eq("Relay"),
+                    races::competition_id.eq(1),
+                    races::team_size.eq(1),
+                ))
+                .returning(races::id)
+                .get_result::<i32>(conn)?;
+            for (name, male, first_bib, last_bib) in
+                [("Men", true, 600, 602), ("Women", false, 800, 899)]
+            {
+                let start_id = diesel::insert_into(starts::table)
+                    .values((
+                        starts::name.eq(name),
+                        starts::time.eq(time::macros::datetime!(2026-02-18 10:00:00)),
+                        starts::race_id.eq(race_id),
+                        starts::first_bib.eq(first_bib),
+                        starts::last_bib.eq(last_bib),
+                    ))
+                    .returning(starts::id)
+                    .get_result::<i32>(conn)?;
+                diesel::insert_into(categories::table)
+                    .values((
+                        categories::label.eq(name),
+                        categories::from_age.eq(0),
+                        categories::to_age.eq(99),
+                        categories::male.eq(male),
+                        categories::start_id.eq(start_id),
+                    ))
+                    .execute(conn)?;
+            }
+            let taken = diesel::select(diesel::dsl::exists(
+                bib_numbers::table.filter(bib_numbers::bib.eq(600)),
+            ))
+            .get_result::<bool>(conn)?;
+            assert!(taken);
+            QueryResult::Ok(race_id)
+        })
+        .await
+        .unwrap();
+    let status = post_form(
+        &router,
+        &cookie,
+        &format!("/admin/races/{race_id}/team_categories"),
+        &[
+            ("label", "Open"),
+            ("composition", "any"),
+            ("from_combined_age", "0"),
+            ("to_combined_age", "200"),
+        ],
+    )
+    .await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+
+    let race = race_id.to_string();
+    for (name, male) in [
+        ("Anna", "false"),
+        ("Ben", "true"),
+        ("Carl", "true"),
+        ("Dan", "true"),
+    ] {
+        let status = post_form(
+            &router,
+            "",
+            "/1/team/",
+            &[
+                ("race", &race),
+                ("team_name", name),
+                ("consent", "on"),
+                ("firstname_1", name),
+                ("lastname_1", "Bib"),
+                ("age_1", "1990"),
+                ("male_1", male),
+            ],
+        )
+        .await;
+        assert_eq!(status, StatusCode::SEE_OTHER, "{name}");
+    }
+    let bibs = state
+        .with_connection(|conn| {
+            participants::table
+                .left_join(bib_numbers::table)
+                .filter(participants::last_name.eq("Bib"))
+                .order_by(participants::id)
+                .select((participants::first_name, bib_numbers::bib.nullable()))
+                .load::<(String, Option<i32>)>(conn)
+        })
+        .await
+        .unwrap();
+    // the taken bib is skipped and the men's range is exhausted by the
+    // third man
+    assert_eq!(
+        bibs,
+        [
+            (String::from("Anna"), Some(800)),
+            (String::from("Ben"), Some(601)),
+            (String::from("Carl"), Some(602)),
+            (String::from("Dan"), None),
+        ]
+    );
+}